@@ -0,0 +1,14 @@
+//! Excellon drill file parser module.
+//!
+//! Excellon is the format CAD tools use for drill data: a header that
+//! defines the units, coordinate format and tool table, followed by the
+//! hits made with each tool. Plated (PTH) and non-plated (NPTH) holes
+//! usually come in separate files.
+//!
+//! ## Submodules
+//!
+//! - `types`: Defines drill file structures (tools, holes).
+//! - `parse`: Implements the parser for Excellon files.
+
+pub mod parse;
+pub mod types;
@@ -0,0 +1,509 @@
+//! Parser implementation for Excellon drill files.
+//!
+//! Excellon is line based: every line is one command. The header, between
+//! `M48` and `%` (or `M95`), sets the units, zero suppression and coordinate
+//! format and defines the tool table. The body selects tools (`T1`) and
+//! drills hits (`X...Y...`), with coordinates that are modal per axis.
+//!
+//! Slots come either as G85 canned slots (`X...Y...G85X...Y...`) or as
+//! routed paths: `G00` moves the router, `M15` plunges it, `G01`/`G02`/`G03`
+//! cut lines and arcs, and `M16` retracts it. Each cut becomes a slot record.
+//!
+//! X2 attributes are carried in comments (`; #@! TF.FileFunction,Plated,...`),
+//! and KiCad announces the plating of each tool with a `; #@! TA.AperFunction`
+//! comment right before its definition.
+
+use std::collections::HashMap;
+
+use crate::excellon::types::{DrillFile, Hole, Plating};
+use crate::gerber::plot::{MM_PER_INCH, arc_points};
+use crate::gerber::types::{Attributes, Diagnostic, DiagnosticKind, InterpolationMode, Point};
+
+/// Header lines that carry no information needed to build the model.
+const IGNORED_HEADER_LINES: [&str; 5] = ["FMAT,2", "VER,1", "DETECT,ON", "ATC,ON", "OM48"];
+
+/// Which zeros are written in coordinates without a decimal point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Zeros {
+    /// `LZ`: leading zeros are kept, trailing zeros are suppressed
+    Leading,
+    /// `TZ`: trailing zeros are kept, leading zeros are suppressed
+    Trailing,
+}
+
+/// Parser state carried from line to line.
+struct State {
+    in_header: bool,
+    metric: bool,
+    zeros: Zeros,
+    /// Integer and decimal digits, `None` for the default of the units
+    format: Option<(u8, u8)>,
+    incremental: bool,
+    /// Route mode (`G00`) instead of drill mode (`G05`)
+    routing: bool,
+    /// Router plunged with `M15`
+    tool_down: bool,
+    interpolation: InterpolationMode,
+    tool: Option<u32>,
+    position: Point,
+    /// Plating announced by a `TA.AperFunction` comment for the next tool
+    pending_plating: Option<Plating>,
+    tool_plating: HashMap<u32, Plating>,
+    /// Maximum chord deviation of routed arcs in mm
+    arc_tolerance: f64,
+}
+
+impl State {
+    fn scale(&self) -> f64 {
+        if self.metric { 1.0 } else { MM_PER_INCH }
+    }
+
+    fn digits(&self) -> (u8, u8) {
+        self.format
+            .unwrap_or(if self.metric { (3, 3) } else { (2, 4) })
+    }
+}
+
+/// Parse an Excellon drill file.
+///
+/// Holes are converted to millimeters. Their plating comes from the tool's
+/// `TA.AperFunction` comment, then the file's `TF.FileFunction`, and falls
+/// back to `default_plating` (typically guessed from the file name).
+///
+/// # Arguments
+///
+/// * `content` - String content of the drill file
+/// * `default_plating` - Plating of holes the file does not describe
+/// * `strict` - Fail on any line that cannot be parsed instead of skipping it
+/// * `arc_tolerance` - Maximum chord deviation of routed arcs in mm
+///
+/// # Returns
+///
+/// * `Result<DrillFile, String>` - The parsed drill file or an error message
+pub fn parse_excellon(
+    content: &str,
+    default_plating: Plating,
+    strict: bool,
+    arc_tolerance: f64,
+) -> Result<DrillFile, String> {
+    let mut state = State {
+        in_header: false,
+        metric: false,
+        zeros: Zeros::Trailing,
+        format: None,
+        incremental: false,
+        routing: false,
+        tool_down: false,
+        interpolation: InterpolationMode::Linear,
+        tool: None,
+        position: Point { x: 0.0, y: 0.0 },
+        pending_plating: None,
+        tool_plating: HashMap::new(),
+        arc_tolerance,
+    };
+    let mut tools = HashMap::new();
+    let mut holes = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut file_attributes = Attributes::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut skip = |kind: DiagnosticKind, reason: &str| {
+            diagnostics.push(Diagnostic {
+                line: index + 1,
+                column: 1,
+                text: line.to_string(),
+                kind,
+                reason: reason.to_string(),
+            });
+        };
+
+        if let Some(comment) = line.strip_prefix(';') {
+            parse_comment(comment, &mut state, &mut file_attributes);
+            continue;
+        }
+
+        match line {
+            "M48" => state.in_header = true,
+            "%" | "M95" => state.in_header = false,
+            "M30" | "M00" => break,
+            "G90" => state.incremental = false,
+            "G91" => state.incremental = true,
+            "G05" | "G81" => {
+                state.routing = false;
+                state.tool_down = false;
+            }
+            "M15" => state.tool_down = true,
+            "M16" | "M17" => state.tool_down = false,
+            "M71" => state.metric = true,
+            "M72" => state.metric = false,
+            "T0" | "T00" => state.tool = None,
+            _ if state.in_header && IGNORED_HEADER_LINES.contains(&line) => {}
+            _ if line.starts_with("INCH") || line.starts_with("METRIC") => {
+                parse_units(line, &mut state);
+            }
+            _ if line.starts_with("ICI") => state.incremental = line.ends_with("ON"),
+            _ if line.starts_with('T') => match parse_tool(line) {
+                Some((number, diameter)) => {
+                    if let Some(diameter) = diameter {
+                        tools.insert(number, diameter * state.scale());
+                        if let Some(plating) = state.pending_plating.take() {
+                            state.tool_plating.insert(number, plating);
+                        }
+                    }
+                    // Tool definitions only select the tool in the body
+                    if !state.in_header {
+                        if tools.contains_key(&number) {
+                            state.tool = Some(number);
+                        } else {
+                            skip(
+                                DiagnosticKind::Malformed,
+                                &format!("tool T{} is not defined", number),
+                            );
+                        }
+                    }
+                }
+                None => skip(DiagnosticKind::Malformed, "invalid tool"),
+            },
+            _ if !state.in_header && is_motion(line) => {
+                let cuts = match parse_motion(line, &mut state) {
+                    Ok(cuts) => cuts,
+                    Err((kind, reason)) => {
+                        skip(kind, reason);
+                        continue;
+                    }
+                };
+                if cuts.is_empty() {
+                    continue;
+                }
+
+                let Some(tool) = state.tool else {
+                    skip(DiagnosticKind::Malformed, "hit without a selected tool");
+                    continue;
+                };
+                let plating = state
+                    .tool_plating
+                    .get(&tool)
+                    .copied()
+                    .or_else(|| file_plating(&file_attributes))
+                    .unwrap_or(default_plating);
+                for (position, end) in cuts {
+                    holes.push(Hole {
+                        tool,
+                        position,
+                        end,
+                        diameter: tools[&tool],
+                        plating,
+                    });
+                }
+            }
+            _ => skip(DiagnosticKind::Unsupported, "unsupported drill command"),
+        }
+    }
+
+    if strict && !diagnostics.is_empty() {
+        let details: Vec<String> = diagnostics.iter().map(|d| format!("  {}", d)).collect();
+        return Err(format!(
+            "{} line(s) could not be parsed in strict mode:\n{}",
+            diagnostics.len(),
+            details.join("\n")
+        ));
+    }
+
+    Ok(DrillFile {
+        tools,
+        holes,
+        diagnostics,
+        file_attributes,
+    })
+}
+
+/// Handles a header comment: X2 attributes and coordinate format hints.
+///
+/// - `#@! TF.FileFunction,Plated,1,2,PTH` (file attribute)
+/// - `#@! TA.AperFunction,NonPlated,NPTH,ComponentDrill` (plating of the next tool)
+/// - `FILE_FORMAT=4:4` or `FORMAT={3:3/ absolute / metric / suppress trailing zeros}`
+fn parse_comment(comment: &str, state: &mut State, file_attributes: &mut Attributes) {
+    let comment = comment.trim();
+
+    if let Some(attribute) = comment.strip_prefix("#@!") {
+        let attribute = attribute.trim();
+        let (kind, body) = match (attribute.get(..2), attribute.get(2..)) {
+            (Some(kind), Some(body)) => (kind, body),
+            _ => return,
+        };
+        let mut fields = body.split(',').map(|field| field.trim().to_string());
+        let name = fields.next().unwrap_or_default();
+        let values: Vec<String> = fields.collect();
+
+        match kind {
+            "TF" => {
+                file_attributes.insert(name, values);
+            }
+            "TA" if name == ".AperFunction" => {
+                state.pending_plating = plating_of(&values);
+            }
+            _ => {}
+        }
+        return;
+    }
+
+    let format = comment
+        .strip_prefix("FILE_FORMAT=")
+        .or_else(|| comment.strip_prefix("FORMAT={"));
+    if let Some(format) = format {
+        let mut digits = format
+            .split(['/', '}'])
+            .next()
+            .unwrap_or_default()
+            .split(':')
+            .map(|d| d.trim().parse::<u8>());
+        if let (Some(Ok(integer)), Some(Ok(decimal))) = (digits.next(), digits.next()) {
+            state.format = Some((integer, decimal));
+        }
+    }
+}
+
+/// Plating from the values of a `.FileFunction` or `.AperFunction` attribute.
+fn plating_of(values: &[String]) -> Option<Plating> {
+    match values.first().map(|v| v.as_str()) {
+        Some("Plated") => Some(Plating::Plated),
+        Some("NonPlated") => Some(Plating::NonPlated),
+        _ => None,
+    }
+}
+
+/// Plating declared for the whole file by `.FileFunction`.
+fn file_plating(file_attributes: &Attributes) -> Option<Plating> {
+    file_attributes
+        .get(".FileFunction")
+        .and_then(|values| plating_of(values))
+}
+
+/// Parse a units line: `INCH`, `METRIC`, optionally followed by `LZ`/`TZ`
+/// and an explicit format such as `000.000`.
+fn parse_units(line: &str, state: &mut State) {
+    let mut fields = line.split(',');
+    state.metric = fields.next() == Some("METRIC");
+    state.format = None;
+
+    for field in fields {
+        match field {
+            "LZ" => state.zeros = Zeros::Leading,
+            "TZ" => state.zeros = Zeros::Trailing,
+            _ => {
+                if let Some((integer, decimal)) = field.split_once('.')
+                    && integer.chars().all(|c| c == '0')
+                    && decimal.chars().all(|c| c == '0')
+                {
+                    state.format = Some((integer.len() as u8, decimal.len() as u8));
+                }
+            }
+        }
+    }
+}
+
+/// Parse a tool line such as `T1C0.0300` or `T01F200S65C0.8`.
+///
+/// Returns the tool number and, for definitions, the diameter in file units.
+fn parse_tool(line: &str) -> Option<(u32, Option<f64>)> {
+    let body = line.strip_prefix('T')?;
+    let digits_end = body
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(body.len());
+    let number = body[..digits_end].parse().ok()?;
+
+    let params = &body[digits_end..];
+    let diameter = match params.find('C') {
+        Some(start) => {
+            let value = &params[start + 1..];
+            let end = value
+                .find(|c: char| c.is_ascii_alphabetic())
+                .unwrap_or(value.len());
+            Some(value[..end].parse().ok()?)
+        }
+        None => None,
+    };
+
+    Some((number, diameter))
+}
+
+/// Returns `true` for lines that move the tool: hits, G85 slots and routing.
+fn is_motion(line: &str) -> bool {
+    line.starts_with('X')
+        || line.starts_with('Y')
+        || ["G00", "G01", "G02", "G03"]
+            .iter()
+            .any(|code| line.starts_with(code))
+        || line.contains("G85")
+}
+
+/// A cut made by a motion line: a hole center, or a slot start and end.
+type Cut = (Point, Option<Point>);
+
+/// Parse a motion line and return the cuts it makes, in mm.
+///
+/// - `X1.5Y-2.25`: a hit in drill mode, a cut or move in route mode
+/// - `X1.0Y1.0G85X2.0Y1.0`: a canned slot
+/// - `G00X...Y...`: enter route mode and move with the router up
+/// - `G01X...Y...`: route a straight line
+/// - `G02X...Y...I...J...` / `G03...A...`: route an arc, by center offset or radius
+///
+/// Arcs are split into straight slots along [`arc_points`].
+fn parse_motion(line: &str, state: &mut State) -> Result<Vec<Cut>, (DiagnosticKind, &'static str)> {
+    let malformed = || (DiagnosticKind::Malformed, "invalid coordinates");
+
+    // G85 canned slot from the first to the second position
+    if let Some((start, end)) = line.split_once("G85") {
+        let start = parse_position(&parse_words(start, state).ok_or_else(malformed)?, state);
+        state.position = start.clone();
+        let end = parse_position(&parse_words(end, state).ok_or_else(malformed)?, state);
+        state.position = end.clone();
+        return Ok(vec![(start, Some(end))]);
+    }
+
+    let mut rest = line;
+    let mut rapid = false;
+    if let Some(code) = line.get(..3).filter(|code| code.starts_with('G')) {
+        match code {
+            "G00" => {
+                state.routing = true;
+                state.tool_down = false;
+                rapid = true;
+            }
+            "G01" => state.interpolation = InterpolationMode::Linear,
+            "G02" => state.interpolation = InterpolationMode::ClockwiseCircular,
+            "G03" => state.interpolation = InterpolationMode::CounterClockwiseCircular,
+            _ => return Err((DiagnosticKind::Unsupported, "unsupported drill command")),
+        }
+        rest = &line[3..];
+    }
+    if rest.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let words = parse_words(rest, state).ok_or_else(malformed)?;
+    let target = parse_position(&words, state);
+    let from = std::mem::replace(&mut state.position, target.clone());
+
+    if !state.routing {
+        return Ok(vec![(target, None)]);
+    }
+    if rapid || !state.tool_down {
+        return Ok(Vec::new());
+    }
+
+    let clockwise = match state.interpolation {
+        InterpolationMode::Linear => return Ok(vec![(from, Some(target))]),
+        InterpolationMode::ClockwiseCircular => true,
+        InterpolationMode::CounterClockwiseCircular => false,
+    };
+    let word = |letter: char| words.iter().find(|(l, _)| *l == letter).map(|(_, v)| *v);
+    let center = match (word('I'), word('J'), word('A')) {
+        (_, _, Some(radius)) => arc_center(&from, &target, radius, clockwise),
+        (None, None, None) => return Err((DiagnosticKind::Malformed, "arc without a center")),
+        (i, j, None) => Point {
+            x: from.x + i.unwrap_or(0.0),
+            y: from.y + j.unwrap_or(0.0),
+        },
+    };
+
+    let mut cuts = Vec::new();
+    let mut start = from;
+    for point in arc_points(&start.clone(), &center, &target, clockwise, state.arc_tolerance) {
+        cuts.push((std::mem::replace(&mut start, point.clone()), Some(point)));
+    }
+    Ok(cuts)
+}
+
+/// Center of the arc of `radius` from `from` to `to` spanning at most 180°.
+fn arc_center(from: &Point, to: &Point, radius: f64, clockwise: bool) -> Point {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let chord = dx.hypot(dy);
+    let mid = Point {
+        x: (from.x + to.x) / 2.0,
+        y: (from.y + to.y) / 2.0,
+    };
+    if chord == 0.0 {
+        return mid;
+    }
+
+    // A counter-clockwise minor arc has its center left of the chord
+    let offset = (radius * radius - chord * chord / 4.0).max(0.0).sqrt();
+    let side = if clockwise { -1.0 } else { 1.0 };
+    Point {
+        x: mid.x - dy / chord * offset * side,
+        y: mid.y + dx / chord * offset * side,
+    }
+}
+
+/// Split coordinates such as `X1.5Y-2.25` or `X015I01` into letters and
+/// values in mm.
+fn parse_words(text: &str, state: &State) -> Option<Vec<(char, f64)>> {
+    let mut words = Vec::new();
+    let mut rest = text;
+
+    while let Some(letter) = rest.chars().next() {
+        if !matches!(letter, 'X' | 'Y' | 'I' | 'J' | 'A') {
+            return None;
+        }
+        let value = &rest[1..];
+        let end = value
+            .find(|c: char| c.is_ascii_alphabetic())
+            .unwrap_or(value.len());
+        words.push((letter, parse_coordinate(&value[..end], state)?));
+        rest = &value[end..];
+    }
+
+    Some(words)
+}
+
+/// The absolute position reached by X/Y words; a missing axis keeps its
+/// previous value.
+fn parse_position(words: &[(char, f64)], state: &State) -> Point {
+    let word = |letter: char| words.iter().find(|(l, _)| *l == letter).map(|(_, v)| *v);
+    let (dx, dy) = if state.incremental {
+        (state.position.x, state.position.y)
+    } else {
+        (0.0, 0.0)
+    };
+    Point {
+        x: word('X').map(|x| x + dx).unwrap_or(state.position.x),
+        y: word('Y').map(|y| y + dy).unwrap_or(state.position.y),
+    }
+}
+
+/// Convert a coordinate to mm, applying zero suppression when it has no
+/// decimal point.
+fn parse_coordinate(text: &str, state: &State) -> Option<f64> {
+    if text.is_empty() {
+        return None;
+    }
+    if text.contains('.') {
+        return text.parse::<f64>().ok().map(|v| v * state.scale());
+    }
+
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (integer, decimal) = state.digits();
+    let total = (integer + decimal) as usize;
+    let padded = match state.zeros {
+        Zeros::Leading => format!("{:0<width$}", digits, width = total),
+        Zeros::Trailing => format!("{:0>width$}", digits, width = total),
+    };
+    let split = padded.len() - decimal as usize;
+    let value: f64 = format!("{}.{}", &padded[..split], &padded[split..])
+        .parse()
+        .ok()?;
+
+    Some(if negative { -value } else { value } * state.scale())
+}
@@ -0,0 +1,63 @@
+//! Data structures for Excellon drill file representation.
+
+use std::collections::HashMap;
+
+use crate::gerber::types::{Attributes, Diagnostic, Point};
+
+/// Whether a hole has copper plating on its wall.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Plating {
+    /// Plated through hole (vias, component leads)
+    Plated,
+    /// Non-plated hole (mounting holes, tooling holes)
+    NonPlated,
+}
+
+/// A drilled hole or a slot.
+///
+/// A slot is the area swept by the tool from `position` to `end`, a
+/// stadium shape. Coordinates and diameter are in millimeters regardless of
+/// the units of the drill file.
+#[derive(Debug, Clone)]
+pub struct Hole {
+    /// Tool number that drilled the hole
+    pub tool: u32,
+    /// Center of the hole, or start of the slot
+    pub position: Point,
+    /// End of the slot, `None` for a round hole
+    pub end: Option<Point>,
+    /// Hole diameter, or slot width
+    pub diameter: f64,
+    /// Whether the hole wall is plated
+    pub plating: Plating,
+}
+
+impl Hole {
+    /// Returns `true` for slots (G85 or routed), `false` for round holes.
+    pub fn is_slot(&self) -> bool {
+        self.end.is_some()
+    }
+}
+
+/// Complete drill file representation.
+#[derive(Debug)]
+pub struct DrillFile {
+    /// Tool diameters in millimeters by tool number
+    pub tools: HashMap<u32, f64>,
+    /// Every hit and slot in file order
+    pub holes: Vec<Hole>,
+    /// Lines that were skipped, in file order
+    pub diagnostics: Vec<Diagnostic>,
+    /// X2 file attributes found in `; #@! TF...` header comments
+    pub file_attributes: Attributes,
+}
+
+impl DrillFile {
+    /// Number of plated holes.
+    pub fn plated_count(&self) -> usize {
+        self.holes
+            .iter()
+            .filter(|hole| hole.plating == Plating::Plated)
+            .count()
+    }
+}
@@ -0,0 +1,529 @@
+//! Boolean operations on sets of polygons.
+//!
+//! The operations work on an overlay of the edges of both operands:
+//!
+//! 1. Coordinates are snapped to a 1 nm grid so that every predicate is
+//!    evaluated exactly in integer arithmetic
+//! 2. Edges are split wherever they cross or touch another edge, and
+//!    coincident edges are merged, adding up the winding each operand
+//!    contributes to them
+//! 3. Every edge gets the winding numbers of both operands on each of its
+//!    sides (nonzero fill rule); the edges with the result inside on exactly
+//!    one side form the result boundary
+//! 4. The boundary edges are linked into contours, always taking the
+//!    sharpest left turn so that polygons touching at a vertex come out
+//!    separate, and each hole is assigned to the outer contour around it
+//!
+//! Touching and collinear edges need no special handling this way: they
+//! end up as one shared edge whose windings cancel or add up. Each operand
+//! may overlap itself; overlapping parts are treated as one area. Should the
+//! edges still cross after [`MAX_SPLIT_PASSES`] splitting passes, the
+//! operation fails rather than classify an inconsistent overlay; so does a
+//! vertex that is not finite, before anything is snapped.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::geometry::polygon::{Polygon, contains_point, signed_area};
+use crate::gerber::types::Point;
+
+/// Grid units per millimeter: coordinates are snapped to 1 nm.
+const GRID: f64 = 1e6;
+
+/// Largest coordinate magnitude in mm (1 km), which keeps the differences
+/// and products of grid coordinates far from overflowing.
+const MAX_COORDINATE: f64 = 1e6;
+
+/// Largest number of edge splitting passes. Snapping crossing points to the
+/// grid moves edges slightly, which can create new crossings for the next
+/// pass to resolve; the passes stop as soon as no edges cross.
+const MAX_SPLIT_PASSES: usize = 64;
+
+/// A boolean operation between two polygon sets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BooleanOp {
+    /// Area covered by either operand
+    Union,
+    /// Area of the subject not covered by the clip
+    Difference,
+    /// Area covered by both operands
+    Intersection,
+    /// Area covered by exactly one operand
+    Xor,
+}
+
+/// A point on the integer grid.
+type GridPoint = (i64, i64);
+
+/// A directed edge of one operand (0 for the subject, 1 for the clip).
+type Segment = (GridPoint, GridPoint, usize);
+
+/// An edge of the overlay, stored from its lexicographically lower endpoint
+/// to the higher one, with the winding each operand contributes in that
+/// direction.
+struct OverlayEdge {
+    from: GridPoint,
+    to: GridPoint,
+    winding: [i32; 2],
+}
+
+/// Union of two polygon sets.
+pub fn union(subject: &[Polygon], clip: &[Polygon]) -> Result<Vec<Polygon>, String> {
+    boolean(subject, clip, BooleanOp::Union)
+}
+
+/// The subject polygons minus the clip polygons.
+pub fn difference(subject: &[Polygon], clip: &[Polygon]) -> Result<Vec<Polygon>, String> {
+    boolean(subject, clip, BooleanOp::Difference)
+}
+
+/// Intersection of two polygon sets.
+pub fn intersection(subject: &[Polygon], clip: &[Polygon]) -> Result<Vec<Polygon>, String> {
+    boolean(subject, clip, BooleanOp::Intersection)
+}
+
+/// Area covered by exactly one of two polygon sets.
+pub fn xor(subject: &[Polygon], clip: &[Polygon]) -> Result<Vec<Polygon>, String> {
+    boolean(subject, clip, BooleanOp::Xor)
+}
+
+/// Merges a set of possibly overlapping polygons into disjoint ones.
+pub fn union_all(polygons: &[Polygon]) -> Result<Vec<Polygon>, String> {
+    boolean(polygons, &[], BooleanOp::Union)
+}
+
+/// Applies a boolean operation to two polygon sets.
+///
+/// # Arguments
+///
+/// * `subject` - The first operand
+/// * `clip` - The second operand
+/// * `op` - The operation
+///
+/// # Returns
+///
+/// * `Result<Vec<Polygon>, String>` - Disjoint polygons covering the result,
+///   or an error message when a vertex is not finite or the edges cannot be
+///   split into a consistent overlay
+pub fn boolean(
+    subject: &[Polygon],
+    clip: &[Polygon],
+    op: BooleanOp,
+) -> Result<Vec<Polygon>, String> {
+    let mut segments: Vec<Segment> = Vec::new();
+    for (operand, polygons) in [subject, clip].into_iter().enumerate() {
+        for contour in polygons.iter().flat_map(Polygon::contours) {
+            let points = contour.iter().map(snap).collect::<Result<Vec<_>, _>>()?;
+            for (i, &from) in points.iter().enumerate() {
+                let to = points[(i + 1) % points.len()];
+                if from != to {
+                    segments.push((from, to, operand));
+                }
+            }
+        }
+    }
+
+    let inside = |winding: [i32; 2]| {
+        let (a, b) = (winding[0] != 0, winding[1] != 0);
+        match op {
+            BooleanOp::Union => a || b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Xor => a != b,
+        }
+    };
+
+    let edges = overlay(segments)?;
+    let boundary: Vec<(GridPoint, GridPoint)> = classify(&edges)
+        .into_iter()
+        .zip(&edges)
+        .filter_map(
+            |((left, right), edge)| match (inside(left), inside(right)) {
+                (true, false) => Some((edge.from, edge.to)),
+                (false, true) => Some((edge.to, edge.from)),
+                _ => None,
+            },
+        )
+        .collect();
+
+    Ok(assemble(link(&boundary)?))
+}
+
+/// Snaps a point to the grid, failing on coordinates that are not finite or
+/// beyond [`MAX_COORDINATE`].
+fn snap(point: &Point) -> Result<GridPoint, String> {
+    if !(point.x.abs() <= MAX_COORDINATE && point.y.abs() <= MAX_COORDINATE) {
+        return Err(format!(
+            "Polygon vertex ({}, {}) is not finite or out of range",
+            point.x, point.y
+        ));
+    }
+    Ok((
+        (point.x * GRID).round() as i64,
+        (point.y * GRID).round() as i64,
+    ))
+}
+
+/// Twice the signed area of the triangle (o, a, b); positive when `b` is
+/// to the left of the line from `o` to `a`.
+fn orient(o: GridPoint, a: GridPoint, b: GridPoint) -> i128 {
+    (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+}
+
+/// Splits the segments at every crossing and touching point, then merges
+/// coincident edges.
+///
+/// Splitting is repeated until no segments cross or touch, and fails after
+/// [`MAX_SPLIT_PASSES`] passes.
+fn overlay(mut segments: Vec<Segment>) -> Result<Vec<OverlayEdge>, String> {
+    let mut passes = 0;
+    loop {
+        let splits = find_splits(&segments);
+        if splits.iter().all(Vec::is_empty) {
+            break;
+        }
+        if passes == MAX_SPLIT_PASSES {
+            return Err(format!(
+                "Polygon edges still cross after {} splitting passes",
+                MAX_SPLIT_PASSES
+            ));
+        }
+        passes += 1;
+
+        let mut split_segments = Vec::with_capacity(segments.len() + splits.len());
+        for ((from, to, operand), mut points) in segments.into_iter().zip(splits) {
+            // Order the split points along the segment
+            let (dx, dy) = ((to.0 - from.0) as i128, (to.1 - from.1) as i128);
+            points.sort_by_key(|p| (p.0 - from.0) as i128 * dx + (p.1 - from.1) as i128 * dy);
+            points.dedup();
+
+            let mut start = from;
+            for point in points.into_iter().chain(std::iter::once(to)) {
+                if point != start {
+                    split_segments.push((start, point, operand));
+                    start = point;
+                }
+            }
+        }
+        segments = split_segments;
+    }
+
+    // Merge coincident edges, whatever their direction
+    let mut merged: HashMap<(GridPoint, GridPoint), [i32; 2]> = HashMap::new();
+    for (from, to, operand) in segments {
+        let (key, sign) = if from < to {
+            ((from, to), 1)
+        } else {
+            ((to, from), -1)
+        };
+        merged.entry(key).or_insert([0, 0])[operand] += sign;
+    }
+
+    let mut edges: Vec<OverlayEdge> = merged
+        .into_iter()
+        .filter(|(_, winding)| *winding != [0, 0])
+        .map(|((from, to), winding)| OverlayEdge { from, to, winding })
+        .collect();
+    edges.sort_unstable_by_key(|edge| (edge.from, edge.to));
+    Ok(edges)
+}
+
+/// Finds the points at which each segment has to be split: where another
+/// segment crosses it, or ends on its interior.
+fn find_splits(segments: &[Segment]) -> Vec<Vec<GridPoint>> {
+    let mut splits = vec![Vec::new(); segments.len()];
+
+    // Sweep along X so that only segments with overlapping X ranges meet
+    let x_range = |i: usize| {
+        let (a, b, _) = segments[i];
+        (a.0.min(b.0), a.0.max(b.0))
+    };
+    let y_range = |i: usize| {
+        let (a, b, _) = segments[i];
+        (a.1.min(b.1), a.1.max(b.1))
+    };
+    let mut order: Vec<usize> = (0..segments.len()).collect();
+    order.sort_unstable_by_key(|&i| x_range(i).0);
+
+    for (k, &i) in order.iter().enumerate() {
+        let x_end = x_range(i).1;
+        let (y_start, y_end) = y_range(i);
+        for &j in &order[k + 1..] {
+            if x_range(j).0 > x_end {
+                break;
+            }
+            let (y0, y1) = y_range(j);
+            if y0 > y_end || y1 < y_start {
+                continue;
+            }
+            split_pair(segments, i, j, &mut splits);
+        }
+    }
+
+    splits
+}
+
+/// Records the split points of two segments against each other.
+fn split_pair(segments: &[Segment], i: usize, j: usize, splits: &mut [Vec<GridPoint>]) {
+    let (a1, b1, _) = segments[i];
+    let (a2, b2, _) = segments[j];
+    let (d1, d2) = (orient(a1, b1, a2), orient(a1, b1, b2));
+    let (d3, d4) = (orient(a2, b2, a1), orient(a2, b2, b1));
+
+    // Proper crossing: both segments are split at the rounded crossing point
+    if d1.signum() * d2.signum() < 0 && d3.signum() * d4.signum() < 0 {
+        let t = d3 as f64 / (d3 - d4) as f64;
+        let point = (
+            (a1.0 as f64 + t * (b1.0 - a1.0) as f64).round() as i64,
+            (a1.1 as f64 + t * (b1.1 - a1.1) as f64).round() as i64,
+        );
+        if point != a1 && point != b1 {
+            splits[i].push(point);
+        }
+        if point != a2 && point != b2 {
+            splits[j].push(point);
+        }
+        return;
+    }
+
+    // An endpoint on the interior of the other segment (T-junctions and
+    // collinear overlaps)
+    let interior = |p: GridPoint, a: GridPoint, b: GridPoint| {
+        p != a
+            && p != b
+            && p.0 >= a.0.min(b.0)
+            && p.0 <= a.0.max(b.0)
+            && p.1 >= a.1.min(b.1)
+            && p.1 <= a.1.max(b.1)
+    };
+    if d1 == 0 && interior(a2, a1, b1) {
+        splits[i].push(a2);
+    }
+    if d2 == 0 && interior(b2, a1, b1) {
+        splits[i].push(b2);
+    }
+    if d3 == 0 && interior(a1, a2, b2) {
+        splits[j].push(a1);
+    }
+    if d4 == 0 && interior(b1, a2, b2) {
+        splits[j].push(b1);
+    }
+}
+
+/// Winding numbers of both operands on the left and the right of every edge.
+///
+/// The winding next to an edge is found by casting a ray towards +X from
+/// its midpoint and adding up the windings of the edges it crosses. Rays
+/// are half-open in Y (an edge counts when `min_y <= y < max_y`), which
+/// places them just above the midpoint; edges are bucketed into horizontal
+/// strips so that each ray only visits nearby edges.
+fn classify(edges: &[OverlayEdge]) -> Vec<([i32; 2], [i32; 2])> {
+    if edges.is_empty() {
+        return Vec::new();
+    }
+
+    // Work in doubled coordinates so that midpoints stay on the grid
+    let double = |p: GridPoint| (p.0 * 2, p.1 * 2);
+    let y_min = edges
+        .iter()
+        .map(|e| e.from.1.min(e.to.1))
+        .min()
+        .unwrap_or(0)
+        * 2;
+    let y_max = edges
+        .iter()
+        .map(|e| e.from.1.max(e.to.1))
+        .max()
+        .unwrap_or(0)
+        * 2;
+    let strip_count = (edges.len() / 16).clamp(1, 65536);
+    let strip_height = (y_max - y_min) / strip_count as i64 + 1;
+    let strip_of = |y: i64| (((y - y_min) / strip_height) as usize).min(strip_count - 1);
+
+    let mut strips: Vec<Vec<usize>> = vec![Vec::new(); strip_count];
+    for (i, edge) in edges.iter().enumerate() {
+        let (y0, y1) = (
+            edge.from.1.min(edge.to.1) * 2,
+            edge.from.1.max(edge.to.1) * 2,
+        );
+        for strip in &mut strips[strip_of(y0)..=strip_of(y1)] {
+            strip.push(i);
+        }
+    }
+    // Rightmost edges first, so that a ray can stop at the first edge
+    // entirely on its left (edges run towards +X, `to` is the right end)
+    for strip in &mut strips {
+        strip.sort_unstable_by_key(|&j| std::cmp::Reverse(edges[j].to.0));
+    }
+
+    edges
+        .iter()
+        .enumerate()
+        .map(|(i, edge)| {
+            let (from, to) = (double(edge.from), double(edge.to));
+            let mid = ((from.0 + to.0) / 2, (from.1 + to.1) / 2);
+
+            let mut winding = [0, 0];
+            for &j in &strips[strip_of(mid.1)] {
+                let other = &edges[j];
+                let (p, q) = (double(other.from), double(other.to));
+                if q.0 < mid.0 {
+                    break;
+                }
+                if j == i || (p.1 > mid.1) == (q.1 > mid.1) {
+                    continue;
+                }
+                let (bottom, top, sign) = if p.1 < q.1 { (p, q, 1) } else { (q, p, -1) };
+                if orient(bottom, top, mid) > 0 {
+                    winding[0] += sign * other.winding[0];
+                    winding[1] += sign * other.winding[1];
+                }
+            }
+
+            // The ray measures the side facing +X, or the upper side of a
+            // horizontal edge; the edge's own winding gives the other side
+            let plus = |w: [i32; 2]| [w[0] + edge.winding[0], w[1] + edge.winding[1]];
+            let minus = |w: [i32; 2]| [w[0] - edge.winding[0], w[1] - edge.winding[1]];
+            if edge.to.1 > edge.from.1 {
+                (plus(winding), winding)
+            } else {
+                (winding, minus(winding))
+            }
+        })
+        .collect()
+}
+
+/// Links directed boundary edges into closed contours.
+///
+/// At a vertex with several outgoing edges, the contour takes the one
+/// reached first turning clockwise from the way it came in, which is the
+/// sharpest left turn. Fails when a contour reaches a vertex no edge leaves,
+/// which a consistent overlay never has.
+fn link(edges: &[(GridPoint, GridPoint)]) -> Result<Vec<Vec<GridPoint>>, String> {
+    let angle =
+        |from: GridPoint, to: GridPoint| ((to.1 - from.1) as f64).atan2((to.0 - from.0) as f64);
+
+    let mut outgoing: HashMap<GridPoint, Vec<usize>> = HashMap::new();
+    for (i, (from, _)) in edges.iter().enumerate() {
+        outgoing.entry(*from).or_default().push(i);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+
+        let mut contour = Vec::new();
+        let mut current = start;
+        loop {
+            used[current] = true;
+            let (from, vertex) = edges[current];
+            contour.push(from);
+
+            let back = angle(vertex, from);
+            let next = outgoing
+                .get(&vertex)
+                .ok_or_else(|| {
+                    format!(
+                        "Boundary vertex ({}, {}) has no outgoing edge",
+                        vertex.0 as f64 / GRID,
+                        vertex.1 as f64 / GRID
+                    )
+                })?
+                .iter()
+                .copied()
+                .filter(|&e| !used[e] || e == start)
+                .min_by(|&a, &b| {
+                    let turn = |e: usize| {
+                        let cw = (back - angle(vertex, edges[e].1)).rem_euclid(2.0 * PI);
+                        if cw == 0.0 { 2.0 * PI } else { cw }
+                    };
+                    turn(a).total_cmp(&turn(b))
+                });
+            match next {
+                Some(next) if next != start => current = next,
+                _ => break,
+            }
+        }
+        contours.push(contour);
+    }
+
+    Ok(contours)
+}
+
+/// Removes collinear vertices and turns the contours into polygons, with
+/// every hole (clockwise contour) inside the smallest outer contour around it.
+fn assemble(contours: Vec<Vec<GridPoint>>) -> Vec<Polygon> {
+    let to_point = |p: &GridPoint| Point {
+        x: p.0 as f64 / GRID,
+        y: p.1 as f64 / GRID,
+    };
+
+    let mut outers: Vec<(f64, Vec<Point>)> = Vec::new();
+    let mut holes: Vec<Vec<Point>> = Vec::new();
+    for mut contour in contours {
+        simplify(&mut contour);
+        if contour.len() < 3 {
+            continue;
+        }
+        let points: Vec<Point> = contour.iter().map(to_point).collect();
+        let area = signed_area(&points);
+        if area > 0.0 {
+            outers.push((area, points));
+        } else if area < 0.0 {
+            holes.push(points);
+        }
+    }
+
+    let mut polygons: Vec<Polygon> = outers
+        .iter()
+        .map(|(_, outer)| Polygon {
+            outer: outer.clone(),
+            holes: Vec::new(),
+        })
+        .collect();
+    for hole in holes {
+        // Probe just off the middle of the first edge, on the material side
+        // (the left of a clockwise hole)
+        let (a, b) = (&hole[0], &hole[1]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = dx.hypot(dy);
+        let probe = Point {
+            x: (a.x + b.x) / 2.0 - dy / length * 0.1 / GRID,
+            y: (a.y + b.y) / 2.0 + dx / length * 0.1 / GRID,
+        };
+        let owner = outers
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, outer))| contains_point(outer, &probe))
+            .min_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b))
+            .map(|(i, _)| i);
+        if let Some(owner) = owner {
+            polygons[owner].holes.push(hole);
+        }
+    }
+
+    polygons
+}
+
+/// Drops repeated vertices and vertices on a straight line (or spike)
+/// between their neighbors.
+fn simplify(contour: &mut Vec<GridPoint>) {
+    let mut changed = true;
+    while changed && contour.len() >= 3 {
+        changed = false;
+        let mut i = 0;
+        while i < contour.len() && contour.len() >= 3 {
+            let n = contour.len();
+            let (prev, point, next) = (contour[(i + n - 1) % n], contour[i], contour[(i + 1) % n]);
+            if point == prev || orient(prev, point, next) == 0 {
+                contour.remove(i);
+                changed = true;
+            } else {
+                i += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Closed contours from loose outline segments.
+//!
+//! Board outlines are drawn as separate strokes that CAD tools write in no
+//! particular order or direction. The strokes are chained end to end into
+//! closed loops, and the loops are nested by containment: a loop inside an
+//! even number of others is a board outline, a loop inside an odd number is
+//! a cutout of the loop directly around it.
+
+use crate::geometry::polygon::{Polygon, contains_point, signed_area};
+use crate::gerber::types::Point;
+
+/// Chains paths into closed loops.
+///
+/// Paths are joined where an end of one lies within `tolerance` of an end
+/// of another, reversing them as needed. A chain whose end comes back
+/// within `tolerance` of its start is closed; chains that run out of paths
+/// to join before that are open.
+///
+/// # Arguments
+///
+/// * `paths` - Polylines with at least two points each
+/// * `tolerance` - Largest gap in mm bridged between path ends
+///
+/// # Returns
+///
+/// * `(Vec<Vec<Point>>, Vec<Vec<Point>>)` - The closed loops (first point not
+///   repeated) and the open chains
+pub fn chain_paths(paths: Vec<Vec<Point>>, tolerance: f64) -> (Vec<Vec<Point>>, Vec<Vec<Point>>) {
+    let near = |a: &Point, b: &Point| (a.x - b.x).hypot(a.y - b.y) <= tolerance;
+
+    let mut unused: Vec<Vec<Point>> = paths.into_iter().filter(|path| path.len() >= 2).collect();
+    let mut closed = Vec::new();
+    let mut open = Vec::new();
+    while let Some(mut chain) = unused.pop() {
+        loop {
+            let (start, end) = (&chain[0], &chain[chain.len() - 1]);
+            if chain.len() > 2 && near(start, end) {
+                chain.pop();
+                closed.push(chain);
+                break;
+            }
+
+            // The closest path end within the tolerance continues the chain
+            let distance = |p: &Point| (p.x - end.x).hypot(p.y - end.y);
+            let next = unused
+                .iter()
+                .enumerate()
+                .flat_map(|(i, path)| {
+                    [
+                        (i, false, distance(&path[0])),
+                        (i, true, distance(&path[path.len() - 1])),
+                    ]
+                })
+                .filter(|(_, _, d)| *d <= tolerance)
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+            let Some((i, reversed, _)) = next else {
+                open.push(chain);
+                break;
+            };
+
+            let mut path = unused.swap_remove(i);
+            if reversed {
+                path.reverse();
+            }
+            // The joining point is shared; keep the chain's copy
+            chain.extend(path.into_iter().skip(1));
+        }
+    }
+
+    (closed, open)
+}
+
+/// Nests closed loops into polygons with holes.
+///
+/// A loop inside an even number of other loops is an outer boundary; a
+/// loop inside an odd number is a hole of the smallest outer boundary
+/// around it.
+///
+/// # Arguments
+///
+/// * `loops` - Closed loops in either winding order, not crossing each other
+///
+/// # Returns
+///
+/// * `Vec<Polygon>` - One polygon per outer boundary
+pub fn nest_loops(loops: Vec<Vec<Point>>) -> Vec<Polygon> {
+    let loops: Vec<Vec<Point>> = loops
+        .into_iter()
+        .filter(|points| points.len() >= 3 && signed_area(points) != 0.0)
+        .collect();
+
+    // Probe each loop at the middle of its first edge, which is clear of
+    // the other loops unless they touch
+    let probes: Vec<Point> = loops
+        .iter()
+        .map(|points| Point {
+            x: (points[0].x + points[1].x) / 2.0,
+            y: (points[0].y + points[1].y) / 2.0,
+        })
+        .collect();
+    let mut outers: Vec<usize> = Vec::new();
+    let mut holes: Vec<(usize, Option<usize>)> = Vec::new();
+    for (i, probe) in probes.iter().enumerate() {
+        let around: Vec<usize> = (0..loops.len())
+            .filter(|&j| j != i && contains_point(&loops[j], probe))
+            .collect();
+        if around.len().is_multiple_of(2) {
+            outers.push(i);
+        } else {
+            // The innermost loop around a hole is its outer boundary
+            let owner = around.into_iter().min_by(|&a, &b| {
+                signed_area(&loops[a])
+                    .abs()
+                    .total_cmp(&signed_area(&loops[b]).abs())
+            });
+            holes.push((i, owner));
+        }
+    }
+
+    outers
+        .iter()
+        .map(|&outer| {
+            let outer_holes = holes
+                .iter()
+                .filter(|(_, owner)| *owner == Some(outer))
+                .map(|(hole, _)| loops[*hole].clone())
+                .collect();
+            Polygon::with_holes(loops[outer].clone(), outer_holes)
+        })
+        .collect()
+}
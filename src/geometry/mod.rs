@@ -0,0 +1,20 @@
+//! 2D geometry shared by the layer builders.
+//!
+//! Layers are composed from many overlapping shapes: traces and pads are
+//! unioned, clear-polarity objects and mask openings are subtracted, and
+//! everything is clipped to the board outline. This module provides the
+//! polygon type and the operations for that:
+//!
+//! - `polygon.rs`: [`Polygon`](polygon::Polygon), an outer boundary with holes
+//! - `boolean.rs`: Union, difference, intersection and XOR of polygon sets
+//! - `offset.rs`: Growing and shrinking polygons by a distance
+//! - `shapes.rs`: Regular polygons and stadiums
+//! - `contour.rs`: Chaining loose segments into closed, nested contours
+//! - `triangulate.rs`: Splitting polygon caps into triangles
+
+pub mod boolean;
+pub mod contour;
+pub mod offset;
+pub mod polygon;
+pub mod shapes;
+pub mod triangulate;
@@ -0,0 +1,48 @@
+//! Growing and shrinking polygons by a distance.
+//!
+//! The offset is the Minkowski sum (or difference) of the polygons with a
+//! disc: every boundary edge is swept into a stadium of the offset radius,
+//! and the stadiums are added to or subtracted from the polygons. Corners
+//! come out rounded, as with a round aperture tracing the outline.
+
+use crate::geometry::boolean::{difference, union, union_all};
+use crate::geometry::polygon::Polygon;
+use crate::geometry::shapes::{CIRCLE_SEGMENTS, stadium};
+
+/// Offsets polygons by a distance.
+///
+/// # Arguments
+///
+/// * `polygons` - The polygons to offset
+/// * `delta` - Distance in mm; positive grows the polygons, negative shrinks them
+///
+/// # Returns
+///
+/// * `Result<Vec<Polygon>, String>` - Disjoint polygons covering the offset
+///   area, or an error message from the boolean operation
+pub fn offset(polygons: &[Polygon], delta: f64) -> Result<Vec<Polygon>, String> {
+    if delta == 0.0 {
+        return union_all(polygons);
+    }
+
+    let band: Vec<Polygon> = polygons
+        .iter()
+        .flat_map(Polygon::contours)
+        .flat_map(|contour| {
+            (0..contour.len()).map(move |i| {
+                Polygon::new(stadium(
+                    &contour[i],
+                    &contour[(i + 1) % contour.len()],
+                    delta.abs(),
+                    CIRCLE_SEGMENTS,
+                ))
+            })
+        })
+        .collect();
+
+    if delta > 0.0 {
+        union(polygons, &band)
+    } else {
+        difference(polygons, &band)
+    }
+}
@@ -0,0 +1,225 @@
+//! Polygons with holes, and the primitives on plain outlines they are
+//! built on: area, point containment and hole bridging.
+
+use crate::gerber::types::Point;
+
+/// A polygon with holes.
+///
+/// The outer boundary is counter-clockwise and the holes are clockwise,
+/// neither repeating its first point. Holes lie inside the outer boundary
+/// and do not overlap each other.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    /// Outer boundary, counter-clockwise
+    pub outer: Vec<Point>,
+    /// Holes, clockwise
+    pub holes: Vec<Vec<Point>>,
+}
+
+impl Polygon {
+    /// A polygon without holes, from an outline in either winding order.
+    pub fn new(outer: Vec<Point>) -> Polygon {
+        Polygon::with_holes(outer, Vec::new())
+    }
+
+    /// A polygon from an outer boundary and holes in either winding order.
+    pub fn with_holes(mut outer: Vec<Point>, holes: Vec<Vec<Point>>) -> Polygon {
+        if signed_area(&outer) < 0.0 {
+            outer.reverse();
+        }
+        let holes = holes
+            .into_iter()
+            .map(|mut hole| {
+                if signed_area(&hole) > 0.0 {
+                    hole.reverse();
+                }
+                hole
+            })
+            .collect();
+        Polygon { outer, holes }
+    }
+
+    /// Area of the outer boundary minus the holes.
+    pub fn area(&self) -> f64 {
+        signed_area(&self.outer) + self.holes.iter().map(|hole| signed_area(hole)).sum::<f64>()
+    }
+
+    /// Returns `true` if a point lies inside the polygon and outside its holes.
+    pub fn contains(&self, point: &Point) -> bool {
+        contains_point(&self.outer, point)
+            && !self.holes.iter().any(|hole| contains_point(hole, point))
+    }
+
+    /// Every boundary of the polygon: the outer one first, then the holes.
+    pub fn contours(&self) -> impl Iterator<Item = &Vec<Point>> {
+        std::iter::once(&self.outer).chain(&self.holes)
+    }
+
+    /// The polygon as a single outline, with the holes joined to the outer
+    /// boundary by zero-width bridges (see [`bridge_holes`]).
+    pub fn to_outline(&self) -> Vec<Point> {
+        if self.holes.is_empty() {
+            return self.outer.clone();
+        }
+        bridge_holes(&self.outer, &self.holes)
+    }
+}
+
+/// Total area of a set of polygons.
+pub fn total_area(polygons: &[Polygon]) -> f64 {
+    polygons.iter().map(Polygon::area).sum()
+}
+
+/// Signed area of a closed polygon; positive for counter-clockwise winding.
+pub fn signed_area(points: &[Point]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (&points[i], &points[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Returns `true` if a point lies inside a closed polygon (even-odd rule).
+pub fn contains_point(polygon: &[Point], point: &Point) -> bool {
+    let n = polygon.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (&polygon[i], &polygon[(i + n - 1) % n]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Joins holes to an outer outline with zero-width bridges.
+///
+/// The result is a single weakly simple polygon (the bridge edges are
+/// traversed once in each direction) that covers the outline minus the
+/// holes, so that it can be emitted as one cap face. Each hole is connected
+/// from its rightmost vertex to a vertex of the polygon visible along the
+/// +X direction, rightmost holes first, so that no bridge crosses a hole.
+///
+/// # Arguments
+///
+/// * `outer` - Outer outline, counter-clockwise
+/// * `holes` - Holes lying strictly inside the outline and not overlapping
+///
+/// # Returns
+///
+/// * `Vec<Point>` - The bridged polygon, counter-clockwise
+pub fn bridge_holes(outer: &[Point], holes: &[Vec<Point>]) -> Vec<Point> {
+    let max_x = |outline: &Vec<Point>| {
+        (0..outline.len())
+            .max_by(|&a, &b| outline[a].x.total_cmp(&outline[b].x))
+            .unwrap_or(0)
+    };
+
+    // Holes must run clockwise inside the counter-clockwise outline
+    let mut holes: Vec<Vec<Point>> = holes
+        .iter()
+        .filter(|hole| hole.len() >= 3)
+        .map(|hole| {
+            let mut hole = hole.clone();
+            if signed_area(&hole) > 0.0 {
+                hole.reverse();
+            }
+            hole
+        })
+        .collect();
+    holes.sort_by(|a, b| b[max_x(b)].x.total_cmp(&a[max_x(a)].x));
+
+    let mut polygon = outer.to_vec();
+    for hole in holes {
+        let m = max_x(&hole);
+        let Some(p) = visible_vertex(&polygon, &hole[m]) else {
+            continue;
+        };
+
+        let mut merged = Vec::with_capacity(polygon.len() + hole.len() + 2);
+        merged.extend_from_slice(&polygon[..=p]);
+        merged.extend_from_slice(&hole[m..]);
+        merged.extend_from_slice(&hole[..=m]);
+        merged.extend_from_slice(&polygon[p..]);
+        polygon = merged;
+    }
+
+    polygon
+}
+
+/// Finds a polygon vertex that can be connected to `from` without crossing
+/// any edge (Eberly's hole bridging).
+fn visible_vertex(polygon: &[Point], from: &Point) -> Option<usize> {
+    let n = polygon.len();
+    let cross =
+        |o: &Point, a: &Point, b: &Point| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+
+    // Closest edge hit by a ray cast towards +X; with the material on the
+    // left of every edge, the ray leaves it through an upward edge
+    let mut hit: Option<(f64, usize)> = None;
+    for i in 0..n {
+        let (a, b) = (&polygon[i], &polygon[(i + 1) % n]);
+        if a.y > from.y || b.y <= from.y {
+            continue;
+        }
+        let x = a.x + (from.y - a.y) * (b.x - a.x) / (b.y - a.y);
+        if x >= from.x && hit.is_none_or(|(best, _)| x < best) {
+            hit = Some((x, i));
+        }
+    }
+    let (x, edge) = hit?;
+    let intersection = Point { x, y: from.y };
+
+    // A vertex on the ray is reached before the edge behind it; of the
+    // copies of the closest one, take a corner that opens towards the hole
+    let opens = |i: usize| {
+        let (prev, point, next) = (&polygon[(i + n - 1) % n], &polygon[i], &polygon[(i + 1) % n]);
+        let (left_of_next, left_of_prev) = (cross(point, next, from), cross(prev, point, from));
+        if cross(prev, point, next) > 0.0 {
+            left_of_next >= 0.0 && left_of_prev >= 0.0
+        } else {
+            left_of_next >= 0.0 || left_of_prev >= 0.0
+        }
+    };
+    let on_ray = (0..n)
+        .filter(|&i| polygon[i].y == from.y && polygon[i].x > from.x && polygon[i].x <= x)
+        .min_by(|&a, &b| polygon[a].x.total_cmp(&polygon[b].x).then(opens(b).cmp(&opens(a))));
+    if on_ray.is_some() {
+        return on_ray;
+    }
+
+    // Candidate: the edge endpoint furthest along the ray
+    let (a, b) = (edge, (edge + 1) % n);
+    let candidate = if polygon[a].x > polygon[b].x { a } else { b };
+
+    // A reflex vertex inside the triangle (from, intersection, candidate)
+    // would block the view; take the one closest in angle to the ray instead
+    let triangle = [from.clone(), intersection, polygon[candidate].clone()];
+    let area = signed_area(&triangle);
+    let inside = |p: &Point| {
+        let sign = area.signum();
+        (0..3).all(|i| sign * cross(&triangle[i], &triangle[(i + 1) % 3], p) > 0.0)
+    };
+
+    let mut best = candidate;
+    let mut best_key = (f64::INFINITY, f64::INFINITY);
+    for i in 0..n {
+        let (prev, point, next) = (&polygon[(i + n - 1) % n], &polygon[i], &polygon[(i + 1) % n]);
+        if i == candidate || cross(prev, point, next) >= 0.0 || !inside(point) {
+            continue;
+        }
+        let (dx, dy) = (point.x - from.x, point.y - from.y);
+        let key = (dy.abs().atan2(dx), dx.hypot(dy));
+        if key < best_key {
+            best_key = key;
+            best = i;
+        }
+    }
+
+    Some(best)
+}
@@ -0,0 +1,78 @@
+//! Outlines of basic shapes: circles approximated by regular polygons, and
+//! stadiums swept by a circle along a segment.
+
+use std::f64::consts::PI;
+
+use crate::gerber::types::Point;
+
+/// Number of segments used to approximate a full circle when no arc
+/// tolerance applies.
+pub const CIRCLE_SEGMENTS: usize = 32;
+
+/// Builds a regular polygon with `sides` vertices on a circle.
+pub fn regular_polygon(center: &Point, radius: f64, sides: usize, rotation_deg: f64) -> Vec<Point> {
+    let rotation = rotation_deg.to_radians();
+    (0..sides)
+        .map(|i| {
+            let angle = rotation + 2.0 * PI * i as f64 / sides as f64;
+            Point {
+                x: center.x + radius * angle.cos(),
+                y: center.y + radius * angle.sin(),
+            }
+        })
+        .collect()
+}
+
+/// Outline of a stadium: the area swept by a circle of `radius` moving from
+/// `start` to `end`, counter-clockwise. The circle is approximated with
+/// `segments` points, a multiple of 4; the stadium is that circle when both
+/// ends coincide.
+pub fn stadium(start: &Point, end: &Point, radius: f64, segments: usize) -> Vec<Point> {
+    let mut outline = half_stadium(start, end, radius, segments, true);
+    outline.pop();
+    let mut right = half_stadium(start, end, radius, segments, false);
+    right.pop();
+    outline.extend(right);
+    outline
+}
+
+/// One side of a stadium, counter-clockwise from axis to axis.
+///
+/// The left half (seen from `start` towards `end`) runs from the far point
+/// of the end cap to the far point of the start cap; the right half runs
+/// back. Both end points are included.
+pub fn half_stadium(
+    start: &Point,
+    end: &Point,
+    radius: f64,
+    segments: usize,
+    left: bool,
+) -> Vec<Point> {
+    let direction = (end.y - start.y).atan2(end.x - start.x);
+    let quarter = (segments / 4).max(1);
+    let caps = if left {
+        [(end, direction), (start, direction + PI / 2.0)]
+    } else {
+        [(start, direction + PI), (end, direction + 1.5 * PI)]
+    };
+
+    let mut points: Vec<Point> = Vec::with_capacity(2 * quarter + 2);
+    for (center, from) in caps {
+        for i in 0..=quarter {
+            let angle = from + PI / 2.0 * i as f64 / quarter as f64;
+            let point = Point {
+                x: center.x + radius * angle.cos(),
+                y: center.y + radius * angle.sin(),
+            };
+            // The two quarter arcs meet when the ends coincide
+            if let Some(last) = points.last()
+                && (last.x - point.x).abs() < 1e-12
+                && (last.y - point.y).abs() < 1e-12
+            {
+                continue;
+            }
+            points.push(point);
+        }
+    }
+    points
+}
@@ -0,0 +1,257 @@
+//! Triangulation of polygon caps by ear clipping.
+//!
+//! Meshes and exporters need the caps of extruded polygons as triangles.
+//! Polygons with holes are first bridged into a single weakly simple
+//! outline (see [`bridge_holes`](crate::geometry::polygon::bridge_holes)),
+//! then split into triangles.
+//!
+//! Like the boolean operations, the triangulation snaps vertices to a 1 nm
+//! grid so that its orientation tests are exact. Only true ears are cut,
+//! and every vertex of the outline stays a corner of some triangle (unless
+//! the outline is degenerate there), so that the caps meet the walls along
+//! the same edges and the extruded mesh is closed.
+
+use std::collections::HashMap;
+
+use crate::gerber::types::Point;
+
+/// Grid units per millimeter: vertices are snapped to 1 nm.
+const GRID: f64 = 1e6;
+
+/// A vertex on the integer grid.
+type GridPoint = (i64, i64);
+
+/// Splits a simple (or weakly simple, see
+/// [`bridge_holes`](crate::geometry::polygon::bridge_holes)) polygon into
+/// triangles by ear clipping.
+///
+/// Triangles keep the winding of the polygon. A vertex is cut off only
+/// when it is strictly convex and its triangle holds no other part of the
+/// boundary; collinear vertices are kept until a neighbor is cut, so they
+/// end up as corners rather than as T-junctions. Reflex and collinear
+/// vertices, the only ones that can lie inside an ear, are looked up in a
+/// grid. Should no ear be left in a degenerate remainder, it is left out
+/// rather than covered by overlapping triangles.
+///
+/// # Arguments
+///
+/// * `polygon` - Polygon vertices, in either winding order
+///
+/// # Returns
+///
+/// * `Vec<[usize; 3]>` - Triangles as indices into `polygon`
+pub fn triangulate(polygon: &[Point]) -> Vec<[usize; 3]> {
+    let n = polygon.len();
+    if n < 3 {
+        return Vec::new();
+    }
+
+    // Work counter-clockwise and flip the triangles back at the end
+    let snapped: Vec<GridPoint> = polygon.iter().map(snap).collect();
+    let area: i128 = (0..n)
+        .map(|i| {
+            let (a, b) = (snapped[i], snapped[(i + 1) % n]);
+            a.0 as i128 * b.1 as i128 - b.0 as i128 * a.1 as i128
+        })
+        .sum();
+    if area == 0 {
+        return Vec::new();
+    }
+    let clockwise = area < 0;
+    let order: Vec<usize> = if clockwise {
+        (0..n).rev().collect()
+    } else {
+        (0..n).collect()
+    };
+
+    let mut ring = Ring::new(order.iter().map(|&i| snapped[i]).collect());
+    let mut triangles = Vec::with_capacity(n - 2);
+    let mut ear = 0;
+    let mut misses = 0;
+    while ring.len > 3 && misses < ring.len {
+        let (prev, next) = (ring.prev[ear], ring.next[ear]);
+        if ring.is_ear(ear) {
+            triangles.push([order[prev], order[ear], order[next]]);
+            ring.remove(ear);
+            ear = next;
+            misses = 0;
+        } else {
+            ear = next;
+            misses += 1;
+        }
+    }
+    if ring.len == 3 {
+        let (prev, next) = (ring.prev[ear], ring.next[ear]);
+        if orient(ring.points[prev], ring.points[ear], ring.points[next]) > 0 {
+            triangles.push([order[prev], order[ear], order[next]]);
+        }
+    }
+
+    if clockwise {
+        for triangle in &mut triangles {
+            triangle.reverse();
+        }
+    }
+    triangles
+}
+
+/// The vertices left to triangulate, as a counter-clockwise linked ring.
+struct Ring {
+    points: Vec<GridPoint>,
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    removed: Vec<bool>,
+    /// Whether each vertex is reflex or collinear
+    reflex: Vec<bool>,
+    len: usize,
+    /// Vertices sharing a position with others, such as the ends of bridges
+    shared: HashMap<GridPoint, Vec<usize>>,
+    /// Reflex and collinear vertices bucketed by position; vertices that
+    /// are cut or turn convex are skipped when looked up
+    cells: Vec<Vec<usize>>,
+    origin: GridPoint,
+    cell_size: i64,
+    columns: usize,
+    rows: usize,
+}
+
+impl Ring {
+    fn new(points: Vec<GridPoint>) -> Ring {
+        let n = points.len();
+        let prev: Vec<usize> = (0..n).map(|i| (i + n - 1) % n).collect();
+        let next: Vec<usize> = (0..n).map(|i| (i + 1) % n).collect();
+        let reflex: Vec<bool> = (0..n)
+            .map(|i| orient(points[prev[i]], points[i], points[next[i]]) <= 0)
+            .collect();
+
+        let mut shared: HashMap<GridPoint, Vec<usize>> = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            shared.entry(*point).or_default().push(i);
+        }
+        shared.retain(|_, vertices| vertices.len() > 1);
+
+        // About one reflex vertex per cell on a square grid
+        let (min_x, max_x) = min_max(points.iter().map(|p| p.0));
+        let (min_y, max_y) = min_max(points.iter().map(|p| p.1));
+        let side = (n as f64).sqrt().ceil() as i64;
+        let cell_size = ((max_x - min_x).max(max_y - min_y) / side).max(1) + 1;
+        let columns = ((max_x - min_x) / cell_size + 1) as usize;
+        let rows = ((max_y - min_y) / cell_size + 1) as usize;
+
+        let mut ring = Ring {
+            points,
+            prev,
+            next,
+            removed: vec![false; n],
+            reflex,
+            len: n,
+            shared,
+            cells: vec![Vec::new(); columns * rows],
+            origin: (min_x, min_y),
+            cell_size,
+            columns,
+            rows,
+        };
+        for i in (0..n).filter(|&i| ring.reflex[i]) {
+            let (column, row) = ring.cell(ring.points[i]);
+            ring.cells[row * ring.columns + column].push(i);
+        }
+        ring
+    }
+
+    /// The grid cell of a point, clamped to the grid.
+    fn cell(&self, point: GridPoint) -> (usize, usize) {
+        let column = ((point.0 - self.origin.0) / self.cell_size).max(0) as usize;
+        let row = ((point.1 - self.origin.1) / self.cell_size).max(0) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    /// Cuts a vertex out of the ring and updates its neighbors.
+    fn remove(&mut self, vertex: usize) {
+        let (prev, next) = (self.prev[vertex], self.next[vertex]);
+        self.next[prev] = next;
+        self.prev[next] = prev;
+        self.removed[vertex] = true;
+        self.len -= 1;
+        for i in [prev, next] {
+            self.reflex[i] =
+                orient(self.points[self.prev[i]], self.points[i], self.points[self.next[i]]) <= 0;
+        }
+    }
+
+    /// Whether the triangle of a vertex and its neighbors is an ear: the
+    /// vertex is strictly convex, the new diagonal runs inside the polygon,
+    /// and no other part of the boundary lies in or enters the triangle.
+    fn is_ear(&self, ear: usize) -> bool {
+        let (prev, next) = (self.prev[ear], self.next[ear]);
+        let (a, b, c) = (self.points[prev], self.points[ear], self.points[next]);
+        if orient(a, b, c) <= 0 {
+            return false;
+        }
+
+        // The diagonal leaves both ends on their inner side
+        let before = self.points[self.prev[prev]];
+        let after = self.points[self.next[next]];
+        if (!self.reflex[prev] && orient(before, a, c) < 0)
+            || (!self.reflex[next] && orient(c, after, a) < 0)
+        {
+            return false;
+        }
+
+        // Other vertices at a corner must not lead into the triangle
+        let corners = [(a, b, c), (b, c, a), (c, a, b)];
+        for (corner, to, from) in corners {
+            let Some(vertices) = self.shared.get(&corner) else {
+                continue;
+            };
+            for &vertex in vertices {
+                if self.removed[vertex] || [prev, ear, next].contains(&vertex) {
+                    continue;
+                }
+                for neighbor in [self.prev[vertex], self.next[vertex]] {
+                    let point = self.points[neighbor];
+                    if orient(corner, to, point) > 0 && orient(from, corner, point) > 0 {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        // No reflex or collinear vertex lies in the triangle or on its edges
+        let (min_column, min_row) = self.cell((a.0.min(b.0).min(c.0), a.1.min(b.1).min(c.1)));
+        let (max_column, max_row) = self.cell((a.0.max(b.0).max(c.0), a.1.max(b.1).max(c.1)));
+        for row in min_row..=max_row {
+            for column in min_column..=max_column {
+                for &vertex in &self.cells[row * self.columns + column] {
+                    let p = self.points[vertex];
+                    if self.removed[vertex] || !self.reflex[vertex] || p == a || p == b || p == c {
+                        continue;
+                    }
+                    if orient(a, b, p) >= 0 && orient(b, c, p) >= 0 && orient(c, a, p) >= 0 {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Snaps a point to the grid.
+fn snap(point: &Point) -> GridPoint {
+    (
+        (point.x * GRID).round() as i64,
+        (point.y * GRID).round() as i64,
+    )
+}
+
+/// Twice the signed area of the triangle (o, a, b); positive when `b` is
+/// to the left of the line from `o` to `a`.
+fn orient(o: GridPoint, a: GridPoint, b: GridPoint) -> i128 {
+    (a.0 - o.0) as i128 * (b.1 - o.1) as i128 - (a.1 - o.1) as i128 * (b.0 - o.0) as i128
+}
+
+/// Smallest and largest of a non-empty sequence.
+fn min_max(values: impl Iterator<Item = i64>) -> (i64, i64) {
+    values.fold((i64::MAX, i64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)))
+}
@@ -0,0 +1,341 @@
+//! Gerber job files (`.gbrjob`).
+//!
+//! A job file is a JSON manifest written next to the Gerber files. It lists
+//! every file with its `FileFunction` and polarity, and describes the board
+//! as a whole: size, layer count, thickness, finish and material stackup.
+//! When present it is the authoritative source for layer mapping and board
+//! thickness.
+//!
+//! The JSON is parsed with a small nom grammar into [`JsonValue`], then the
+//! known fields are picked out into a [`JobFile`].
+
+use nom::{
+    IResult,
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, multispace0, none_of},
+    combinator::{map, value},
+    multi::separated_list0,
+    number::complete::double,
+    sequence::{delimited, separated_pair},
+};
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// Members in file order
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Member of an object by key.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[JsonValue] {
+        match self {
+            JsonValue::Array(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+/// Image polarity of a file: negative files mark where material is absent
+/// (typically solder mask openings).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Polarity {
+    Positive,
+    Negative,
+}
+
+/// A file listed in the `FilesAttributes` section.
+#[derive(Debug, Clone)]
+pub struct JobFileEntry {
+    /// File path, relative to the job file
+    pub path: String,
+    /// `FileFunction` fields, e.g. `["Copper", "L1", "Top"]`
+    pub file_function: Vec<String>,
+    /// Image polarity, if given
+    pub polarity: Option<Polarity>,
+}
+
+/// A layer of the `MaterialStackup` section.
+#[derive(Debug, Clone)]
+pub struct StackupEntry {
+    /// Layer type: `Legend`, `SolderPaste`, `SolderMask`, `Copper` or `Dielectric`
+    pub layer_type: String,
+    /// Layer name, e.g. `F.Cu`
+    pub name: String,
+    /// Material, e.g. `FR4`
+    pub material: Option<String>,
+    /// Thickness in mm, if given
+    pub thickness: Option<f64>,
+    /// Color, e.g. `Green`
+    pub color: Option<String>,
+}
+
+impl StackupEntry {
+    /// An entry with only a type and a name.
+    pub fn new(layer_type: &str, name: &str) -> StackupEntry {
+        StackupEntry {
+            layer_type: layer_type.to_string(),
+            name: name.to_string(),
+            material: None,
+            thickness: None,
+            color: None,
+        }
+    }
+}
+
+/// The parts of a Gerber job file used to build the model.
+#[derive(Debug, Clone, Default)]
+pub struct JobFile {
+    /// Project name from `GeneralSpecs.ProjectId`
+    pub project: Option<String>,
+    /// Project revision from `GeneralSpecs.ProjectId`
+    pub revision: Option<String>,
+    /// Vendor, application and version from `Header.GenerationSoftware`
+    pub generator: Option<String>,
+    /// Board bounding box size (X, Y) in mm
+    pub size: Option<(f64, f64)>,
+    /// Number of copper layers
+    pub layer_count: Option<u32>,
+    /// Board thickness in mm
+    pub board_thickness: Option<f64>,
+    /// Surface finish, e.g. `ENIG` or `None`
+    pub finish: Option<String>,
+    /// Every file of the job
+    pub files: Vec<JobFileEntry>,
+    /// Physical layers from top to bottom
+    pub stackup: Vec<StackupEntry>,
+}
+
+impl JobFile {
+    /// The entry for a file, matched by file name.
+    pub fn file(&self, file_name: &str) -> Option<&JobFileEntry> {
+        self.files.iter().find(|entry| {
+            let name = entry.path.rsplit(['/', '\\']).next().unwrap_or(&entry.path);
+            name == file_name
+        })
+    }
+}
+
+/// Parse the content of a Gerber job file.
+///
+/// # Arguments
+///
+/// * `content` - String content of the `.gbrjob` file
+///
+/// # Returns
+///
+/// * `Result<JobFile, String>` - The job description or an error message
+pub fn parse_job_file(content: &str) -> Result<JobFile, String> {
+    let root = parse_json(content)?;
+    let specs = root.get("GeneralSpecs");
+    let spec = |key: &str| specs.and_then(|specs| specs.get(key));
+    let project = spec("ProjectId");
+
+    let generator = root
+        .get("Header")
+        .and_then(|header| header.get("GenerationSoftware"))
+        .map(|software| {
+            ["Vendor", "Application", "Version"]
+                .iter()
+                .filter_map(|key| software.get(key).and_then(JsonValue::as_str))
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+
+    let size =
+        spec("Size").and_then(|size| Some((size.get("X")?.as_f64()?, size.get("Y")?.as_f64()?)));
+
+    let files = root
+        .get("FilesAttributes")
+        .map(JsonValue::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            let path = entry.get("Path")?.as_str()?.to_string();
+            let file_function = entry
+                .get("FileFunction")
+                .and_then(JsonValue::as_str)
+                .map(|function| function.split(',').map(|f| f.trim().to_string()).collect())
+                .unwrap_or_default();
+            let polarity = match entry.get("FilePolarity").and_then(JsonValue::as_str) {
+                Some("Positive") => Some(Polarity::Positive),
+                Some("Negative") => Some(Polarity::Negative),
+                _ => None,
+            };
+            Some(JobFileEntry {
+                path,
+                file_function,
+                polarity,
+            })
+        })
+        .collect();
+
+    let text = |value: &JsonValue, key: &str| {
+        value.get(key).and_then(JsonValue::as_str).map(String::from)
+    };
+    let stackup = root
+        .get("MaterialStackup")
+        .map(JsonValue::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            Some(StackupEntry {
+                layer_type: text(entry, "Type")?,
+                name: text(entry, "Name").unwrap_or_default(),
+                material: text(entry, "Material"),
+                thickness: entry.get("Thickness").and_then(JsonValue::as_f64),
+                color: text(entry, "Color"),
+            })
+        })
+        .collect();
+
+    Ok(JobFile {
+        project: project
+            .and_then(|id| id.get("Name"))
+            .and_then(JsonValue::as_str)
+            .map(String::from),
+        revision: project
+            .and_then(|id| id.get("Revision"))
+            .and_then(JsonValue::as_str)
+            .map(String::from),
+        generator,
+        size,
+        layer_count: spec("LayerNumber")
+            .and_then(JsonValue::as_f64)
+            .map(|n| n as u32),
+        board_thickness: spec("BoardThickness").and_then(JsonValue::as_f64),
+        finish: spec("Finish").and_then(JsonValue::as_str).map(String::from),
+        files,
+        stackup,
+    })
+}
+
+/// Parse a JSON document.
+///
+/// # Arguments
+///
+/// * `content` - The JSON text
+///
+/// # Returns
+///
+/// * `Result<JsonValue, String>` - The root value or an error message
+pub fn parse_json(content: &str) -> Result<JsonValue, String> {
+    match json_value(content) {
+        Ok(("", value)) => Ok(value),
+        Ok((rest, _)) => Err(format!(
+            "Unexpected content after JSON value: {}",
+            rest.chars().take(20).collect::<String>()
+        )),
+        Err(e) => Err(format!("Invalid JSON: {}", e)),
+    }
+}
+
+/// Any JSON value with surrounding whitespace
+fn json_value(input: &str) -> IResult<&str, JsonValue> {
+    delimited(
+        multispace0,
+        alt((
+            value(JsonValue::Null, tag("null")),
+            value(JsonValue::Bool(true), tag("true")),
+            value(JsonValue::Bool(false), tag("false")),
+            map(double, JsonValue::Number),
+            map(json_string, JsonValue::String),
+            map(
+                delimited(
+                    char('['),
+                    separated_list0(char(','), json_value),
+                    delimited(multispace0, char(']'), multispace0),
+                ),
+                JsonValue::Array,
+            ),
+            map(
+                delimited(
+                    char('{'),
+                    separated_list0(
+                        char(','),
+                        separated_pair(
+                            delimited(multispace0, json_string, multispace0),
+                            char(':'),
+                            json_value,
+                        ),
+                    ),
+                    delimited(multispace0, char('}'), multispace0),
+                ),
+                JsonValue::Object,
+            ),
+        )),
+        multispace0,
+    )(input)
+}
+
+/// A double-quoted string with JSON escapes
+fn json_string(input: &str) -> IResult<&str, String> {
+    let (mut input, _) = char('"')(input)?;
+    let mut text = String::new();
+
+    loop {
+        if let Some(rest) = input.strip_prefix('"') {
+            return Ok((rest, text));
+        }
+        if let Some(rest) = input.strip_prefix('\\') {
+            let mut chars = rest.chars();
+            let escaped = match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('u') => {
+                    let hex = rest.get(1..5).ok_or_else(|| fail(input))?;
+                    let code = u32::from_str_radix(hex, 16).map_err(|_| fail(input))?;
+                    text.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                    input = &rest[5..];
+                    continue;
+                }
+                Some(c @ ('"' | '\\' | '/')) => c,
+                _ => return Err(fail(input)),
+            };
+            text.push(escaped);
+            input = chars.as_str();
+            continue;
+        }
+
+        let (rest, c) = none_of("\"\\")(input)?;
+        text.push(c);
+        input = rest;
+    }
+}
+
+fn fail(input: &str) -> nom::Err<nom::error::Error<&str>> {
+    nom::Err::Error(nom::error::Error::new(
+        input,
+        nom::error::ErrorKind::Escaped,
+    ))
+}
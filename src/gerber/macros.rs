@@ -0,0 +1,431 @@
+//! Evaluation of aperture macros (`%AM`) into flashable shapes.
+//!
+//! A macro is a template of primitives whose parameters are arithmetic
+//! expressions. Instantiating it with the actual parameters of an `%ADD`
+//! command binds `$1`, `$2`, ... and turns every primitive into one or more
+//! closed outlines relative to the aperture origin.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::geometry::shapes::regular_polygon;
+use crate::gerber::plot::{arc_points, circle_segments};
+use crate::gerber::types::{ApertureMacro, MacroExpr, MacroPrimitive, MacroStatement, Point};
+
+/// An outline produced by a macro primitive.
+#[derive(Debug, Clone)]
+pub struct MacroShape {
+    /// `true` when the primitive adds material, `false` when it clears it
+    pub exposure: bool,
+    /// Closed outline relative to the aperture origin (first point not repeated)
+    pub outline: Vec<Point>,
+}
+
+/// Evaluates a macro with the parameters of an aperture definition.
+///
+/// # Arguments
+///
+/// * `aperture_macro` - The macro definition
+/// * `params` - Actual parameters, bound to `$1`, `$2`, ... in order
+/// * `tolerance` - Maximum chord deviation of arcs, in the units of the file
+///
+/// # Returns
+///
+/// * `Result<Vec<MacroShape>, String>` - The shapes in drawing order, or an error message
+pub fn evaluate_macro(
+    aperture_macro: &ApertureMacro,
+    params: &[f64],
+    tolerance: f64,
+) -> Result<Vec<MacroShape>, String> {
+    let mut variables: HashMap<u32, f64> = params
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (i as u32 + 1, *value))
+        .collect();
+
+    let mut shapes = Vec::new();
+    for statement in &aperture_macro.statements {
+        match statement {
+            MacroStatement::Assign { variable, value } => {
+                let value = evaluate_expression(value, &variables).map_err(|e| {
+                    format!("Error in aperture macro {}: {}", aperture_macro.name, e)
+                })?;
+                variables.insert(*variable, value);
+            }
+            MacroStatement::Primitive(primitive) => {
+                shapes.extend(evaluate_primitive(primitive, &variables, tolerance).map_err(|e| {
+                    format!("Error in aperture macro {}: {}", aperture_macro.name, e)
+                })?);
+            }
+        }
+    }
+
+    Ok(shapes)
+}
+
+/// Evaluates a macro expression. Undefined variables evaluate to zero.
+///
+/// # Arguments
+///
+/// * `expr` - The expression
+/// * `variables` - Values of the macro variables by index
+///
+/// # Returns
+///
+/// * `Result<f64, String>` - The value, or an error message on a division
+///   by zero or a result that is not finite
+pub fn evaluate_expression(expr: &MacroExpr, variables: &HashMap<u32, f64>) -> Result<f64, String> {
+    let eval = |e: &MacroExpr| evaluate_expression(e, variables);
+    let value = match expr {
+        MacroExpr::Number(value) => *value,
+        MacroExpr::Variable(index) => variables.get(index).copied().unwrap_or(0.0),
+        MacroExpr::Negate(inner) => -eval(inner)?,
+        MacroExpr::Add(lhs, rhs) => eval(lhs)? + eval(rhs)?,
+        MacroExpr::Subtract(lhs, rhs) => eval(lhs)? - eval(rhs)?,
+        MacroExpr::Multiply(lhs, rhs) => eval(lhs)? * eval(rhs)?,
+        MacroExpr::Divide(lhs, rhs) => {
+            let (lhs, rhs) = (eval(lhs)?, eval(rhs)?);
+            if rhs == 0.0 {
+                return Err(format!("division of {} by zero", lhs));
+            }
+            lhs / rhs
+        }
+    };
+    if !value.is_finite() {
+        return Err(format!("expression evaluates to {}", value));
+    }
+    Ok(value)
+}
+
+/// Turns a single primitive into outlines, applying its rotation.
+fn evaluate_primitive(
+    primitive: &MacroPrimitive,
+    variables: &HashMap<u32, f64>,
+    tolerance: f64,
+) -> Result<Vec<MacroShape>, String> {
+    let eval = |e: &MacroExpr| evaluate_expression(e, variables);
+    let exposed = |e: &MacroExpr| eval(e).map(|value| value != 0.0);
+
+    let (shapes, rotation) = match primitive {
+        MacroPrimitive::Circle {
+            exposure,
+            diameter,
+            center_x,
+            center_y,
+            rotation,
+        } => {
+            let center = Point {
+                x: eval(center_x)?,
+                y: eval(center_y)?,
+            };
+            let radius = eval(diameter)? / 2.0;
+            let segments = circle_segments(radius, tolerance);
+            let outline = regular_polygon(&center, radius, segments, 0.0);
+            (vec![(exposed(exposure)?, outline)], eval(rotation)?)
+        }
+        MacroPrimitive::VectorLine {
+            exposure,
+            width,
+            start_x,
+            start_y,
+            end_x,
+            end_y,
+            rotation,
+        } => {
+            let (sx, sy, ex, ey) = (eval(start_x)?, eval(start_y)?, eval(end_x)?, eval(end_y)?);
+            let length = (ex - sx).hypot(ey - sy);
+            if length == 0.0 {
+                return Ok(Vec::new());
+            }
+
+            // Offset perpendicular to the line by half the width on each side
+            let half = eval(width)? / 2.0;
+            let (nx, ny) = (-(ey - sy) / length * half, (ex - sx) / length * half);
+            let outline = vec![
+                Point {
+                    x: sx - nx,
+                    y: sy - ny,
+                },
+                Point {
+                    x: ex - nx,
+                    y: ey - ny,
+                },
+                Point {
+                    x: ex + nx,
+                    y: ey + ny,
+                },
+                Point {
+                    x: sx + nx,
+                    y: sy + ny,
+                },
+            ];
+            (vec![(exposed(exposure)?, outline)], eval(rotation)?)
+        }
+        MacroPrimitive::CenterLine {
+            exposure,
+            width,
+            height,
+            center_x,
+            center_y,
+            rotation,
+        } => {
+            let center = Point {
+                x: eval(center_x)?,
+                y: eval(center_y)?,
+            };
+            let outline = rectangle(&center, eval(width)?, eval(height)?);
+            (vec![(exposed(exposure)?, outline)], eval(rotation)?)
+        }
+        MacroPrimitive::Outline {
+            exposure,
+            points,
+            rotation,
+        } => {
+            let mut outline: Vec<Point> = points
+                .iter()
+                .map(|(x, y)| {
+                    Ok(Point {
+                        x: eval(x)?,
+                        y: eval(y)?,
+                    })
+                })
+                .collect::<Result<_, String>>()?;
+
+            // The last vertex repeats the first one to close the outline
+            if outline.len() > 1 {
+                let (first, last) = (&outline[0], &outline[outline.len() - 1]);
+                if (first.x - last.x).abs() < 1e-9 && (first.y - last.y).abs() < 1e-9 {
+                    outline.pop();
+                }
+            }
+            (vec![(exposed(exposure)?, outline)], eval(rotation)?)
+        }
+        MacroPrimitive::Polygon {
+            exposure,
+            vertices,
+            center_x,
+            center_y,
+            diameter,
+            rotation,
+        } => {
+            let sides = eval(vertices)?.round();
+            if !(3.0..=12.0).contains(&sides) {
+                return Err(format!("polygon with {} vertices", sides));
+            }
+            let center = Point {
+                x: eval(center_x)?,
+                y: eval(center_y)?,
+            };
+            let outline = regular_polygon(&center, eval(diameter)? / 2.0, sides as usize, 0.0);
+            (vec![(exposed(exposure)?, outline)], eval(rotation)?)
+        }
+        MacroPrimitive::Moire {
+            center_x,
+            center_y,
+            outer_diameter,
+            ring_thickness,
+            gap,
+            max_rings,
+            crosshair_thickness,
+            crosshair_length,
+            rotation,
+        } => {
+            let center = Point {
+                x: eval(center_x)?,
+                y: eval(center_y)?,
+            };
+            let (thickness, gap) = (eval(ring_thickness)?, eval(gap)?);
+            let mut shapes = Vec::new();
+
+            let mut outer = eval(outer_diameter)? / 2.0;
+            for _ in 0..eval(max_rings)?.max(0.0) as usize {
+                if outer <= 0.0 {
+                    break;
+                }
+                let inner = outer - thickness;
+                for outline in ring(&center, outer, inner, tolerance) {
+                    shapes.push((true, outline));
+                }
+                outer = inner - gap;
+            }
+
+            let (cross_t, cross_l) = (eval(crosshair_thickness)?, eval(crosshair_length)?);
+            shapes.push((true, rectangle(&center, cross_l, cross_t)));
+            shapes.push((true, rectangle(&center, cross_t, cross_l)));
+
+            (shapes, eval(rotation)?)
+        }
+        MacroPrimitive::Thermal {
+            center_x,
+            center_y,
+            outer_diameter,
+            inner_diameter,
+            gap,
+            rotation,
+        } => {
+            let center = Point {
+                x: eval(center_x)?,
+                y: eval(center_y)?,
+            };
+            let outlines = thermal(
+                &center,
+                eval(outer_diameter)? / 2.0,
+                eval(inner_diameter)? / 2.0,
+                eval(gap)? / 2.0,
+                tolerance,
+            );
+            (
+                outlines.into_iter().map(|o| (true, o)).collect(),
+                eval(rotation)?,
+            )
+        }
+    };
+
+    Ok(shapes
+        .into_iter()
+        .filter(|(_, outline)| outline.len() >= 3)
+        .map(|(exposure, outline)| MacroShape {
+            exposure,
+            outline: rotate(outline, rotation),
+        })
+        .collect())
+}
+
+/// Axis-aligned rectangle centered on a point.
+fn rectangle(center: &Point, width: f64, height: f64) -> Vec<Point> {
+    let (hw, hh) = (width / 2.0, height / 2.0);
+    vec![
+        Point {
+            x: center.x - hw,
+            y: center.y - hh,
+        },
+        Point {
+            x: center.x + hw,
+            y: center.y - hh,
+        },
+        Point {
+            x: center.x + hw,
+            y: center.y + hh,
+        },
+        Point {
+            x: center.x - hw,
+            y: center.y + hh,
+        },
+    ]
+}
+
+/// A ring between two radii, split into two halves so that each half is a
+/// simple polygon. A non-positive inner radius yields a full disc.
+///
+/// Arcs are flattened with `tolerance` as the maximum chord deviation.
+pub fn ring(center: &Point, outer: f64, inner: f64, tolerance: f64) -> Vec<Vec<Point>> {
+    if inner <= 0.0 {
+        return vec![regular_polygon(center, outer, circle_segments(outer, tolerance), 0.0)];
+    }
+
+    [0.0, PI]
+        .iter()
+        .map(|start| {
+            let end = start + PI;
+            let at = |r: f64, a: f64| Point {
+                x: center.x + r * a.cos(),
+                y: center.y + r * a.sin(),
+            };
+
+            let mut outline = vec![at(outer, *start)];
+            outline.extend(arc_points(
+                &at(outer, *start),
+                center,
+                &at(outer, end),
+                false,
+                tolerance,
+            ));
+            outline.push(at(inner, end));
+            outline.extend(arc_points(
+                &at(inner, end),
+                center,
+                &at(inner, *start),
+                true,
+                tolerance,
+            ));
+            outline
+        })
+        .collect()
+}
+
+/// The four segments of a thermal relief ring.
+///
+/// `gap` is the half width of the cross-shaped gap along the X and Y axes,
+/// and `tolerance` the maximum chord deviation of the arcs.
+fn thermal(center: &Point, outer: f64, inner: f64, gap: f64, tolerance: f64) -> Vec<Vec<Point>> {
+    if gap >= outer {
+        return Vec::new();
+    }
+
+    // Build the first quadrant, then rotate it into the other three
+    let outer_start = Point {
+        x: (outer * outer - gap * gap).sqrt(),
+        y: gap,
+    };
+    let outer_end = Point {
+        x: gap,
+        y: outer_start.x,
+    };
+    let origin = Point { x: 0.0, y: 0.0 };
+
+    let mut quadrant = vec![outer_start.clone()];
+    quadrant.extend(arc_points(
+        &outer_start,
+        &origin,
+        &outer_end,
+        false,
+        tolerance,
+    ));
+    if gap < inner {
+        let inner_start = Point {
+            x: gap,
+            y: (inner * inner - gap * gap).sqrt(),
+        };
+        let inner_end = Point {
+            x: inner_start.y,
+            y: gap,
+        };
+        quadrant.push(inner_start.clone());
+        quadrant.extend(arc_points(
+            &inner_start,
+            &origin,
+            &inner_end,
+            true,
+            tolerance,
+        ));
+    } else {
+        quadrant.push(Point { x: gap, y: gap });
+    }
+
+    (0..4)
+        .map(|i| {
+            rotate(quadrant.clone(), 90.0 * i as f64)
+                .into_iter()
+                .map(|p| Point {
+                    x: p.x + center.x,
+                    y: p.y + center.y,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Rotates points counter-clockwise around the origin by `degrees`.
+fn rotate(points: Vec<Point>, degrees: f64) -> Vec<Point> {
+    if degrees == 0.0 {
+        return points;
+    }
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    points
+        .into_iter()
+        .map(|p| Point {
+            x: p.x * cos - p.y * sin,
+            y: p.x * sin + p.y * cos,
+        })
+        .collect()
+}
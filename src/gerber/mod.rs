@@ -8,6 +8,12 @@
 //!
 //! - `types`: Defines Gerber file structures and commands.
 //! - `parse`: Implements the parser for Gerber files.
+//! - `macros`: Evaluates aperture macros (`%AM`) into shapes.
+//! - `plot`: Replays parsed commands into 2D outlines (strokes, flashes, regions).
+//! - `job`: Reads Gerber job files (`.gbrjob`) describing the whole board.
 
+pub mod job;
+pub mod macros;
 pub mod parse;
+pub mod plot;
 pub mod types;
\ No newline at end of file
@@ -9,32 +9,151 @@
 //!
 //! ## Parsing strategy
 //! 
-//! The parser processes Gerber files line by line, applying different parsers
-//! based on the command type:
+//! The content is first split into blocks on the `*` and `%` delimiters (see
+//! [`tokenize`]), so line breaks do not matter. Each block is then handed to
+//! different parsers based on the command type:
 //!
 //! - Format specification (`%FSLAX...`)
 //! - Units setting (`%MOMM*%` or `%MOIN*%`)
+//! - Load polarity (`%LPD*%` or `%LPC*%`)
+//! - Aperture macros (`%AM...`)
 //! - Aperture definitions (`%ADD...`)
+//! - X2 attributes (`%TF`, `%TA`, `%TO`, `%TD`)
 //! - Interpolation mode commands (`G01`, `G02`, `G03`)
+//! - Quadrant mode commands (`G74`, `G75`)
 //! - Region commands (`G36`, `G37`)
 //! - Drawing commands (`D01`, `D02`, `D03`)
 //! - Aperture selection commands (`D10*` etc.)
 //!
 //! The parser maintains state for current coordinates, format specification,
 //! and interpolation mode, which is needed to properly interpret commands.
+//! Blocks that no parser accepts are reported as diagnostics with their
+//! position instead of being dropped silently.
 
 use nom::{
     IResult,
     branch::alt,
     bytes::complete::tag,
-    combinator::value,
+    character::complete::{char, digit0, digit1, one_of},
+    combinator::{map, opt, recognize, value},
+    multi::fold_many0,
+    sequence::{delimited, pair, preceded},
 };
 
-use crate::gerber::types::{Aperture, Command, InterpolationMode, Point};
+use std::collections::HashMap;
+
+use crate::gerber::types::{
+    Aperture, ApertureMacro, Attributes, Command, Diagnostic, DiagnosticKind, GerberFile,
+    InterpolationMode, LoadPolarity, MacroExpr, MacroPrimitive, MacroStatement, Point,
+    QuadrantMode,
+};
+
+/// A single data block or extended command, as delimited in the file.
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// Block text without line breaks, including its `*` (and `%`) delimiters
+    pub text: String,
+    /// Line on which the block starts (1-based)
+    pub line: usize,
+    /// Column at which the block starts (1-based)
+    pub column: usize,
+}
+
+/// Splits Gerber content into blocks using the delimiters defined by the spec.
+///
+/// Word commands end at `*`; extended commands are enclosed in `%...%`.
+/// Line breaks carry no meaning and are dropped, so a file with several
+/// commands per line, commands split across lines or no line breaks at all
+/// tokenizes the same way. Extended commands holding several data blocks
+/// (`%FSLAX26Y26*MOIN*%`) are split into one `%...*%` block each, except
+/// aperture macros which are kept whole.
+///
+/// # Arguments
+///
+/// * `content` - The content of the Gerber file as a string
+///
+/// # Returns
+///
+/// * `Vec<Block>` - The blocks in file order
+pub fn tokenize(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut in_extended = false;
+    let (mut line, mut column) = (1, 0);
+
+    for c in content.chars() {
+        match c {
+            '\n' => {
+                line += 1;
+                column = 0;
+                continue;
+            }
+            '\r' => continue,
+            _ => column += 1,
+        }
+
+        match c {
+            '%' if in_extended => {
+                text.push('%');
+                let (line, column) = start.take().unwrap_or((line, column));
+                blocks.extend(split_extended(&text, line, column));
+                text.clear();
+                in_extended = false;
+            }
+            '%' => {
+                // Text without a '*' terminator is kept so that it can be reported
+                if let Some((line, column)) = start.take() {
+                    blocks.push(Block { text: std::mem::take(&mut text), line, column });
+                }
+                text.push('%');
+                start = Some((line, column));
+                in_extended = true;
+            }
+            '*' if !in_extended => {
+                text.push('*');
+                let (line, column) = start.take().unwrap_or((line, column));
+                blocks.push(Block { text: std::mem::take(&mut text), line, column });
+            }
+            _ => {
+                if start.is_none() {
+                    if c.is_whitespace() {
+                        continue;
+                    }
+                    start = Some((line, column));
+                }
+                text.push(c);
+            }
+        }
+    }
+
+    if let Some((line, column)) = start {
+        blocks.push(Block { text, line, column });
+    }
+
+    blocks
+}
+
+/// Splits an extended command into one block per data block.
+fn split_extended(text: &str, line: usize, column: usize) -> Vec<Block> {
+    let body = text.trim_start_matches('%').trim_end_matches('%');
+    if body.starts_with("AM") {
+        return vec![Block { text: text.to_string(), line, column }];
+    }
+
+    body.split('*')
+        .map(str::trim)
+        .filter(|data| !data.is_empty())
+        .map(|data| Block { text: format!("%{}*%", data), line, column })
+        .collect()
+}
 
 /// Main parser function for Gerber files.
 /// Parses a Gerber file's content into a list of structured commands.
 ///
+/// Blocks that cannot be interpreted are skipped; use [`parse_gerber_file`]
+/// to find out which ones.
+///
 /// # Arguments
 ///
 /// * `content` - The content of the Gerber file as a string
@@ -43,6 +162,24 @@ use crate::gerber::types::{Aperture, Command, InterpolationMode, Point};
 ///
 /// * `Result<Vec<Command>, String>` - The parsed commands on success, or an error message
 pub fn parse_gerber(content: &str) -> Result<Vec<Command>, String> {
+    parse_gerber_file(content, false).map(|file| file.commands)
+}
+
+/// Parses a Gerber file's content into commands and diagnostics.
+///
+/// Every block that is skipped is recorded as a [`Diagnostic`] with its
+/// position, raw text and the reason it was skipped.
+///
+/// # Arguments
+///
+/// * `content` - The content of the Gerber file as a string
+/// * `strict` - Fail instead of returning a file with diagnostics
+///
+/// # Returns
+///
+/// * `Result<GerberFile, String>` - The parsed file, or an error message listing
+///   the diagnostics in strict mode
+pub fn parse_gerber_file(content: &str, strict: bool) -> Result<GerberFile, String> {
     // Context for parsing
     let mut current_x = 0.0;
     let mut current_y = 0.0;
@@ -52,49 +189,225 @@ pub fn parse_gerber(content: &str) -> Result<Vec<Command>, String> {
     
     // Results
     let mut commands = Vec::new();
+    let mut diagnostics = Vec::new();
+    let mut macro_names: Vec<String> = Vec::new();
     
-    // Process each line
-    for line in content.lines() {
-        let line = line.trim();
+    // Process each block
+    for block in tokenize(content) {
+        let mut text = block.text.as_str();
         
-        // Skip empty lines and comments
-        if line.is_empty() || line.starts_with("G04") {
+        // Skip comments and blocks that only restate the defaults we assume
+        if text.starts_with("G04") || IGNORED_BLOCKS.contains(&text) {
             continue;
         }
+
+        // Deprecated G54 prefix before an aperture selection (G54D10*)
+        if let Some(rest) = text.strip_prefix("G54").or_else(|| text.strip_prefix("G55")) {
+            text = rest;
+        }
         
-        // Try to parse the line with different parsers
-        if let Ok((_, cmd)) = parse_format_spec(line) {
+        // Try to parse the block with different parsers
+        if let Ok((_, cmd)) = parse_format_spec(text) {
             integer_digits = cmd.0;
             decimal_digits = cmd.1;
             commands.push(Command::FormatSpecification {
                 integer_digits,
                 decimal_digits,
             });
-        } else if let Ok((_, Command::SetUnitsMM)) = parse_units_mm(line) {
+        } else if let Ok((_, Command::SetUnitsMM)) = parse_units_mm(text) {
             commands.push(Command::SetUnitsMM);
-        } else if let Ok((_, Command::SetUnitsInch)) = parse_units_inch(line) {
+        } else if let Ok((_, Command::SetUnitsInch)) = parse_units_inch(text) {
             commands.push(Command::SetUnitsInch);
-        } else if let Ok((_, aperture_def)) = parse_aperture_definition(line) {
+        } else if let Ok((_, polarity)) = parse_load_polarity(text) {
+            commands.push(Command::SetPolarity(polarity));
+        } else if let Ok((_, aperture_macro)) = parse_aperture_macro(text) {
+            if let Command::DefineMacro(definition) = &aperture_macro {
+                macro_names.push(definition.name.clone());
+            }
+            commands.push(aperture_macro);
+        } else if let Ok((_, aperture_def)) = parse_aperture_definition(text) {
+            if let Command::DefineAperture { aperture: Aperture::Macro { name, .. }, .. } = &aperture_def
+                && !macro_names.contains(name)
+            {
+                diagnostics.push(Diagnostic {
+                    line: block.line,
+                    column: block.column,
+                    text: block.text.clone(),
+                    kind: DiagnosticKind::Malformed,
+                    reason: format!("aperture macro {} is not defined", name),
+                });
+                continue;
+            }
+            // The aperture is kept, with the hole clipped to its outline
+            if let Command::DefineAperture { aperture, .. } = &aperture_def
+                && let Some(reason) = oversized_hole(aperture)
+            {
+                diagnostics.push(Diagnostic {
+                    line: block.line,
+                    column: block.column,
+                    text: block.text.clone(),
+                    kind: DiagnosticKind::Malformed,
+                    reason,
+                });
+            }
             commands.push(aperture_def);
-        } else if let Ok((_, mode)) = parse_interpolation_mode(line) {
+        } else if let Ok((_, attribute)) = parse_attribute(text) {
+            commands.push(attribute);
+        } else if let Ok((rest, mode)) = parse_interpolation_mode(text) {
             current_interpolation = mode.clone();
             commands.push(Command::SetInterpolationMode(mode));
-        } else if let Ok((_, Command::BeginRegion)) = parse_begin_region(line) {
+
+            // Deprecated combined form: G01X100Y100D01*
+            if rest != "*"
+                && let Some(cmd) = parse_draw_command(rest, &mut current_x, &mut current_y,
+                                                      integer_digits, decimal_digits, &current_interpolation)
+            {
+                commands.push(cmd);
+            }
+        } else if let Ok((_, mode)) = parse_quadrant_mode(text) {
+            commands.push(Command::SetQuadrantMode(mode));
+        } else if let Ok((_, Command::BeginRegion)) = parse_begin_region(text) {
             commands.push(Command::BeginRegion);
-        } else if let Ok((_, Command::EndRegion)) = parse_end_region(line) {
+        } else if let Ok((_, Command::EndRegion)) = parse_end_region(text) {
             commands.push(Command::EndRegion);
-        } else if let Ok((_, Command::EndOfFile)) = parse_end_of_file(line) {
+        } else if let Ok((_, Command::EndOfFile)) = parse_end_of_file(text) {
             commands.push(Command::EndOfFile);
-        } else if let Ok((_, aperture_select)) = parse_aperture_selection(line) {
+        } else if let Ok((_, aperture_select)) = parse_aperture_selection(text) {
             commands.push(aperture_select);
-        } else if let Some(cmd) = parse_draw_command(line, &mut current_x, &mut current_y, 
+        } else if let Some(cmd) = parse_draw_command(text, &mut current_x, &mut current_y, 
                                               integer_digits, decimal_digits, &current_interpolation) {
             commands.push(cmd);
+        } else {
+            let (kind, reason) = classify_unparsed_block(text);
+            diagnostics.push(Diagnostic {
+                line: block.line,
+                column: block.column,
+                text: block.text.clone(),
+                kind,
+                reason,
+            });
         }
-        // Other commands could be added here
+    }
+
+    if strict && !diagnostics.is_empty() {
+        let details: Vec<String> = diagnostics.iter().map(|d| format!("  {}", d)).collect();
+        return Err(format!(
+            "{} block(s) could not be parsed in strict mode:\n{}",
+            diagnostics.len(),
+            details.join("\n")
+        ));
     }
     
-    Ok(commands)
+    let (file_attributes, aperture_attributes, object_attributes) = resolve_attributes(&commands);
+
+    Ok(GerberFile {
+        commands,
+        diagnostics,
+        file_attributes,
+        aperture_attributes,
+        object_attributes,
+    })
+}
+
+/// Replays the attribute commands and attaches each dictionary to what it describes.
+///
+/// File attributes apply to the whole file. The aperture dictionary is
+/// captured by every `%ADD` and the object dictionary by every draw, arc and
+/// flash, as the X2 specification defines.
+fn resolve_attributes(
+    commands: &[Command],
+) -> (Attributes, HashMap<u32, Attributes>, HashMap<usize, Attributes>) {
+    let mut file_attributes = Attributes::new();
+    let mut aperture_attributes = HashMap::new();
+    let mut object_attributes = HashMap::new();
+
+    let mut aperture_dictionary = Attributes::new();
+    let mut object_dictionary = Attributes::new();
+
+    for (index, command) in commands.iter().enumerate() {
+        match command {
+            Command::FileAttribute { name, values } => {
+                file_attributes.insert(name.clone(), values.clone());
+            }
+            Command::ApertureAttribute { name, values } => {
+                aperture_dictionary.insert(name.clone(), values.clone());
+            }
+            Command::ObjectAttribute { name, values } => {
+                object_dictionary.insert(name.clone(), values.clone());
+            }
+            Command::DeleteAttribute { name: Some(name) } => {
+                aperture_dictionary.remove(name);
+                object_dictionary.remove(name);
+            }
+            Command::DeleteAttribute { name: None } => {
+                aperture_dictionary.clear();
+                object_dictionary.clear();
+            }
+            Command::DefineAperture { code, .. } if !aperture_dictionary.is_empty() => {
+                aperture_attributes.insert(*code, aperture_dictionary.clone());
+            }
+            Command::Draw { .. } | Command::ArcDraw { .. } | Command::Flash { .. }
+                if !object_dictionary.is_empty() =>
+            {
+                object_attributes.insert(index, object_dictionary.clone());
+            }
+            _ => {}
+        }
+    }
+
+    (file_attributes, aperture_attributes, object_attributes)
+}
+
+/// Blocks that are accepted without effect because they restate the
+/// defaults the parser already assumes.
+const IGNORED_BLOCKS: &[&str] = &[
+    "G90*",    // Absolute coordinates
+    "M01*",    // Optional stop
+    "%IPPOS*%", // Positive image polarity
+];
+
+/// Explains why a block was not recognized by any parser.
+///
+/// Blocks whose command pcbgen knows but failed to parse are malformed;
+/// valid commands pcbgen does not handle are unsupported.
+fn classify_unparsed_block(text: &str) -> (DiagnosticKind, String) {
+    use DiagnosticKind::{Malformed, Unsupported};
+
+    let unterminated = if text.starts_with('%') {
+        !text.ends_with("*%")
+    } else {
+        !text.ends_with('*')
+    };
+    if unterminated {
+        return (Malformed, "block is not properly terminated".to_string());
+    }
+
+    let (kind, reason) = match text {
+        t if t.starts_with("%FSLA") => (Malformed, "invalid format specification"),
+        t if t.starts_with("%FS") => (
+            Unsupported,
+            "only absolute coordinates with omitted leading zeros (%FSLA) are supported",
+        ),
+        t if t.starts_with("%MO") => (Malformed, "unknown units"),
+        t if t.starts_with("%AD") => (Malformed, "invalid aperture definition"),
+        t if t.starts_with("%AM") => (Malformed, "invalid aperture macro"),
+        t if t.starts_with("%LP") => (Malformed, "unknown load polarity"),
+        t if ["%TF", "%TA", "%TO", "%TD"].iter().any(|p| t.starts_with(p)) => {
+            (Malformed, "invalid attribute")
+        }
+        t if t.starts_with("%SR") => (Unsupported, "step and repeat is not supported"),
+        t if ["%LM", "%LR", "%LS"].iter().any(|p| t.starts_with(p)) => (
+            Unsupported,
+            "aperture transformations are not supported",
+        ),
+        t if t.starts_with('%') => (Unsupported, "unknown or deprecated extended command"),
+        t if t.starts_with("G91") => (Unsupported, "incremental coordinates are not supported"),
+        t if t.starts_with(['X', 'Y', 'I', 'J']) => (Malformed, "invalid coordinate data"),
+        t if t.starts_with('D') => (Malformed, "invalid D-code"),
+        _ => (Unsupported, "unrecognized command"),
+    };
+
+    (kind, reason.to_string())
 }
 
 /// Parse a format specification line like %FSLAX46Y46*%
@@ -112,21 +425,20 @@ pub fn parse_gerber(content: &str) -> Result<Vec<Command>, String> {
 /// A tuple containing (integer_digits, decimal_digits) on success.
 fn parse_format_spec(input: &str) -> IResult<&str, (u8, u8)> {
     // Extract the format part between %FSLAX and *%
-    if let Some(format_str) = input.strip_prefix("%FSLAX") {
-        if let Some(format_str) = format_str.strip_suffix("*%") {
-            if let Some(pos) = format_str.find('Y') {
-                let x_format = &format_str[..pos];
-                
-                // Get integer and decimal digits
-                if x_format.len() == 2 {
-                    if let (Some(int_digit), Some(dec_digit)) = (
-                        x_format.chars().next().and_then(|c| c.to_digit(10)),
-                        x_format.chars().nth(1).and_then(|c| c.to_digit(10))
-                    ) {
-                        return Ok(("", (int_digit as u8, dec_digit as u8)));
-                    }
-                }
-            }
+    if let Some(format_str) = input.strip_prefix("%FSLAX")
+        && let Some(format_str) = format_str.strip_suffix("*%")
+        && let Some(pos) = format_str.find('Y')
+    {
+        let x_format = &format_str[..pos];
+
+        // Get integer and decimal digits
+        if x_format.len() == 2
+            && let (Some(int_digit), Some(dec_digit)) = (
+                x_format.chars().next().and_then(|c| c.to_digit(10)),
+                x_format.chars().nth(1).and_then(|c| c.to_digit(10)),
+            )
+        {
+            return Ok(("", (int_digit as u8, dec_digit as u8)));
         }
     }
     Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag)))
@@ -148,57 +460,78 @@ fn parse_units_inch(input: &str) -> IResult<&str, Command> {
     value(Command::SetUnitsInch, tag("%MOIN*%"))(input)
 }
 
+/// Parse load polarity: %LPD*% or %LPC*%
+///
+/// Dark objects add to the image; clear objects erase the parts of the
+/// image drawn before them.
+fn parse_load_polarity(input: &str) -> IResult<&str, LoadPolarity> {
+    alt((
+        value(LoadPolarity::Dark, tag("%LPD*%")),
+        value(LoadPolarity::Clear, tag("%LPC*%")),
+    ))(input)
+}
+
 /// Parse aperture definition like %ADD10C,0.1*%
 ///
 /// Apertures define the shape and size used for drawing operations.
-/// This function handles circle and rectangle apertures:
+/// The template after the D-code selects the shape:
 ///
 /// - Circle: %ADD10C,0.1*% (aperture D10 is a circle with diameter 0.1)
 /// - Rectangle: %ADD11R,0.1X0.2*% (aperture D11 is a rectangle 0.1×0.2)
+/// - Obround: %ADD13O,0.1X0.2*% (aperture D13 is a 0.1×0.2 stadium)
+/// - Polygon: %ADD14P,0.5X6X30*% (aperture D14 is a hexagon of diameter 0.5
+///   rotated by 30°)
 ///
-/// More aperture types could be added in the future.
+/// Standard apertures accept an extra trailing parameter with the diameter
+/// of a round hole, e.g. %ADD15C,1.0X0.4*%.
+/// - Macro: %ADD12RoundRect,0.25X-0.45X0.26*% (aperture D12 instantiates the
+///   `RoundRect` macro with the given parameters)
 fn parse_aperture_definition(input: &str) -> IResult<&str, Command> {
-    if let Some(aperture_def) = input.strip_prefix("%ADD") {
-        if let Some(aperture_def) = aperture_def.strip_suffix("*%") {
-            // First try to parse a circle aperture
-            if let Some(pos) = aperture_def.find('C') {
-                let code_str = &aperture_def[..pos];
-                let params_str = &aperture_def[pos+1..];
-                
-                if let Ok(code) = code_str.parse::<u32>() {
-                    if params_str.starts_with(',') {
-                        let diameter_str = &params_str[1..];
-                        if let Ok(diameter) = diameter_str.parse::<f64>() {
-                            return Ok(("", Command::DefineAperture {
-                                code,
-                                aperture: Aperture::Circle { diameter },
-                            }));
-                        }
-                    }
-                }
-            }
-            
-            // Then try to parse a rectangle aperture
-            if let Some(pos) = aperture_def.find('R') {
-                let code_str = &aperture_def[..pos];
-                let params_str = &aperture_def[pos+1..];
-                
-                if let Ok(code) = code_str.parse::<u32>() {
-                    if params_str.starts_with(',') {
-                        let params = &params_str[1..];
-                        if let Some(x_pos) = params.find('X') {
-                            let width_str = &params[..x_pos];
-                            let height_str = &params[x_pos+1..];
-                            
-                            if let (Ok(width), Ok(height)) = (width_str.parse::<f64>(), height_str.parse::<f64>()) {
-                                return Ok(("", Command::DefineAperture {
-                                    code,
-                                    aperture: Aperture::Rectangle { width, height },
-                                }));
-                            }
-                        }
-                    }
+    if let Some(aperture_def) = input.strip_prefix("%ADD")
+        && let Some(aperture_def) = aperture_def.strip_suffix("*%")
+    {
+        let code_len = aperture_def.find(|c: char| !c.is_ascii_digit()).unwrap_or(aperture_def.len());
+        let (code_str, rest) = aperture_def.split_at(code_len);
+        let (template, params_str) = rest.split_once(',').unwrap_or((rest, ""));
+
+        let params: Result<Vec<f64>, _> = if params_str.is_empty() {
+            Ok(Vec::new())
+        } else {
+            params_str.split('X').map(|p| p.trim().parse::<f64>()).collect()
+        };
+
+        if let (Ok(code), Ok(params)) = (code_str.parse::<u32>(), params)
+            && !template.is_empty()
+        {
+            let aperture = match (template, params.as_slice()) {
+                ("C", [diameter, hole @ ..]) if hole.len() <= 1 => Some(Aperture::Circle {
+                    diameter: *diameter,
+                    hole: hole.first().copied(),
+                }),
+                ("R", [width, height, hole @ ..]) if hole.len() <= 1 => Some(Aperture::Rectangle {
+                    width: *width,
+                    height: *height,
+                    hole: hole.first().copied(),
+                }),
+                ("O", [width, height, hole @ ..]) if hole.len() <= 1 => Some(Aperture::Obround {
+                    width: *width,
+                    height: *height,
+                    hole: hole.first().copied(),
+                }),
+                ("P", [diameter, vertices, rest @ ..]) if rest.len() <= 2 && (3.0..=12.0).contains(vertices) => {
+                    Some(Aperture::Polygon {
+                        diameter: *diameter,
+                        vertices: *vertices as u32,
+                        rotation: rest.first().copied().unwrap_or(0.0),
+                        hole: rest.get(1).copied(),
+                    })
                 }
+                ("C", _) | ("R", _) | ("O", _) | ("P", _) => None,
+                (name, _) => Some(Aperture::Macro { name: name.to_string(), params }),
+            };
+
+            if let Some(aperture) = aperture {
+                return Ok(("", Command::DefineAperture { code, aperture }));
             }
         }
     }
@@ -206,6 +539,255 @@ fn parse_aperture_definition(input: &str) -> IResult<&str, Command> {
     Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag)))
 }
 
+/// Checks that the hole of a standard aperture lies strictly inside it, as
+/// the Gerber specification requires.
+///
+/// # Returns
+///
+/// * `Option<String>` - The reason the hole does not fit, if it does not
+fn oversized_hole(aperture: &Aperture) -> Option<String> {
+    let (hole, room) = match *aperture {
+        Aperture::Circle { diameter, hole } => (hole?, diameter),
+        Aperture::Rectangle { width, height, hole } | Aperture::Obround { width, height, hole } => {
+            (hole?, width.min(height))
+        }
+        // The inscribed circle of the polygon
+        Aperture::Polygon {
+            diameter,
+            vertices,
+            hole,
+            ..
+        } => (hole?, diameter * (std::f64::consts::PI / vertices as f64).cos()),
+        Aperture::Macro { .. } => return None,
+    };
+    (hole >= room).then(|| format!("hole of {} does not fit in the aperture", hole))
+}
+
+/// Parse an aperture macro definition like %AMDonut*1,1,$1,0,0*1,0,$2,0,0*%
+///
+/// The first data block is the macro name; each following block is either a
+/// comment (primitive code 0), a variable definition (`$3=$1x2`) or a
+/// primitive with comma-separated parameter expressions.
+fn parse_aperture_macro(input: &str) -> IResult<&str, Command> {
+    let fail = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag));
+
+    let body = input
+        .strip_prefix("%AM")
+        .and_then(|body| body.strip_suffix('%'))
+        .ok_or_else(fail)?;
+
+    let mut blocks = body.split('*').map(str::trim).filter(|block| !block.is_empty());
+    let name = blocks.next().ok_or_else(fail)?.to_string();
+
+    let mut statements = Vec::new();
+    for block in blocks {
+        if let Some(assignment) = block.strip_prefix('$') {
+            let (variable, expr) = assignment.split_once('=').ok_or_else(fail)?;
+            let variable = variable.trim().parse::<u32>().map_err(|_| fail())?;
+            let value = parse_macro_expression(expr).map_err(|_| fail())?;
+            statements.push(MacroStatement::Assign { variable, value });
+            continue;
+        }
+
+        let mut fields = block.split(',');
+        let code = fields.next().unwrap_or_default().trim();
+
+        // Comments start with the code 0 followed by free text
+        if code == "0" || code.starts_with("0 ") {
+            continue;
+        }
+
+        let code = code.parse::<u32>().map_err(|_| fail())?;
+        let params = fields
+            .map(parse_macro_expression)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| fail())?;
+        statements.push(MacroStatement::Primitive(build_macro_primitive(code, params).ok_or_else(fail)?));
+    }
+
+    Ok(("", Command::DefineMacro(ApertureMacro { name, statements })))
+}
+
+/// Map a primitive code and its parameter list onto a typed macro primitive.
+///
+/// Trailing rotation parameters are optional and default to zero.
+fn build_macro_primitive(code: u32, params: Vec<MacroExpr>) -> Option<MacroPrimitive> {
+    let mut params = params.into_iter();
+    let mut next = || params.next();
+    let zero = || MacroExpr::Number(0.0);
+
+    let primitive = match code {
+        1 => MacroPrimitive::Circle {
+            exposure: next()?,
+            diameter: next()?,
+            center_x: next()?,
+            center_y: next()?,
+            rotation: next().unwrap_or_else(zero),
+        },
+        20 => MacroPrimitive::VectorLine {
+            exposure: next()?,
+            width: next()?,
+            start_x: next()?,
+            start_y: next()?,
+            end_x: next()?,
+            end_y: next()?,
+            rotation: next().unwrap_or_else(zero),
+        },
+        21 => MacroPrimitive::CenterLine {
+            exposure: next()?,
+            width: next()?,
+            height: next()?,
+            center_x: next()?,
+            center_y: next()?,
+            rotation: next().unwrap_or_else(zero),
+        },
+        4 => {
+            let exposure = next()?;
+            let _vertex_count = next()?;
+            let mut rest: Vec<MacroExpr> = std::iter::from_fn(&mut next).collect();
+
+            // exposure, n, then n+1 coordinate pairs and the rotation
+            let rotation = if rest.len() % 2 == 1 { rest.pop()? } else { zero() };
+            let mut points = Vec::with_capacity(rest.len() / 2);
+            let mut coords = rest.into_iter();
+            while let (Some(x), Some(y)) = (coords.next(), coords.next()) {
+                points.push((x, y));
+            }
+            if points.len() < 3 {
+                return None;
+            }
+
+            MacroPrimitive::Outline { exposure, points, rotation }
+        }
+        5 => MacroPrimitive::Polygon {
+            exposure: next()?,
+            vertices: next()?,
+            center_x: next()?,
+            center_y: next()?,
+            diameter: next()?,
+            rotation: next().unwrap_or_else(zero),
+        },
+        6 => MacroPrimitive::Moire {
+            center_x: next()?,
+            center_y: next()?,
+            outer_diameter: next()?,
+            ring_thickness: next()?,
+            gap: next()?,
+            max_rings: next()?,
+            crosshair_thickness: next()?,
+            crosshair_length: next()?,
+            rotation: next().unwrap_or_else(zero),
+        },
+        7 => MacroPrimitive::Thermal {
+            center_x: next()?,
+            center_y: next()?,
+            outer_diameter: next()?,
+            inner_diameter: next()?,
+            gap: next()?,
+            rotation: next().unwrap_or_else(zero),
+        },
+        _ => return None,
+    };
+
+    Some(primitive)
+}
+
+/// Parse an aperture macro arithmetic expression like `$1x0.5+-0.1`
+///
+/// Supports decimal numbers, `$n` variables, unary `+`/`-`, parentheses and
+/// the binary operators `+`, `-`, `x`/`X` (multiplication) and `/` with the
+/// usual precedence. Whitespace is ignored.
+pub fn parse_macro_expression(input: &str) -> Result<MacroExpr, String> {
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+    match macro_sum(&compact) {
+        Ok(("", expr)) => Ok(expr),
+        _ => Err(format!("Invalid macro expression: {}", input)),
+    }
+}
+
+/// Additive level of a macro expression: `term (('+' | '-') term)*`
+fn macro_sum(input: &str) -> IResult<&str, MacroExpr> {
+    let (input, first) = macro_product(input)?;
+    fold_many0(
+        pair(one_of("+-"), macro_product),
+        move || first.clone(),
+        |acc, (op, rhs)| match op {
+            '+' => MacroExpr::Add(Box::new(acc), Box::new(rhs)),
+            _ => MacroExpr::Subtract(Box::new(acc), Box::new(rhs)),
+        },
+    )(input)
+}
+
+/// Multiplicative level of a macro expression: `factor (('x' | '/') factor)*`
+fn macro_product(input: &str) -> IResult<&str, MacroExpr> {
+    let (input, first) = macro_factor(input)?;
+    fold_many0(
+        pair(one_of("xX/"), macro_factor),
+        move || first.clone(),
+        |acc, (op, rhs)| match op {
+            '/' => MacroExpr::Divide(Box::new(acc), Box::new(rhs)),
+            _ => MacroExpr::Multiply(Box::new(acc), Box::new(rhs)),
+        },
+    )(input)
+}
+
+/// Unary operators, parentheses, variables and numbers
+fn macro_factor(input: &str) -> IResult<&str, MacroExpr> {
+    alt((
+        map(preceded(char('-'), macro_factor), |expr| MacroExpr::Negate(Box::new(expr))),
+        preceded(char('+'), macro_factor),
+        delimited(char('('), macro_sum, char(')')),
+        map(preceded(char('$'), digit1), |digits: &str| {
+            MacroExpr::Variable(digits.parse().unwrap_or(0))
+        }),
+        map(
+            recognize(alt((
+                recognize(pair(digit1, opt(pair(char('.'), digit0)))),
+                recognize(pair(char('.'), digit1)),
+            ))),
+            |number: &str| MacroExpr::Number(number.parse().unwrap_or(0.0)),
+        ),
+    ))(input)
+}
+
+/// Parse a Gerber X2 attribute command: %TF, %TA, %TO or %TD
+///
+/// Attributes attach metadata to the file, apertures or objects:
+///
+/// - %TF.FileFunction,Copper,L1,Top*% (file attribute)
+/// - %TA.AperFunction,ComponentPad*% (aperture attribute)
+/// - %TO.N,GND*% (object attribute: net name)
+/// - %TD.N*% or %TD*% (delete one or all attributes)
+///
+/// The first field is the attribute name, the remaining comma-separated
+/// fields are its values.
+fn parse_attribute(input: &str) -> IResult<&str, Command> {
+    let fail = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag));
+
+    let body = input
+        .strip_prefix("%T")
+        .and_then(|body| body.strip_suffix("*%"))
+        .ok_or_else(fail)?;
+    let mut chars = body.chars();
+    let kind = chars.next().ok_or_else(fail)?;
+
+    let mut fields = chars.as_str().split(',').map(|field| field.trim().to_string());
+    let name = fields.next().unwrap_or_default();
+    let values: Vec<String> = fields.collect();
+
+    let command = match kind {
+        'D' if name.is_empty() => Command::DeleteAttribute { name: None },
+        'D' => Command::DeleteAttribute { name: Some(name) },
+        _ if name.is_empty() => return Err(fail()),
+        'F' => Command::FileAttribute { name, values },
+        'A' => Command::ApertureAttribute { name, values },
+        'O' => Command::ObjectAttribute { name, values },
+        _ => return Err(fail()),
+    };
+
+    Ok(("", command))
+}
+
 /// Parse interpolation mode: G01, G02, G03
 ///
 /// The interpolation mode defines how drawing operations are performed:
@@ -221,6 +803,17 @@ fn parse_interpolation_mode(input: &str) -> IResult<&str, InterpolationMode> {
     ))(input)
 }
 
+/// Parse quadrant mode: G74, G75
+///
+/// - G74: Single-quadrant mode (arcs of at most 90°, unsigned offsets)
+/// - G75: Multi-quadrant mode (arcs of any angle, signed offsets)
+fn parse_quadrant_mode(input: &str) -> IResult<&str, QuadrantMode> {
+    alt((
+        value(QuadrantMode::Single, tag("G74*")),
+        value(QuadrantMode::Multi, tag("G75*")),
+    ))(input)
+}
+
 /// Parse begin region command: G36
 ///
 /// Recognizes the command that starts a region definition.
@@ -253,10 +846,9 @@ fn parse_end_of_file(input: &str) -> IResult<&str, Command> {
 fn parse_aperture_selection(input: &str) -> IResult<&str, Command> {
     if input.starts_with('D') && input.ends_with('*') {
         let code_str = &input[1..input.len()-1];
-        if let Ok(code) = code_str.parse::<u32>() {
-            if code >= 10 {  // D10 and above are aperture selections
-                return Ok(("", Command::SelectAperture { code }));
-            }
+        // D10 and above are aperture selections
+        if let Ok(code) = code_str.parse::<u32>() && code >= 10 {
+            return Ok(("", Command::SelectAperture { code }));
         }
     }
     
@@ -341,12 +933,12 @@ fn parse_draw_command(
     // Determine command type
     if input.contains("D01") || input.contains("D1") {
         // Draw command
-        if matches!(current_interpolation, InterpolationMode::ClockwiseCircular | InterpolationMode::CounterClockwiseCircular) 
-           && i.is_some() && j.is_some() {
-            // Arc draw
+        let circular = matches!(current_interpolation, InterpolationMode::ClockwiseCircular | InterpolationMode::CounterClockwiseCircular);
+        if circular && (i.is_some() || j.is_some()) {
+            // Arc draw; a missing offset is zero
             Some(Command::ArcDraw {
                 end_point: Point { x: *current_x, y: *current_y },
-                center_offset: Point { x: i.unwrap(), y: j.unwrap() },
+                center_offset: Point { x: i.unwrap_or(0.0), y: j.unwrap_or(0.0) },
             })
         } else {
             // Linear draw
@@ -0,0 +1,587 @@
+//! Rendering of Gerber command streams into 2D shapes.
+//!
+//! The parser produces a flat list of commands; this module replays them
+//! the way a photoplotter would and collects the resulting filled outlines:
+//!
+//! - `D01` draws stroke the current aperture along the segment or arc
+//! - `D03` flashes stamp the current aperture shape at a point
+//! - `G36`/`G37` regions are filled contours built from the enclosed draws
+//!
+//! Every outline is returned as a closed polygon (first point not repeated)
+//! in counter-clockwise order, with coordinates in millimeters.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use crate::geometry::boolean::{difference, union};
+use crate::geometry::polygon::{Polygon, signed_area};
+use crate::geometry::shapes::regular_polygon;
+use crate::gerber::macros::evaluate_macro;
+use crate::gerber::types::{
+    Aperture, ApertureMacro, Command, InterpolationMode, LoadPolarity, Point, QuadrantMode,
+};
+
+/// Default maximum distance in mm between an arc and the chords that
+/// approximate it.
+pub const ARC_TOLERANCE: f64 = 0.005;
+
+/// Upper bound on the number of chords per arc, for tiny tolerances.
+const MAX_ARC_SEGMENTS: usize = 4096;
+
+/// Millimeters per inch, used to normalize `%MOIN*%` Gerber files and inch
+/// drill files.
+pub const MM_PER_INCH: f64 = 25.4;
+
+/// Replays Gerber commands and returns the filled outlines they produce.
+///
+/// Only dark objects are returned; clear objects erase parts of them,
+/// which [`plot_layer`] takes into account.
+///
+/// # Arguments
+///
+/// * `commands` - The parsed Gerber commands of a single layer
+/// * `tolerance` - Maximum chord deviation of arcs in mm, see [`arc_points`]
+///
+/// # Returns
+///
+/// * `Result<Vec<Vec<Point>>, String>` - One counter-clockwise polygon per
+///   stroke, flash or region contour, or an error message when an aperture
+///   macro cannot be composed
+pub fn plot_commands(commands: &[Command], tolerance: f64) -> Result<Vec<Vec<Point>>, String> {
+    Ok(plot_runs(commands, tolerance)?
+        .into_iter()
+        .filter(|(polarity, _)| *polarity == LoadPolarity::Dark)
+        .flat_map(|(_, shapes)| shapes)
+        .collect())
+}
+
+/// Replays Gerber commands and composes the image they produce.
+///
+/// Dark objects are merged, and each clear object erases what was drawn
+/// before it, so the result is the layer as it would be plotted.
+///
+/// # Arguments
+///
+/// * `commands` - The parsed Gerber commands of a single layer
+/// * `tolerance` - Maximum chord deviation of arcs in mm, see [`arc_points`]
+///
+/// # Returns
+///
+/// * `Result<Vec<Polygon>, String>` - Disjoint polygons with holes covering
+///   the image, or an error message when the objects cannot be composed
+pub fn plot_layer(commands: &[Command], tolerance: f64) -> Result<Vec<Polygon>, String> {
+    plot_runs(commands, tolerance)?
+        .into_iter()
+        .try_fold(Vec::new(), |image, (polarity, shapes)| {
+            let shapes: Vec<Polygon> = shapes.into_iter().map(Polygon::new).collect();
+            match polarity {
+                LoadPolarity::Dark => union(&image, &shapes),
+                LoadPolarity::Clear => difference(&image, &shapes),
+            }
+        })
+}
+
+/// Outlines of consecutive objects with the same polarity.
+type Run = (LoadPolarity, Vec<Vec<Point>>);
+
+/// Replays Gerber commands into runs of objects with the same polarity.
+fn plot_runs(commands: &[Command], tolerance: f64) -> Result<Vec<Run>, String> {
+    let mut macros: HashMap<String, ApertureMacro> = HashMap::new();
+    let mut apertures: HashMap<u32, Vec<Vec<Point>>> = HashMap::new();
+    let mut current_aperture: Option<u32> = None;
+    let mut current_mode = InterpolationMode::Linear;
+    let mut quadrant_mode = QuadrantMode::Multi;
+    let mut current = Point { x: 0.0, y: 0.0 };
+    let mut scale = 1.0;
+    let mut polarity = LoadPolarity::Dark;
+
+    let mut in_region = false;
+    let mut contour: Vec<Point> = Vec::new();
+    let mut shapes: Vec<Vec<Point>> = Vec::new();
+    let mut runs: Vec<Run> = Vec::new();
+
+    for cmd in commands {
+        match cmd {
+            Command::SetUnitsMM => scale = 1.0,
+            Command::SetUnitsInch => scale = MM_PER_INCH,
+            Command::DefineMacro(aperture_macro) => {
+                macros.insert(aperture_macro.name.clone(), aperture_macro.clone());
+            }
+            Command::DefineAperture { code, aperture } => {
+                let outlines = resolve_aperture(aperture, &macros, tolerance / scale)?;
+                apertures.insert(*code, scale_outlines(outlines, scale));
+            }
+            Command::SelectAperture { code } => current_aperture = Some(*code),
+            Command::SetInterpolationMode(mode) => current_mode = mode.clone(),
+            Command::SetQuadrantMode(mode) => quadrant_mode = *mode,
+            Command::SetPolarity(new_polarity) if *new_polarity != polarity => {
+                runs.push((polarity, std::mem::take(&mut shapes)));
+                polarity = *new_polarity;
+            }
+            Command::BeginRegion => {
+                in_region = true;
+                contour.clear();
+            }
+            Command::EndRegion => {
+                push_contour(&mut shapes, &mut contour);
+                in_region = false;
+            }
+            Command::Move { point } => {
+                current = scaled(point, scale);
+                if in_region {
+                    push_contour(&mut shapes, &mut contour);
+                }
+            }
+            Command::Draw { point } => {
+                let end = scaled(point, scale);
+                if in_region {
+                    if contour.is_empty() {
+                        contour.push(current.clone());
+                    }
+                    contour.push(end.clone());
+                } else if let Some(aperture) = current_aperture.and_then(|c| apertures.get(&c)) {
+                    shapes.push(stroke_segment(aperture, &current, &end));
+                }
+                current = end;
+            }
+            Command::ArcDraw {
+                end_point,
+                center_offset,
+            } => {
+                let end = scaled(end_point, scale);
+                let offset = scaled(center_offset, scale);
+                let clockwise = matches!(current_mode, InterpolationMode::ClockwiseCircular);
+                let arc =
+                    arc_draw_points(&current, &end, &offset, clockwise, quadrant_mode, tolerance);
+
+                if in_region {
+                    if contour.is_empty() {
+                        contour.push(current.clone());
+                    }
+                    contour.extend(arc);
+                } else if let Some(aperture) = current_aperture.and_then(|c| apertures.get(&c)) {
+                    let mut from = current.clone();
+                    for to in arc {
+                        shapes.push(stroke_segment(aperture, &from, &to));
+                        from = to;
+                    }
+                }
+                current = end;
+            }
+            Command::Flash { point } => {
+                current = scaled(point, scale);
+                if let Some(aperture) = current_aperture.and_then(|c| apertures.get(&c)) {
+                    shapes.extend(flash_at(aperture, &current));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // An unterminated region still describes geometry; keep what we have
+    push_contour(&mut shapes, &mut contour);
+    runs.push((polarity, shapes));
+
+    Ok(runs)
+}
+
+/// Generates the points of an arc draw (`G02`/`G03` with `D01`).
+///
+/// In multi-quadrant mode (`G75`) the center is the start point plus the
+/// offset, and an arc that ends where it starts is a full circle. In
+/// single-quadrant mode (`G74`) the offset is unsigned: the center is the
+/// one, among the four sign combinations, that gives an arc of at most 90°
+/// in the direction of travel.
+///
+/// # Arguments
+///
+/// * `start` - Start point of the arc
+/// * `end` - End point of the arc
+/// * `offset` - Center offset from the start point (I and J values)
+/// * `clockwise` - Direction of travel (G02 when `true`, G03 otherwise)
+/// * `quadrant` - Quadrant mode in effect
+/// * `tolerance` - Maximum chord deviation in mm, see [`arc_points`]
+pub fn arc_draw_points(
+    start: &Point,
+    end: &Point,
+    offset: &Point,
+    clockwise: bool,
+    quadrant: QuadrantMode,
+    tolerance: f64,
+) -> Vec<Point> {
+    match arc_center(start, end, offset, clockwise, quadrant) {
+        Some(center) => arc_points(start, &center, end, clockwise, tolerance),
+        None => vec![end.clone()],
+    }
+}
+
+/// Finds the absolute center of an arc draw, see [`arc_draw_points`].
+///
+/// # Returns
+///
+/// * `Option<Point>` - The center, or `None` for a single-quadrant arc that
+///   ends where it starts, which draws nothing
+pub fn arc_center(
+    start: &Point,
+    end: &Point,
+    offset: &Point,
+    clockwise: bool,
+    quadrant: QuadrantMode,
+) -> Option<Point> {
+    match quadrant {
+        QuadrantMode::Multi => Some(Point {
+            x: start.x + offset.x,
+            y: start.y + offset.y,
+        }),
+        QuadrantMode::Single => {
+            // A single-quadrant arc cannot be a full circle
+            if (start.x - end.x).abs() < 1e-9 && (start.y - end.y).abs() < 1e-9 {
+                return None;
+            }
+            let radius_error = |center: &Point| {
+                let r_start = (start.x - center.x).hypot(start.y - center.y);
+                let r_end = (end.x - center.x).hypot(end.y - center.y);
+                (r_start - r_end).abs()
+            };
+            let center = [(1.0, 1.0), (1.0, -1.0), (-1.0, 1.0), (-1.0, -1.0)]
+                .iter()
+                .map(|(sx, sy)| Point {
+                    x: start.x + sx * offset.x.abs(),
+                    y: start.y + sy * offset.y.abs(),
+                })
+                .filter(|center| arc_sweep(start, center, end, clockwise).abs() <= PI / 2.0 + 1e-6)
+                .min_by(|a, b| radius_error(a).total_cmp(&radius_error(b)))
+                .unwrap_or(Point {
+                    x: start.x + offset.x,
+                    y: start.y + offset.y,
+                });
+            Some(center)
+        }
+    }
+}
+
+/// Generates the points along a circular arc.
+///
+/// The arc is split into as few equal chords as keep it within `tolerance`
+/// of the true arc (at most 90° each), so small radii get few points and
+/// large radii many. An arc that ends where it starts is a full circle.
+/// The start point is not included; the last point returned is the end point.
+///
+/// # Arguments
+///
+/// * `start` - Start point of the arc
+/// * `center` - Absolute center of the arc
+/// * `end` - End point of the arc
+/// * `clockwise` - Direction of travel (G02 when `true`, G03 otherwise)
+/// * `tolerance` - Maximum distance in mm between the arc and its chords
+pub fn arc_points(
+    start: &Point,
+    center: &Point,
+    end: &Point,
+    clockwise: bool,
+    tolerance: f64,
+) -> Vec<Point> {
+    let start_angle = (start.y - center.y).atan2(start.x - center.x);
+    let radius = ((start.x - center.x).powi(2) + (start.y - center.y).powi(2)).sqrt();
+    let sweep = arc_sweep(start, center, end, clockwise);
+
+    let max_angle = arc_step(radius, tolerance);
+    let segments = ((sweep.abs() / max_angle).ceil() as usize).clamp(1, MAX_ARC_SEGMENTS);
+
+    let mut points = Vec::with_capacity(segments);
+    for i in 1..segments {
+        let angle = start_angle + sweep * (i as f64 / segments as f64);
+        points.push(Point {
+            x: center.x + radius * angle.cos(),
+            y: center.y + radius * angle.sin(),
+        });
+    }
+    points.push(end.clone());
+
+    points
+}
+
+/// Largest angle a chord of an arc of `radius` may span to stay within
+/// `tolerance` of the arc, at most 90°.
+pub fn arc_step(radius: f64, tolerance: f64) -> f64 {
+    // A chord spanning the angle `a` strays r * (1 - cos(a / 2)) from the arc
+    if tolerance < radius {
+        (2.0 * (1.0 - tolerance / radius).acos()).min(PI / 2.0)
+    } else {
+        PI / 2.0
+    }
+}
+
+/// Number of chords that keep a full circle of `radius` within `tolerance`
+/// of the circle, rounded up to a multiple of 4 so that the circle can be
+/// split into quarters (see [`stadium`](crate::geometry::shapes::stadium)).
+pub fn circle_segments(radius: f64, tolerance: f64) -> usize {
+    let segments = (2.0 * PI / arc_step(radius, tolerance)).ceil() as usize;
+    segments.next_multiple_of(4).clamp(4, MAX_ARC_SEGMENTS)
+}
+
+/// Signed angle swept by an arc, negative when clockwise; a full turn when
+/// the arc ends where it starts.
+fn arc_sweep(start: &Point, center: &Point, end: &Point, clockwise: bool) -> f64 {
+    let start_angle = (start.y - center.y).atan2(start.x - center.x);
+    let end_angle = (end.y - center.y).atan2(end.x - center.x);
+
+    let mut sweep = end_angle - start_angle;
+    if clockwise {
+        if sweep >= 0.0 {
+            sweep -= 2.0 * PI;
+        }
+    } else if sweep <= 0.0 {
+        sweep += 2.0 * PI;
+    }
+    sweep
+}
+
+/// Resolves an aperture into its exposed outlines, centered on the origin.
+///
+/// Standard apertures produce a single outline; macro apertures are evaluated
+/// against the macro definitions seen so far. Outlines are counter-clockwise
+/// and in the units of the file they came from.
+///
+/// # Arguments
+///
+/// * `aperture` - The aperture to resolve
+/// * `macros` - Aperture macros defined in the file, by name
+/// * `tolerance` - Maximum chord deviation of arcs, in the units of the file
+///
+/// # Returns
+///
+/// * `Result<Vec<Vec<Point>>, String>` - The outlines, or an error message
+///   when a macro cannot be evaluated or its primitives cannot be composed
+pub fn resolve_aperture(
+    aperture: &Aperture,
+    macros: &HashMap<String, ApertureMacro>,
+    tolerance: f64,
+) -> Result<Vec<Vec<Point>>, String> {
+    let origin = Point { x: 0.0, y: 0.0 };
+    let outlines = match aperture {
+        Aperture::Circle { diameter, hole } => {
+            let radius = diameter / 2.0;
+            let segments = circle_segments(radius, tolerance);
+            with_hole(regular_polygon(&origin, radius, segments, 0.0), *hole, tolerance)?
+        }
+        Aperture::Rectangle {
+            width,
+            height,
+            hole,
+        } => {
+            let (hw, hh) = (width / 2.0, height / 2.0);
+            let outline = vec![
+                Point { x: -hw, y: -hh },
+                Point { x: hw, y: -hh },
+                Point { x: hw, y: hh },
+                Point { x: -hw, y: hh },
+            ];
+            with_hole(outline, *hole, tolerance)?
+        }
+        Aperture::Obround {
+            width,
+            height,
+            hole,
+        } => with_hole(obround(*width, *height, tolerance), *hole, tolerance)?,
+        Aperture::Polygon {
+            diameter,
+            vertices,
+            rotation,
+            hole,
+        } => with_hole(
+            regular_polygon(&origin, diameter / 2.0, *vertices as usize, *rotation),
+            *hole,
+            tolerance,
+        )?,
+        Aperture::Macro { name, params } => {
+            // Primitives are composed in order: exposure off erases what
+            // the previous primitives exposed. Holes are bridged so that
+            // every outline stays a single polygon.
+            let aperture_macro = macros
+                .get(name)
+                .ok_or_else(|| format!("aperture macro {} is not defined", name))?;
+            let shapes = evaluate_macro(aperture_macro, params, tolerance)?;
+            let mut exposed = Vec::new();
+            for shape in shapes {
+                let primitive = [Polygon::new(shape.outline)];
+                exposed = if shape.exposure {
+                    union(&exposed, &primitive)?
+                } else {
+                    difference(&exposed, &primitive)?
+                };
+            }
+            exposed.iter().map(Polygon::to_outline).collect()
+        }
+    };
+
+    Ok(outlines
+        .into_iter()
+        .filter_map(|mut outline| {
+            let area = signed_area(&outline);
+            if outline.len() < 3 || area.abs() < 1e-12 {
+                return None;
+            }
+            if area < 0.0 {
+                outline.reverse();
+            }
+            Some(outline)
+        })
+        .collect())
+}
+
+/// Outline of an obround centered on the origin.
+///
+/// The shorter side is a half circle, flattened with `tolerance` as the
+/// maximum chord deviation; equal sides give a circle.
+fn obround(width: f64, height: f64, tolerance: f64) -> Vec<Point> {
+    let radius = width.min(height) / 2.0;
+    let (dx, dy) = if width >= height {
+        (width / 2.0 - radius, 0.0)
+    } else {
+        (0.0, height / 2.0 - radius)
+    };
+    let segments = circle_segments(radius, tolerance);
+    if dx == 0.0 && dy == 0.0 {
+        return regular_polygon(&Point { x: 0.0, y: 0.0 }, radius, segments, 0.0);
+    }
+
+    // Two half circles facing away from each other, joined by straight sides
+    let half = segments / 2;
+    let start = if width >= height { -PI / 2.0 } else { 0.0 };
+    let mut outline = Vec::with_capacity(2 * (half + 1));
+    for (cx, cy, offset) in [(dx, dy, 0.0), (-dx, -dy, PI)] {
+        for i in 0..=half {
+            let angle = start + offset + PI * i as f64 / half as f64;
+            outline.push(Point {
+                x: cx + radius * angle.cos(),
+                y: cy + radius * angle.sin(),
+            });
+        }
+    }
+    outline
+}
+
+/// Cuts an optional round hole out of an outline centered on the origin.
+///
+/// The hole becomes a hole ring of the aperture polygon, joined to its
+/// outline by a zero-width bridge (see [`Polygon::to_outline`]) so that the
+/// aperture stays a single outline. A hole reaching the edge of the
+/// aperture, which the parser reports, is clipped to it. The hole circle is
+/// flattened with `tolerance` as the maximum chord deviation.
+fn with_hole(
+    outline: Vec<Point>,
+    hole: Option<f64>,
+    tolerance: f64,
+) -> Result<Vec<Vec<Point>>, String> {
+    let radius = match hole {
+        Some(diameter) if diameter > 0.0 => diameter / 2.0,
+        _ => return Ok(vec![outline]),
+    };
+
+    let start = Point { x: radius, y: 0.0 };
+    let ring = arc_points(&start, &Point { x: 0.0, y: 0.0 }, &start, true, tolerance);
+    let pad = difference(&[Polygon::new(outline)], &[Polygon::new(ring)])?;
+    Ok(pad.iter().map(Polygon::to_outline).collect())
+}
+
+/// Places resolved aperture outlines at a point.
+fn flash_at(outlines: &[Vec<Point>], at: &Point) -> Vec<Vec<Point>> {
+    outlines
+        .iter()
+        .map(|outline| {
+            outline
+                .iter()
+                .map(|p| Point {
+                    x: p.x + at.x,
+                    y: p.y + at.y,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Sweeps an aperture along a straight segment.
+///
+/// Standard apertures are convex, so the swept area is the convex hull of the
+/// aperture placed at both ends of the segment.
+fn stroke_segment(outlines: &[Vec<Point>], from: &Point, to: &Point) -> Vec<Point> {
+    let mut points: Vec<Point> = flash_at(outlines, from).into_iter().flatten().collect();
+    points.extend(flash_at(outlines, to).into_iter().flatten());
+    convex_hull(points)
+}
+
+/// Computes the convex hull of a point set (Andrew's monotone chain).
+///
+/// The hull is returned in counter-clockwise order without collinear points.
+fn convex_hull(mut points: Vec<Point>) -> Vec<Point> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup_by(|a, b| a.x == b.x && a.y == b.y);
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross =
+        |o: &Point, a: &Point, b: &Point| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+
+    // Lower chain on the forward pass, upper chain on the way back
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() * 2);
+    for p in &points {
+        while hull.len() >= 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(p.clone());
+    }
+    let lower_len = hull.len() + 1;
+    for p in points.iter().rev().skip(1) {
+        while hull.len() >= lower_len
+            && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0
+        {
+            hull.pop();
+        }
+        hull.push(p.clone());
+    }
+    hull.pop();
+
+    hull
+}
+
+/// Closes the current region contour and stores it if it encloses an area.
+fn push_contour(shapes: &mut Vec<Vec<Point>>, contour: &mut Vec<Point>) {
+    let mut points = std::mem::take(contour);
+
+    // Contours are explicitly closed in Gerber; drop the duplicate end point
+    if points.len() > 1 {
+        let (first, last) = (&points[0], &points[points.len() - 1]);
+        if (first.x - last.x).abs() < 1e-9 && (first.y - last.y).abs() < 1e-9 {
+            points.pop();
+        }
+    }
+
+    if points.len() < 3 {
+        return;
+    }
+
+    let area = signed_area(&points);
+    if area.abs() < 1e-12 {
+        return;
+    }
+    if area < 0.0 {
+        points.reverse();
+    }
+    shapes.push(points);
+}
+
+fn scaled(point: &Point, scale: f64) -> Point {
+    Point {
+        x: point.x * scale,
+        y: point.y * scale,
+    }
+}
+
+fn scale_outlines(outlines: Vec<Vec<Point>>, scale: f64) -> Vec<Vec<Point>> {
+    outlines
+        .into_iter()
+        .map(|outline| outline.iter().map(|p| scaled(p, scale)).collect())
+        .collect()
+}
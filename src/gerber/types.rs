@@ -3,6 +3,8 @@
 //! This module defines the types used to represent Gerber file contents,
 //! including points, apertures, and commands.
 
+use std::collections::HashMap;
+
 /// A 2D point in Gerber coordinates.
 #[derive(Debug, Clone)]
 pub struct Point {
@@ -14,16 +16,143 @@ pub struct Point {
 
 /// Aperture definitions for Gerber files.
 ///
-/// Apertures define shapes used for drawing in Gerber files.
+/// Apertures define shapes used for drawing in Gerber files. The optional
+/// `hole` on standard apertures is the diameter of a round hole in the middle.
 #[derive(Debug, Clone)]
 pub enum Aperture {
     /// Circular aperture with diameter
     #[allow(dead_code)]
-    Circle { diameter: f64 },
+    Circle { diameter: f64, hole: Option<f64> },
     /// Rectangular aperture with width and height
     #[allow(dead_code)]
-    Rectangle { width: f64, height: f64 },
-    // More aperture types can be added later
+    Rectangle { width: f64, height: f64, hole: Option<f64> },
+    /// Obround (stadium) aperture; the shorter side is fully rounded
+    Obround { width: f64, height: f64, hole: Option<f64> },
+    /// Regular polygon aperture with outer diameter, vertex count and rotation in degrees
+    Polygon {
+        diameter: f64,
+        vertices: u32,
+        rotation: f64,
+        hole: Option<f64>,
+    },
+    /// Instance of an aperture macro (%AM) with its actual parameters
+    Macro { name: String, params: Vec<f64> },
+}
+
+/// An arithmetic expression used in aperture macro parameters.
+///
+/// Macro parameters can reference variables (`$1`, `$2`, ...) and combine
+/// them with `+`, `-`, `x` (multiplication) and `/`.
+#[derive(Debug, Clone)]
+pub enum MacroExpr {
+    /// A literal decimal number
+    Number(f64),
+    /// A variable reference (`$n`)
+    Variable(u32),
+    /// Unary minus
+    Negate(Box<MacroExpr>),
+    /// Addition
+    Add(Box<MacroExpr>, Box<MacroExpr>),
+    /// Subtraction
+    Subtract(Box<MacroExpr>, Box<MacroExpr>),
+    /// Multiplication (`x` in Gerber)
+    Multiply(Box<MacroExpr>, Box<MacroExpr>),
+    /// Division
+    Divide(Box<MacroExpr>, Box<MacroExpr>),
+}
+
+/// A primitive inside an aperture macro.
+///
+/// Every parameter is an expression evaluated when the macro is instantiated.
+/// Coordinates are relative to the macro origin, and the rotation (degrees,
+/// counter-clockwise) is applied around that origin.
+#[derive(Debug, Clone)]
+pub enum MacroPrimitive {
+    /// Circle (code 1)
+    Circle {
+        exposure: MacroExpr,
+        diameter: MacroExpr,
+        center_x: MacroExpr,
+        center_y: MacroExpr,
+        rotation: MacroExpr,
+    },
+    /// Line defined by its end points (code 20)
+    VectorLine {
+        exposure: MacroExpr,
+        width: MacroExpr,
+        start_x: MacroExpr,
+        start_y: MacroExpr,
+        end_x: MacroExpr,
+        end_y: MacroExpr,
+        rotation: MacroExpr,
+    },
+    /// Rectangle defined by its center (code 21)
+    CenterLine {
+        exposure: MacroExpr,
+        width: MacroExpr,
+        height: MacroExpr,
+        center_x: MacroExpr,
+        center_y: MacroExpr,
+        rotation: MacroExpr,
+    },
+    /// Closed polygon outline (code 4)
+    Outline {
+        exposure: MacroExpr,
+        /// Vertices, with the last one repeating the first
+        points: Vec<(MacroExpr, MacroExpr)>,
+        rotation: MacroExpr,
+    },
+    /// Regular polygon (code 5)
+    Polygon {
+        exposure: MacroExpr,
+        vertices: MacroExpr,
+        center_x: MacroExpr,
+        center_y: MacroExpr,
+        diameter: MacroExpr,
+        rotation: MacroExpr,
+    },
+    /// Concentric rings with a crosshair (code 6)
+    Moire {
+        center_x: MacroExpr,
+        center_y: MacroExpr,
+        outer_diameter: MacroExpr,
+        ring_thickness: MacroExpr,
+        gap: MacroExpr,
+        max_rings: MacroExpr,
+        crosshair_thickness: MacroExpr,
+        crosshair_length: MacroExpr,
+        rotation: MacroExpr,
+    },
+    /// Ring interrupted by four gaps (code 7)
+    Thermal {
+        center_x: MacroExpr,
+        center_y: MacroExpr,
+        outer_diameter: MacroExpr,
+        inner_diameter: MacroExpr,
+        gap: MacroExpr,
+        rotation: MacroExpr,
+    },
+}
+
+/// A statement in the body of an aperture macro.
+#[derive(Debug, Clone)]
+pub enum MacroStatement {
+    /// A primitive to draw
+    Primitive(MacroPrimitive),
+    /// A variable definition (`$4=$1x0.5`)
+    Assign { variable: u32, value: MacroExpr },
+}
+
+/// An aperture macro definition (%AM).
+///
+/// Macros are templates: `%ADD` instantiates them by name with actual
+/// parameter values, which are bound to `$1`, `$2`, ...
+#[derive(Debug, Clone)]
+pub struct ApertureMacro {
+    /// Name used by `%ADD` to reference the macro
+    pub name: String,
+    /// Primitives and variable definitions, in order
+    pub statements: Vec<MacroStatement>,
 }
 
 /// Interpolation modes for drawing operations.
@@ -39,6 +168,24 @@ pub enum InterpolationMode {
     CounterClockwiseCircular,
 }
 
+/// Quadrant mode for circular interpolation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuadrantMode {
+    /// Arcs of at most 90° with unsigned center offsets (G74)
+    Single,
+    /// Arcs of any angle with signed center offsets (G75)
+    Multi,
+}
+
+/// Load polarity of the objects that follow (`%LPD*%` / `%LPC*%`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadPolarity {
+    /// Objects add to the image
+    Dark,
+    /// Objects erase what was drawn before them
+    Clear,
+}
+
 /// Gerber commands.
 ///
 /// Represents the various commands found in Gerber files,
@@ -59,6 +206,10 @@ pub enum Command {
     SetUnitsInch,
     /// Set interpolation mode (G01, G02, G03)
     SetInterpolationMode(InterpolationMode),
+    /// Set quadrant mode (G74, G75)
+    SetQuadrantMode(QuadrantMode),
+    /// Set load polarity (%LPD*% or %LPC*%)
+    SetPolarity(LoadPolarity),
     /// Move to a point without drawing (D02)
     Move { point: Point },
     /// Draw to a point (D01)
@@ -79,6 +230,16 @@ pub enum Command {
     /// Define an aperture (%ADD10C,0.1*%)
     #[allow(dead_code)]
     DefineAperture { code: u32, aperture: Aperture },
+    /// Define an aperture macro (%AMname*...%)
+    DefineMacro(ApertureMacro),
+    /// Set a file attribute (%TF.FileFunction,Copper,L1,Top*%)
+    FileAttribute { name: String, values: Vec<String> },
+    /// Add an attribute to the dictionary for subsequent apertures (%TA.AperFunction,ViaPad*%)
+    ApertureAttribute { name: String, values: Vec<String> },
+    /// Add an attribute to the dictionary for subsequent objects (%TO.N,GND*%)
+    ObjectAttribute { name: String, values: Vec<String> },
+    /// Delete one attribute, or all aperture and object attributes when `None` (%TD*%)
+    DeleteAttribute { name: Option<String> },
     /// Begin a region (G36)
     BeginRegion,
     /// End a region (G37)
@@ -87,12 +248,91 @@ pub enum Command {
     EndOfFile,
 }
 
+/// Category of a parse diagnostic.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// Valid Gerber that pcbgen does not handle (yet)
+    Unsupported,
+    /// Content that does not follow the Gerber specification
+    Malformed,
+}
+
+/// A block that was skipped or could not be interpreted while parsing.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Line on which the block starts (1-based), 0 when the diagnostic is
+    /// about the layer as a whole, such as an open Edge Cuts contour
+    pub line: usize,
+    /// Column at which the block starts (1-based)
+    pub column: usize,
+    /// Raw text of the block
+    pub text: String,
+    /// Whether the block is unsupported or malformed
+    pub kind: DiagnosticKind,
+    /// Human-readable explanation
+    pub reason: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            DiagnosticKind::Unsupported => "unsupported",
+            DiagnosticKind::Malformed => "malformed",
+        };
+        if self.line == 0 {
+            return write!(f, "[{}]: {} `{}`", kind, self.reason, self.text);
+        }
+        write!(
+            f,
+            "line {}, column {} [{}]: {} `{}`",
+            self.line, self.column, kind, self.reason, self.text
+        )
+    }
+}
+
+/// Gerber X2 attributes by name (e.g. `.FileFunction`), each with its field values.
+pub type Attributes = HashMap<String, Vec<String>>;
+
 /// Complete Gerber file representation.
 ///
-/// Contains all commands parsed from a Gerber file.
-#[allow(dead_code)]
+/// Contains all commands parsed from a Gerber file, along with diagnostics
+/// for every block that was skipped and the X2 attributes resolved against
+/// what they describe.
 #[derive(Debug)]
 pub struct GerberFile {
     /// List of commands in the Gerber file
     pub commands: Vec<Command>,
-}
\ No newline at end of file
+    /// Blocks that were skipped, in file order
+    pub diagnostics: Vec<Diagnostic>,
+    /// File attributes (%TF)
+    pub file_attributes: Attributes,
+    /// Aperture attributes (%TA) in effect when each aperture was defined, by D-code
+    pub aperture_attributes: HashMap<u32, Attributes>,
+    /// Object attributes (%TO) attached to each draw, arc or flash, by command index
+    pub object_attributes: HashMap<usize, Attributes>,
+}
+
+impl GerberFile {
+    /// Values of a file attribute, e.g. `file_attribute(".FileFunction")`.
+    pub fn file_attribute(&self, name: &str) -> Option<&[String]> {
+        self.file_attributes.get(name).map(Vec::as_slice)
+    }
+
+    /// The `.AperFunction` of an aperture (`ComponentPad`, `ViaPad`, `Conductor`, ...).
+    pub fn aperture_function(&self, code: u32) -> Option<&str> {
+        self.aperture_attributes
+            .get(&code)
+            .and_then(|attributes| attributes.get(".AperFunction"))
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// Values of an object attribute attached to the command at `index`,
+    /// e.g. `.N` (net name), `.C` (component reference) or `.P` (pin).
+    pub fn object_attribute(&self, index: usize, name: &str) -> Option<&[String]> {
+        self.object_attributes
+            .get(&index)
+            .and_then(|attributes| attributes.get(name))
+            .map(Vec::as_slice)
+    }
+}
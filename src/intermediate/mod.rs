@@ -3,4 +3,6 @@
 //! This module defines the data structures used to represent a PCB as a 3D model
 //! after parsing the Gerber files but before exporting to USDZ or OBJ formats.
 
-pub mod model;
\ No newline at end of file
+pub mod model;
+pub mod profile;
+pub mod stackup;
\ No newline at end of file
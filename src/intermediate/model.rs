@@ -21,6 +21,9 @@ pub struct Vertex {
 }
 
 /// A face (polygon) in a 3D mesh, defined by indices into a vertex list.
+///
+/// The mesh builders only emit triangles, counter-clockwise seen from the
+/// outside; exporters still accept larger convex or concave polygons.
 #[derive(Debug, Clone)]
 pub struct Face {
     /// Indices into a vertex list that form this face
@@ -36,6 +39,229 @@ pub struct Mesh {
     pub faces: Vec<Face>,
     /// Type of PCB layer this mesh represents
     pub layer_type: LayerType,
+    /// Side of the board the layer is on; `None` for the board itself and
+    /// for what spans it, such as the plated barrels
+    pub side: Option<Side>,
+}
+
+/// Largest angle in degrees between two faces that are shaded smoothly
+/// across their shared edge by [`Mesh::post_process`]; sharper edges are
+/// hard.
+pub const CREASE_ANGLE: f64 = 30.0;
+
+/// Largest distance in mm between vertices merged by [`Mesh::post_process`].
+pub const WELD_EPSILON: f64 = 1e-6;
+
+impl Mesh {
+    /// Prepares a mesh built from extruded outlines for export.
+    ///
+    /// Duplicate vertices are welded, then the normals are recomputed from
+    /// the faces with hard edges split (see [`Mesh::weld`] and
+    /// [`Mesh::split_hard_edges`]), so that walls shade as walls and caps
+    /// as caps.
+    pub fn post_process(&mut self) {
+        self.weld(WELD_EPSILON);
+        self.split_hard_edges(CREASE_ANGLE);
+    }
+
+    /// Merges vertices with the same normal whose positions lie within
+    /// `epsilon` of each other on every axis.
+    ///
+    /// Faces are remapped to the merged vertices; faces left with fewer than
+    /// three distinct vertices are dropped, as are vertices no face uses.
+    ///
+    /// # Arguments
+    ///
+    /// * `epsilon` - Largest distance in mm between merged positions
+    pub fn weld(&mut self, epsilon: f64) {
+        use std::collections::HashMap;
+
+        // Vertices are bucketed in cells of the epsilon size; a match lies
+        // in the same cell or a neighboring one
+        let cell = |value: f64| (value / epsilon).floor() as i64;
+        let mut cells: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        let mut welded: Vec<Vertex> = Vec::new();
+        let mut remap = vec![0; self.vertices.len()];
+
+        for (i, vertex) in self.vertices.iter().enumerate() {
+            let p = &vertex.position;
+            let key = (cell(p.x), cell(p.y), cell(p.z));
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let Some(bucket) = cells.get(&(key.0 + dx, key.1 + dy, key.2 + dz)) else {
+                            continue;
+                        };
+                        found = bucket.iter().copied().find(|&j| {
+                            let other = &welded[j];
+                            within(&other.position, p, epsilon)
+                                && within(&other.normal, &vertex.normal, 1e-9)
+                        });
+                        if found.is_some() {
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            remap[i] = found.unwrap_or_else(|| {
+                welded.push(vertex.clone());
+                cells.entry(key).or_default().push(welded.len() - 1);
+                welded.len() - 1
+            });
+        }
+
+        self.vertices = welded;
+        for face in &mut self.faces {
+            for index in &mut face.vertices {
+                *index = remap[*index];
+            }
+            face.vertices.dedup();
+            while face.vertices.len() > 1 && face.vertices.first() == face.vertices.last() {
+                face.vertices.pop();
+            }
+        }
+        self.faces.retain(|face| face.vertices.len() >= 3);
+        self.remove_unused_vertices();
+    }
+
+    /// Recomputes the vertex normals from the faces, splitting vertices at
+    /// hard edges.
+    ///
+    /// Each corner of a face takes the area-weighted average normal of the
+    /// faces around its vertex that lie within `crease_angle` of its own
+    /// face. Corners of a vertex that end up with different normals get a
+    /// vertex each, so a wall meeting a cap keeps a sharp edge while the
+    /// segments of a round hole shade as one smooth surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `crease_angle` - Largest angle in degrees between faces shaded smoothly
+    pub fn split_hard_edges(&mut self, crease_angle: f64) {
+        let threshold = crease_angle.to_radians().cos();
+
+        // Newell's method: the length of the vector is twice the face area
+        let face_normals: Vec<Point3D> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let n = face.vertices.len();
+                let mut normal = Point3D {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                };
+                for i in 0..n {
+                    let a = &self.vertices[face.vertices[i]].position;
+                    let b = &self.vertices[face.vertices[(i + 1) % n]].position;
+                    normal.x += (a.y - b.y) * (a.z + b.z);
+                    normal.y += (a.z - b.z) * (a.x + b.x);
+                    normal.z += (a.x - b.x) * (a.y + b.y);
+                }
+                normal
+            })
+            .collect();
+        let unit_normals: Vec<Option<Point3D>> = face_normals.iter().map(normalized).collect();
+
+        let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); self.vertices.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in &face.vertices {
+                vertex_faces[v].push(f);
+            }
+        }
+
+        let mut vertices: Vec<Vertex> = Vec::new();
+        // Vertices created for each original vertex, by normal
+        let mut created: Vec<Vec<usize>> = vec![Vec::new(); self.vertices.len()];
+        for f in 0..self.faces.len() {
+            for corner in 0..self.faces[f].vertices.len() {
+                let v = self.faces[f].vertices[corner];
+                let sum = vertex_faces[v]
+                    .iter()
+                    .filter(|&&g| match (&unit_normals[f], &unit_normals[g]) {
+                        (Some(own), Some(other)) => dot(own, other) >= threshold,
+                        // A degenerate face takes the normal of its surroundings
+                        (None, _) => true,
+                        (_, None) => false,
+                    })
+                    .fold(
+                        Point3D {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                        |sum, &g| Point3D {
+                            x: sum.x + face_normals[g].x,
+                            y: sum.y + face_normals[g].y,
+                            z: sum.z + face_normals[g].z,
+                        },
+                    );
+                let normal = normalized(&sum).unwrap_or_else(|| self.vertices[v].normal.clone());
+
+                let existing = created[v]
+                    .iter()
+                    .copied()
+                    .find(|&i| within(&vertices[i].normal, &normal, 1e-9));
+                self.faces[f].vertices[corner] = existing.unwrap_or_else(|| {
+                    vertices.push(Vertex {
+                        position: self.vertices[v].position.clone(),
+                        normal,
+                    });
+                    created[v].push(vertices.len() - 1);
+                    vertices.len() - 1
+                });
+            }
+        }
+        self.vertices = vertices;
+    }
+
+    /// Drops the vertices no face uses, keeping the order of the others.
+    fn remove_unused_vertices(&mut self) {
+        let mut used = vec![false; self.vertices.len()];
+        for face in &self.faces {
+            for &index in &face.vertices {
+                used[index] = true;
+            }
+        }
+        let mut remap = vec![0; self.vertices.len()];
+        let mut kept = 0;
+        for (i, &is_used) in used.iter().enumerate() {
+            remap[i] = kept;
+            if is_used {
+                self.vertices.swap(kept, i);
+                kept += 1;
+            }
+        }
+        self.vertices.truncate(kept);
+        for face in &mut self.faces {
+            for index in &mut face.vertices {
+                *index = remap[*index];
+            }
+        }
+    }
+}
+
+/// Returns `true` if two points lie within `epsilon` of each other on every axis.
+fn within(a: &Point3D, b: &Point3D, epsilon: f64) -> bool {
+    (a.x - b.x).abs() <= epsilon && (a.y - b.y).abs() <= epsilon && (a.z - b.z).abs() <= epsilon
+}
+
+/// Dot product of two vectors.
+fn dot(a: &Point3D, b: &Point3D) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+/// The vector scaled to unit length, or `None` for a zero vector.
+fn normalized(v: &Point3D) -> Option<Point3D> {
+    let length = dot(v, v).sqrt();
+    if length == 0.0 {
+        return None;
+    }
+    Some(Point3D {
+        x: v.x / length,
+        y: v.y / length,
+        z: v.z / length,
+    })
 }
 
 /// Enumeration of PCB layer types.
@@ -54,10 +280,44 @@ pub enum LayerType {
     /// Board outline (edge cuts)
     EdgeCuts,
     /// Drill holes layer
-    #[allow(dead_code)]
     Drill,
 }
 
+/// Side of the board a layer belongs to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    /// Top (component) side
+    Top,
+    /// Inner layer of a multilayer board
+    Inner,
+    /// Bottom (solder) side
+    Bottom,
+}
+
+/// How a layer was identified.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayerSource {
+    /// From the Gerber X2 `.FileFunction` attribute
+    FileFunction,
+    /// From the `FilesAttributes` of a Gerber job file
+    JobFile,
+    /// Guessed from the file name
+    FileName,
+}
+
+/// What a Gerber file contains: its layer type, side and copper index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerInfo {
+    /// Type of PCB layer
+    pub layer_type: LayerType,
+    /// Side of the board, for layers that have one
+    pub side: Option<Side>,
+    /// Copper layer number counted from the top (1 = top), for copper layers
+    pub copper_index: Option<u32>,
+    /// Whether the layer came from file attributes or a file name guess
+    pub source: LayerSource,
+}
+
 /// A complete PCB model composed of multiple layer meshes.
 #[derive(Debug)]
 pub struct PCBModel {
@@ -66,6 +326,27 @@ pub struct PCBModel {
     /// Units used for coordinates (mm or inches)
     #[allow(dead_code)]
     pub units: Units,
+    /// Board-level information, from the job file when there is one
+    pub metadata: BoardMetadata,
+}
+
+/// Board-level information about a PCB model.
+#[derive(Debug, Clone, Default)]
+pub struct BoardMetadata {
+    /// Project name
+    pub project: Option<String>,
+    /// Project revision
+    pub revision: Option<String>,
+    /// CAD tool that generated the fabrication files
+    pub generator: Option<String>,
+    /// Board bounding box size (X, Y) in mm
+    pub size: Option<(f64, f64)>,
+    /// Number of copper layers
+    pub layer_count: Option<u32>,
+    /// Board thickness used for the model in mm
+    pub thickness: f64,
+    /// Surface finish
+    pub finish: Option<String>,
 }
 
 /// Units of measurement for PCB coordinates.
@@ -0,0 +1,259 @@
+//! Board profiles with exact arcs, for CAD exchange formats.
+//!
+//! Layers are composed as polygons, in which arcs and round holes are
+//! flattened into short chords. Mechanical CAD formats such as STEP describe
+//! solids by their exact surfaces instead, so that a drilled hole is a
+//! cylinder. A [`Profile`] restores the arcs of a polygon: every run of
+//! chords that lies on a known [`Circle`], such as an Edge Cuts arc or a
+//! drill hole, becomes a single arc edge.
+
+use std::collections::HashMap;
+
+use crate::geometry::polygon::Polygon;
+use crate::gerber::types::Point;
+use crate::intermediate::model::BoardMetadata;
+
+/// Largest distance in mm between a chord end and a circle for the chord to
+/// be taken as part of the circle; polygon vertices are snapped to a 1 nm
+/// grid.
+pub const CIRCLE_TOLERANCE: f64 = 1e-5;
+
+/// Size in mm of the grid cells that circles are looked up in.
+const CELL_SIZE: f64 = 1.0;
+
+/// Circles larger than this radius in mm are checked against every chord
+/// rather than entered in the grid.
+const LARGE_RADIUS: f64 = 8.0;
+
+/// A circle that the chords of a polygon may have been cut from.
+#[derive(Debug, Clone)]
+pub struct Circle {
+    /// Center of the circle
+    pub center: Point,
+    /// Radius in mm
+    pub radius: f64,
+    /// Largest angle in radians spanned by one chord of the circle, which
+    /// tells arcs apart from straight edges that happen to end on the circle
+    pub max_step: f64,
+}
+
+/// An edge of a profile boundary.
+#[derive(Debug, Clone)]
+pub enum ProfileEdge {
+    /// A straight edge
+    Line { start: Point, end: Point },
+    /// A circular arc; a full circle when it ends where it starts
+    Arc {
+        start: Point,
+        end: Point,
+        center: Point,
+        radius: f64,
+        clockwise: bool,
+    },
+}
+
+impl ProfileEdge {
+    /// Start point of the edge.
+    pub fn start(&self) -> &Point {
+        match self {
+            ProfileEdge::Line { start, .. } | ProfileEdge::Arc { start, .. } => start,
+        }
+    }
+
+    /// End point of the edge.
+    pub fn end(&self) -> &Point {
+        match self {
+            ProfileEdge::Line { end, .. } | ProfileEdge::Arc { end, .. } => end,
+        }
+    }
+}
+
+/// A polygon with its arcs restored: an outer boundary and holes made of
+/// lines and arcs, with the winding of [`Polygon`].
+#[derive(Debug, Clone)]
+pub struct Profile {
+    /// Outer boundary, counter-clockwise
+    pub outer: Vec<ProfileEdge>,
+    /// Holes, clockwise
+    pub holes: Vec<Vec<ProfileEdge>>,
+}
+
+impl Profile {
+    /// Every boundary of the profile: the outer one first, then the holes.
+    pub fn contours(&self) -> impl Iterator<Item = &Vec<ProfileEdge>> {
+        std::iter::once(&self.outer).chain(&self.holes)
+    }
+}
+
+/// A profile extruded between two heights.
+#[derive(Debug, Clone)]
+pub struct Prism {
+    /// Layer name, as used for materials (`Board`, `TopCopper`, ...)
+    pub name: String,
+    /// Cross section of the solid
+    pub profile: Profile,
+    /// Z coordinate of the bottom face
+    pub z_bottom: f64,
+    /// Z coordinate of the top face
+    pub z_top: f64,
+}
+
+/// A PCB as exact solids: the board, and optionally its copper.
+#[derive(Debug, Clone)]
+pub struct SolidModel {
+    /// The solids, the board first
+    pub prisms: Vec<Prism>,
+    /// Board-level information
+    pub metadata: BoardMetadata,
+}
+
+/// Restores the arcs of polygons.
+///
+/// Each boundary is split into edges: a run of consecutive chords lying on
+/// the same circle, running the same way around it, becomes one arc, and a
+/// boundary lying entirely on a circle becomes a full circle. The other
+/// chords stay straight edges.
+///
+/// # Arguments
+///
+/// * `polygons` - The polygons, as composed for the layer
+/// * `circles` - The circles that arcs of the polygons may lie on
+///
+/// # Returns
+///
+/// * `Vec<Profile>` - One profile per polygon
+pub fn profiles(polygons: &[Polygon], circles: &[Circle]) -> Vec<Profile> {
+    let index = CircleIndex::new(circles);
+    polygons
+        .iter()
+        .map(|polygon| Profile {
+            outer: restore_arcs(&polygon.outer, &index),
+            holes: polygon
+                .holes
+                .iter()
+                .map(|hole| restore_arcs(hole, &index))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Splits a closed boundary into lines and arcs, see [`profiles`].
+fn restore_arcs(points: &[Point], index: &CircleIndex) -> Vec<ProfileEdge> {
+    let n = points.len();
+    let next = |i: usize| points[(i + 1) % n].clone();
+
+    // The circle each chord lies on, and whether it runs clockwise
+    let on: Vec<Option<(usize, bool)>> = (0..n)
+        .map(|i| index.chord_circle(&points[i], &points[(i + 1) % n]))
+        .collect();
+    let arc = |start: &Point, end: Point, (circle, clockwise): (usize, bool)| ProfileEdge::Arc {
+        start: start.clone(),
+        end,
+        center: index.circles[circle].center.clone(),
+        radius: index.circles[circle].radius,
+        clockwise,
+    };
+
+    if n == 0 {
+        return Vec::new();
+    }
+    if let Some(first) = on[0]
+        && on.iter().all(|chord| *chord == Some(first))
+    {
+        return vec![arc(&points[0], points[0].clone(), first)];
+    }
+
+    // Start where an edge starts: at a line, or at the first chord of an arc
+    let first = (0..n)
+        .find(|&i| on[i].is_none() || on[i] != on[(i + n - 1) % n])
+        .unwrap_or(0);
+    let mut edges = Vec::new();
+    let mut i = 0;
+    while i < n {
+        let k = (first + i) % n;
+        match on[k] {
+            None => {
+                edges.push(ProfileEdge::Line {
+                    start: points[k].clone(),
+                    end: next(k),
+                });
+                i += 1;
+            }
+            Some(circle) => {
+                let mut j = i + 1;
+                while j < n && on[(first + j) % n] == on[k] {
+                    j += 1;
+                }
+                edges.push(arc(&points[k], next((first + j - 1) % n), circle));
+                i = j;
+            }
+        }
+    }
+    edges
+}
+
+/// Circles bucketed in a grid, to find the circle a chord lies on.
+struct CircleIndex<'a> {
+    circles: &'a [Circle],
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    large: Vec<usize>,
+}
+
+impl<'a> CircleIndex<'a> {
+    fn new(circles: &'a [Circle]) -> CircleIndex<'a> {
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        let mut large = Vec::new();
+        for (i, circle) in circles.iter().enumerate() {
+            if circle.radius > LARGE_RADIUS {
+                large.push(i);
+                continue;
+            }
+            let reach = circle.radius + CIRCLE_TOLERANCE;
+            let (x0, y0) = cell(&Point {
+                x: circle.center.x - reach,
+                y: circle.center.y - reach,
+            });
+            let (x1, y1) = cell(&Point {
+                x: circle.center.x + reach,
+                y: circle.center.y + reach,
+            });
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    cells.entry((x, y)).or_default().push(i);
+                }
+            }
+        }
+        CircleIndex {
+            circles,
+            cells,
+            large,
+        }
+    }
+
+    /// The first circle that a chord lies on, and whether the chord runs
+    /// clockwise around it.
+    fn chord_circle(&self, from: &Point, to: &Point) -> Option<(usize, bool)> {
+        let nearby = self.cells.get(&cell(from)).into_iter().flatten();
+        nearby.chain(&self.large).find_map(|&i| {
+            let circle = &self.circles[i];
+            let (ax, ay) = (from.x - circle.center.x, from.y - circle.center.y);
+            let (bx, by) = (to.x - circle.center.x, to.y - circle.center.y);
+            let on_circle = |x: f64, y: f64| (x.hypot(y) - circle.radius).abs() <= CIRCLE_TOLERANCE;
+            if !on_circle(ax, ay) || !on_circle(bx, by) {
+                return None;
+            }
+            // Ends off the circle by the tolerance widen the angle a little
+            let angle = (ax * by - ay * bx).atan2(ax * bx + ay * by);
+            let max_angle = circle.max_step + 2.0 * CIRCLE_TOLERANCE / circle.radius;
+            (angle != 0.0 && angle.abs() <= max_angle).then_some((i, angle < 0.0))
+        })
+    }
+}
+
+/// The grid cell of a point.
+fn cell(point: &Point) -> (i64, i64) {
+    (
+        (point.x / CELL_SIZE).floor() as i64,
+        (point.y / CELL_SIZE).floor() as i64,
+    )
+}
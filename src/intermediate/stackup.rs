@@ -0,0 +1,262 @@
+//! Board stackup: the physical layers of a PCB and their Z positions.
+//!
+//! A stackup lists the layers from top to bottom (silkscreen, solder mask,
+//! copper and dielectric) with their thicknesses. The board core, from the
+//! bottom of the top copper to the top of the bottom copper, spans
+//! `0..board_thickness`; outer copper, mask and silkscreen sit outside it,
+//! and inner copper layers sit between the dielectrics inside it.
+
+use crate::gerber::job::StackupEntry;
+use crate::intermediate::model::Side;
+use crate::{COPPER_THICKNESS, SILKSCREEN_THICKNESS, SOLDERMASK_THICKNESS};
+
+/// Kind of physical layer in a stackup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StackupLayerKind {
+    /// Silkscreen ink
+    Silkscreen,
+    /// Solder mask coating
+    Soldermask,
+    /// Copper foil
+    Copper,
+    /// Core or prepreg between copper layers
+    Dielectric,
+}
+
+/// A layer of the stackup with its position.
+#[derive(Debug, Clone)]
+pub struct StackupLayer {
+    /// Kind of layer
+    pub kind: StackupLayerKind,
+    /// Display name, e.g. `F.Cu` or `Top Solder Mask`
+    pub name: String,
+    /// Side of the board; inner copper and dielectrics are `Inner`
+    pub side: Side,
+    /// Copper layer number counted from the top (1 = top), for copper layers
+    pub copper_index: Option<u32>,
+    /// Material, e.g. `FR4`
+    pub material: Option<String>,
+    /// Thickness in mm
+    pub thickness: f64,
+    /// Z coordinate of the bottom of the layer
+    pub z_bottom: f64,
+    /// Z coordinate of the top of the layer
+    pub z_top: f64,
+}
+
+/// The layers of a board from top to bottom.
+#[derive(Debug, Clone)]
+pub struct Stackup {
+    /// Layers in order from the top of the board
+    pub layers: Vec<StackupLayer>,
+}
+
+impl Stackup {
+    /// A typical stackup with `copper_layers` copper layers.
+    ///
+    /// Dielectrics share the board thickness evenly, and every side gets a
+    /// solder mask and a silkscreen layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `copper_layers` - Number of copper layers (at least 1)
+    /// * `board_thickness` - Thickness of the board core in mm
+    ///
+    /// # Returns
+    ///
+    /// * `Stackup` - The positioned stackup
+    pub fn with_copper_layers(copper_layers: u32, board_thickness: f64) -> Stackup {
+        let copper_layers = copper_layers.max(1);
+        let mut entries = vec![
+            StackupEntry::new("Legend", "Top Silk Screen"),
+            StackupEntry::new("SolderMask", "Top Solder Mask"),
+        ];
+        for index in 1..=copper_layers {
+            if index > 1 {
+                entries.push(StackupEntry::new("Dielectric", "Dielectric"));
+            }
+            let name = match index {
+                1 => "F.Cu".to_string(),
+                _ if index == copper_layers => "B.Cu".to_string(),
+                _ => format!("In{}.Cu", index - 1),
+            };
+            entries.push(StackupEntry::new("Copper", &name));
+        }
+        entries.push(StackupEntry::new("SolderMask", "Bottom Solder Mask"));
+        entries.push(StackupEntry::new("Legend", "Bottom Silk Screen"));
+
+        Stackup::from_entries(&entries, board_thickness)
+    }
+
+    /// Builds a stackup from the `MaterialStackup` entries of a job file.
+    ///
+    /// Layers without a thickness get a default one; dielectrics without a
+    /// thickness share what is left of the board thickness once the inner
+    /// layers with known thicknesses are accounted for. If every dielectric
+    /// has a thickness they are scaled to fit the board thickness instead.
+    /// Entries of other types (e.g. solder paste) are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Stackup entries from top to bottom
+    /// * `board_thickness` - Thickness of the board core in mm
+    ///
+    /// # Returns
+    ///
+    /// * `Stackup` - The positioned stackup
+    pub fn from_entries(entries: &[StackupEntry], board_thickness: f64) -> Stackup {
+        let mut layers: Vec<StackupLayer> = entries
+            .iter()
+            .filter_map(|entry| {
+                let (kind, default_thickness) = match entry.layer_type.as_str() {
+                    "Legend" => (StackupLayerKind::Silkscreen, SILKSCREEN_THICKNESS),
+                    "SolderMask" => (StackupLayerKind::Soldermask, SOLDERMASK_THICKNESS),
+                    "Copper" => (StackupLayerKind::Copper, COPPER_THICKNESS),
+                    "Dielectric" => (StackupLayerKind::Dielectric, f64::NAN),
+                    _ => return None,
+                };
+                Some(StackupLayer {
+                    kind,
+                    name: entry.name.clone(),
+                    side: Side::Inner,
+                    copper_index: None,
+                    material: entry.material.clone(),
+                    thickness: entry.thickness.unwrap_or(default_thickness),
+                    z_bottom: 0.0,
+                    z_top: 0.0,
+                })
+            })
+            .collect();
+
+        // Number the copper layers and find the core between the outer ones
+        let coppers: Vec<usize> = (0..layers.len())
+            .filter(|&i| layers[i].kind == StackupLayerKind::Copper)
+            .collect();
+        for (n, &i) in coppers.iter().enumerate() {
+            layers[i].copper_index = Some(n as u32 + 1);
+        }
+        let (first, last) = match (coppers.first(), coppers.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return Stackup { layers },
+        };
+        for (i, layer) in layers.iter_mut().enumerate() {
+            layer.side = if i <= first {
+                Side::Top
+            } else if i >= last {
+                Side::Bottom
+            } else {
+                Side::Inner
+            };
+        }
+
+        // Unknown dielectrics take an even share of the rest of the core
+        let core = first + 1..last;
+        let unknown = core
+            .clone()
+            .filter(|&i| layers[i].thickness.is_nan())
+            .count();
+        let known: f64 = core
+            .clone()
+            .filter(|&i| !layers[i].thickness.is_nan())
+            .map(|i| layers[i].thickness)
+            .sum();
+        let share = ((board_thickness - known) / unknown.max(1) as f64).max(0.0);
+        for layer in &mut layers {
+            if layer.thickness.is_nan() {
+                layer.thickness = share;
+            }
+        }
+
+        // When every dielectric is known, scale them so that the core still
+        // matches the board thickness (e.g. after a thickness override)
+        let dielectric: f64 = core
+            .clone()
+            .filter(|&i| layers[i].kind == StackupLayerKind::Dielectric)
+            .map(|i| layers[i].thickness)
+            .sum();
+        if unknown == 0 && dielectric > 0.0 {
+            let scale = ((board_thickness - known + dielectric) / dielectric).max(0.0);
+            for i in core.clone() {
+                if layers[i].kind == StackupLayerKind::Dielectric {
+                    layers[i].thickness *= scale;
+                }
+            }
+        }
+
+        // Walk down from the top so that the core ends at Z = 0. With a
+        // single copper layer the core is the whole board, below the copper.
+        let core_thickness: f64 = if first < last {
+            core.map(|i| layers[i].thickness).sum()
+        } else {
+            board_thickness
+        };
+        let mut z = core_thickness
+            + layers[..=first]
+                .iter()
+                .map(|layer| layer.thickness)
+                .sum::<f64>();
+        for (i, layer) in layers.iter_mut().enumerate() {
+            if i == first + 1 && first == last {
+                z = 0.0;
+            }
+            layer.z_top = z;
+            z -= layer.thickness;
+            layer.z_bottom = z;
+        }
+
+        Stackup { layers }
+    }
+
+    /// Number of copper layers.
+    pub fn copper_count(&self) -> u32 {
+        self.layers
+            .iter()
+            .filter(|layer| layer.kind == StackupLayerKind::Copper)
+            .count() as u32
+    }
+
+    /// The copper layer with the given index, or the outer copper layer of
+    /// the given side when the index is unknown or not in the stackup.
+    pub fn copper(&self, side: Option<Side>, index: Option<u32>) -> Option<&StackupLayer> {
+        let mut coppers = self
+            .layers
+            .iter()
+            .filter(|layer| layer.kind == StackupLayerKind::Copper);
+        if let Some(index) = index
+            && let Some(layer) = coppers
+                .clone()
+                .find(|layer| layer.copper_index == Some(index))
+        {
+            return Some(layer);
+        }
+        match side {
+            Some(Side::Top) => coppers.next(),
+            Some(Side::Bottom) => coppers.next_back(),
+            _ => None,
+        }
+    }
+
+    /// The outermost layer of a kind on one side of the board (e.g. the top
+    /// silkscreen).
+    pub fn outer(&self, kind: StackupLayerKind, side: Side) -> Option<&StackupLayer> {
+        let mut layers = self
+            .layers
+            .iter()
+            .filter(|layer| layer.kind == kind && layer.side == side);
+        match side {
+            Side::Bottom => layers.next_back(),
+            _ => layers.next(),
+        }
+    }
+
+    /// Z range spanned by the whole stackup.
+    pub fn z_range(&self) -> (f64, f64) {
+        let bottom = self
+            .layers
+            .last()
+            .map(|layer| layer.z_bottom)
+            .unwrap_or(0.0);
+        let top = self.layers.first().map(|layer| layer.z_top).unwrap_or(0.0);
+        (bottom, top)
+    }
+}
@@ -8,12 +8,28 @@
 //! - `gerber`: Module for parsing Gerber files
 //!   - `types.rs`: Definitions of Gerber file structures and commands
 //!   - `parse.rs`: Parser for Gerber file format using nom
+//!   - `macros.rs`: Evaluation of aperture macros into shapes
+//!   - `plot.rs`: Replays commands into 2D outlines (strokes, flashes, regions)
+//!
+//! - `excellon`: Module for parsing Excellon drill files
+//!   - `types.rs`: Definitions of tools and holes
+//!   - `parse.rs`: Line-based parser for the Excellon format
+//!
+//! - `geometry`: Module for 2D polygon operations
+//!   - `polygon.rs`: Polygons with holes
+//!   - `boolean.rs`: Union, difference and intersection of polygon sets
+//!   - `offset.rs`: Growing and shrinking polygons
+//!   - `shapes.rs`: Regular polygons and stadiums
+//!   - `triangulate.rs`: Ear clipping of polygon caps into triangles
 //!
 //! - `intermediate`: Module for 3D model representation
 //!   - `model.rs`: Definitions of 3D mesh structures (vertices, faces, etc.)
+//!   - `profile.rs`: Board profiles with exact arcs, for CAD export
+//!   - `stackup.rs`: Physical layer stackup and the Z position of each layer
 //!
 //! - `usdz`: Module for USDZ file generation
 //!   - `export.rs`: Export functions for USDZ and OBJ formats
+//!   - `step.rs`: STEP export of the board solid for mechanical CAD
 //!
 //! ## Workflow
 //!
@@ -23,188 +39,521 @@
 //! 4. Combine meshes into a complete PCB model
 //! 5. Export to USDZ or OBJ format based on user preference
 
+pub mod excellon;
+pub mod geometry;
 pub mod gerber;
 pub mod intermediate;
 pub mod usdz;
 
-use intermediate::model::{LayerType, Mesh, PCBModel, Units};
+use geometry::boolean::{difference, union};
+use geometry::polygon::Polygon;
+use gerber::job::Polarity;
+use gerber::types::{Diagnostic, DiagnosticKind, GerberFile};
+use intermediate::model::{
+    BoardMetadata, LayerInfo, LayerSource, LayerType, Mesh, PCBModel, Side, Units,
+};
+use intermediate::profile::{Circle, SolidModel};
+use intermediate::stackup::{Stackup, StackupLayer, StackupLayerKind};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Standard copper thickness in mm (1 oz/ft²).
+pub const COPPER_THICKNESS: f64 = 0.035;
+
+/// Copper plating thickness on the walls of plated holes in mm.
+pub const PLATING_THICKNESS: f64 = 0.025;
+
+/// Standard solder mask thickness in mm.
+pub const SOLDERMASK_THICKNESS: f64 = 0.01;
+
+/// Standard silkscreen ink thickness in mm.
+pub const SILKSCREEN_THICKNESS: f64 = 0.01;
+
+/// Default PCB thickness in mm, when neither the options nor a job file give one.
+pub const DEFAULT_THICKNESS: f64 = 1.6;
+
+/// Largest gap in mm between Edge Cuts segments that are chained into one contour.
+pub const OUTLINE_TOLERANCE: f64 = 0.01;
+
+/// Options controlling how Gerber files are turned into a PCB model.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessOptions {
+    /// PCB thickness in mm; `None` takes it from the job file, or
+    /// [`DEFAULT_THICKNESS`] without one
+    pub thickness: Option<f64>,
+    /// Fail on any Gerber block that cannot be parsed instead of skipping it,
+    /// on any Edge Cuts contour that is not closed, and on any layer that
+    /// cannot be plotted instead of leaving it out
+    pub strict: bool,
+    /// Stackup file (JSON with a `MaterialStackup` array, as in a job file)
+    /// overriding the job file stackup
+    pub stackup: Option<String>,
+    /// Maximum chord deviation of arcs in mm; `None` uses
+    /// [`gerber::plot::ARC_TOLERANCE`]
+    pub arc_tolerance: Option<f64>,
+}
+
+/// Process Gerber files to create a 3D PCB model.
+///
+/// Shorthand for [`process_gerber_files_with_options`] with the given
+/// thickness and default options otherwise.
+///
+/// # Arguments
+///
+/// * `input_dir` - Directory containing Gerber files
+/// * `thickness` - PCB thickness in mm
+///
+/// # Returns
+///
+/// * `Result<PCBModel, String>` - The complete PCB model on success, or an error message
+pub fn process_gerber_files(input_dir: &str, thickness: f64) -> Result<PCBModel, String> {
+    let options = ProcessOptions {
+        thickness: Some(thickness),
+        ..ProcessOptions::default()
+    };
+    process_gerber_files_with_options(input_dir, &options)
+}
+
 /// Process Gerber files to create a 3D PCB model.
 ///
 /// This function:
 /// 1. Reads each Gerber file for different PCB layers (Edge Cuts, Copper, Silkscreen, etc.)
+///    and the Gerber job file (`.gbrjob`), if any, which takes precedence for
+///    layer mapping and board thickness
 /// 2. Parses the Gerber commands
-/// 3. Converts each layer to a 3D mesh
+/// 3. Converts each layer to a 3D mesh, with welded vertices and per-face
+///    normals (see [`Mesh::post_process`])
 /// 4. Combines meshes into a complete PCB model
 ///
 /// # Arguments
 ///
 /// * `input_dir` - Directory containing Gerber files
-/// * `thickness` - PCB thickness in mm
+/// * `options` - Conversion options (thickness, strict parsing, stackup, arc tolerance)
 ///
 /// # Returns
 ///
 /// * `Result<PCBModel, String>` - The complete PCB model on success, or an error message
-pub fn process_gerber_files(input_dir: &str, thickness: f64) -> Result<PCBModel, String> {
-    let input_path = Path::new(input_dir);
-
-    // Check if the input directory exists
-    if !input_path.exists() || !input_path.is_dir() {
-        return Err(format!("Input directory does not exist: {}", input_dir));
-    }
+pub fn process_gerber_files_with_options(
+    input_dir: &str,
+    options: &ProcessOptions,
+) -> Result<PCBModel, String> {
+    // Parse the job file and every layer and drill file, and lay out the
+    // stackup
+    let Board {
+        job,
+        layers,
+        holes,
+        thickness,
+        arc_tolerance,
+        stackup,
+    } = read_board(input_dir, options)?;
 
     // Create a PCB model
     let mut pcb_model = PCBModel {
         meshes: Vec::new(),
         units: Units::Millimeters, // Default to mm
+        metadata: board_metadata(job.as_ref(), thickness),
     };
 
-    // Find and process Gerber files
-    let entries =
-        fs::read_dir(input_path).map_err(|e| format!("Error reading directory: {}", e))?;
-
-    // Collect file paths by layer type
-    let mut edge_cuts_file: Option<PathBuf> = None;
-    let mut top_copper_file: Option<PathBuf> = None;
-    let mut bottom_copper_file: Option<PathBuf> = None;
-    let mut top_silk_file: Option<PathBuf> = None;
-    let mut bottom_silk_file: Option<PathBuf> = None;
-
-    // First pass: categorize files by their likely layer type
-    for entry in entries {
-        if let Ok(entry) = entry {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if ext == "gbr" || ext == "GBR" {
-                    let file_name = path.file_name().unwrap().to_string_lossy().to_lowercase();
-
-                    // Categorize by common naming conventions
-                    if file_name.contains("edge")
-                        || file_name.contains("outline")
-                        || file_name.contains("cuts")
-                    {
-                        edge_cuts_file = Some(path.clone());
-                    } else if file_name.contains("f.cu")
-                        || file_name.contains("f_cu")
-                        || file_name.contains("top.cu")
-                    {
-                        top_copper_file = Some(path.clone());
-                    } else if file_name.contains("b.cu")
-                        || file_name.contains("b_cu")
-                        || file_name.contains("bottom.cu")
-                    {
-                        bottom_copper_file = Some(path.clone());
-                    } else if file_name.contains("f.silk")
-                        || file_name.contains("f_silk")
-                        || file_name.contains("top.silk")
-                    {
-                        top_silk_file = Some(path.clone());
-                    } else if file_name.contains("b.silk")
-                        || file_name.contains("b_silk")
-                        || file_name.contains("bottom.silk")
-                    {
-                        bottom_silk_file = Some(path.clone());
-                    }
-                }
-            }
-        }
-    }
+    let edge_cuts_file = layers
+        .iter()
+        .find(|(_, info, _)| info.layer_type == LayerType::EdgeCuts)
+        .map(|(path, _, gerber)| (path, gerber));
 
     // Process Edge Cuts layer first (required for PCB outline)
-    if let Some(path) = edge_cuts_file {
-        println!("Processing Edge Cuts layer: {:?}", path);
-        let edge_cuts_commands = read_and_parse_gerber(path.to_str().unwrap())?;
-        let edge_cuts_mesh = build_edge_cuts_mesh(&edge_cuts_commands, Some(thickness))?;
-        pcb_model.meshes.push(edge_cuts_mesh);
-    } else {
+    let Some((path, edge_cuts)) = edge_cuts_file else {
         return Err("Edge Cuts layer not found. This is required for the PCB outline.".to_string());
+    };
+    let outline = edge_cuts_outline(path, &edge_cuts.commands, arc_tolerance, options.strict)?;
+    let edge_cuts_mesh =
+        build_board_mesh_from_outline(&outline, &holes, Some(thickness), arc_tolerance)?;
+    pcb_model.meshes.push(edge_cuts_mesh);
+
+    // Copper barrels of plated holes
+    let barrels = build_plated_barrels_mesh(&holes, &stackup, arc_tolerance)?;
+    if !barrels.faces.is_empty() {
+        println!(
+            "Plated hole barrels mesh created with {} vertices and {} faces",
+            barrels.vertices.len(),
+            barrels.faces.len()
+        );
+        pcb_model.meshes.push(barrels);
     }
 
-    // Process copper layers
-    if let Some(path) = top_copper_file {
-        println!("Processing top copper layer: {:?}", path);
-        if let Ok(commands) = read_and_parse_gerber(path.to_str().unwrap()) {
-            match build_copper_mesh(&commands, true, Some(thickness)) {
-                Ok(mesh) => {
-                    println!(
-                        "Top copper mesh created with {} vertices and {} faces",
-                        mesh.vertices.len(),
-                        mesh.faces.len()
-                    );
-                    pcb_model.meshes.push(mesh);
-                }
-                Err(e) => println!("Warning: Failed to create top copper mesh: {}", e),
+    // Process copper layers from top to bottom, then the mask and silkscreen
+    let copper_layers = copper_layers(&layers, &stackup);
+
+    let outer_layers = |layer_type: LayerType, kind: StackupLayerKind| {
+        [Side::Top, Side::Bottom]
+            .into_iter()
+            .filter_map(|side| {
+                let (path, _, gerber) = layers.iter().find(|(_, info, _)| {
+                    info.layer_type == layer_type && info.side == Some(side)
+                })?;
+                Some((path, gerber, stackup.outer(kind, side)?))
+            })
+            .collect::<Vec<_>>()
+    };
+    let mask_layers = outer_layers(LayerType::Soldermask, StackupLayerKind::Soldermask);
+    let silk_layers = outer_layers(LayerType::Silkscreen, StackupLayerKind::Silkscreen);
+
+    for (path, gerber, layer) in copper_layers.into_iter().chain(mask_layers).chain(silk_layers) {
+        println!("Processing {} layer: {:?}", layer.name, path);
+        let (z_bottom, z_top) = (layer.z_bottom, layer.z_top);
+        let mesh = match layer.kind {
+            // Mask files plot the openings, unless marked positive
+            StackupLayerKind::Soldermask
+                if file_polarity(job.as_ref(), path, gerber) != Some(Polarity::Positive) =>
+            {
+                build_soldermask_mesh(
+                    &outline,
+                    &gerber.commands,
+                    &holes,
+                    layer.side,
+                    z_bottom,
+                    z_top,
+                    arc_tolerance,
+                )
+            }
+            StackupLayerKind::Soldermask => build_layer_mesh(
+                &gerber.commands,
+                LayerType::Soldermask,
+                layer.side,
+                z_bottom,
+                z_top,
+                arc_tolerance,
+            ),
+            StackupLayerKind::Silkscreen => build_layer_mesh(
+                &gerber.commands,
+                LayerType::Silkscreen,
+                layer.side,
+                z_bottom,
+                z_top,
+                arc_tolerance,
+            ),
+            _ => build_layer_mesh(
+                &gerber.commands,
+                LayerType::Copper,
+                layer.side,
+                z_bottom,
+                z_top,
+                arc_tolerance,
+            ),
+        };
+        match mesh {
+            Ok(mesh) => {
+                println!(
+                    "{} mesh created with {} vertices and {} faces",
+                    layer.name,
+                    mesh.vertices.len(),
+                    mesh.faces.len()
+                );
+                pcb_model.meshes.push(mesh);
+            }
+            Err(e) if options.strict => {
+                return Err(format!("Failed to create {} mesh in strict mode: {}", layer.name, e));
             }
+            Err(e) => println!("Warning: Failed to create {} mesh: {}", layer.name, e),
         }
     }
 
-    if let Some(path) = bottom_copper_file {
-        println!("Processing bottom copper layer: {:?}", path);
-        if let Ok(commands) = read_and_parse_gerber(path.to_str().unwrap()) {
-            match build_copper_mesh(&commands, false, Some(thickness)) {
-                Ok(mesh) => {
-                    println!(
-                        "Bottom copper mesh created with {} vertices and {} faces",
-                        mesh.vertices.len(),
-                        mesh.faces.len()
-                    );
-                    pcb_model.meshes.push(mesh);
-                }
-                Err(e) => println!("Warning: Failed to create bottom copper mesh: {}", e),
-            }
+    // Weld duplicate vertices and give the walls their own normals
+    for mesh in &mut pcb_model.meshes {
+        mesh.post_process();
+    }
+
+    Ok(pcb_model)
+}
+
+/// Process Gerber files into the board as exact solids, for mechanical CAD.
+///
+/// This function:
+/// 1. Reads the input directory as [`process_gerber_files_with_options`] does
+/// 2. Cuts the drill holes out of the board outline, as in
+///    [`build_board_mesh_from_outline`]; without `copper`, plated holes are
+///    cut at their finished size, as there are no barrels to make room for
+/// 3. Restores the arcs of the Edge Cuts layer and the round walls of the
+///    holes (see [`intermediate::profile`]), so that the board can be
+///    exported with planar and cylindrical faces
+///
+/// With `copper`, every copper layer is added at its stackup height, cut back
+/// from the holes, along with the plated hole barrels that join them.
+///
+/// # Arguments
+///
+/// * `input_dir` - Directory containing Gerber files
+/// * `options` - Conversion options (thickness, strict parsing, stackup, arc tolerance)
+/// * `copper` - Add the copper layers and plated hole barrels
+///
+/// # Returns
+///
+/// * `Result<SolidModel, String>` - The board solids on success, or an error message
+pub fn process_board_solids(
+    input_dir: &str,
+    options: &ProcessOptions,
+    copper: bool,
+) -> Result<SolidModel, String> {
+    use intermediate::profile::{Prism, Profile, profiles};
+
+    let Board {
+        job,
+        layers,
+        holes,
+        thickness,
+        arc_tolerance,
+        stackup,
+    } = read_board(input_dir, options)?;
+
+    let edge_cuts_file = layers
+        .iter()
+        .find(|(_, info, _)| info.layer_type == LayerType::EdgeCuts)
+        .map(|(path, _, gerber)| (path, gerber));
+    let Some((path, edge_cuts)) = edge_cuts_file else {
+        return Err("Edge Cuts layer not found. This is required for the PCB outline.".to_string());
+    };
+    let outline = edge_cuts_outline(path, &edge_cuts.commands, arc_tolerance, options.strict)?;
+
+    // Arcs lie on the Edge Cuts arcs and the walls of the holes
+    let mut circles = outline_circles(&edge_cuts.commands, arc_tolerance);
+    circles.extend(drill_circles(&holes, copper, arc_tolerance));
+
+    let prisms_of = |name: &str, profiles: Vec<Profile>, z_bottom: f64, z_top: f64| {
+        profiles
+            .into_iter()
+            .map(|profile| Prism {
+                name: name.to_string(),
+                profile,
+                z_bottom,
+                z_top,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Without barrels, the board is cut at the finished hole size
+    let cutouts = drill_cutouts(&holes, copper, arc_tolerance);
+    let board = profiles(&difference(&outline, &cutouts)?, &circles);
+    println!(
+        "Board solid created with {} profile(s) ({} holes)",
+        board.len(),
+        holes.len()
+    );
+    let mut prisms = prisms_of("Board", board, 0.0, thickness);
+
+    if copper {
+        for (path, gerber, layer) in copper_layers(&layers, &stackup) {
+            println!("Processing {} layer: {:?}", layer.name, path);
+            let name = match layer.side {
+                Side::Top => "TopCopper",
+                Side::Inner => "InnerCopper",
+                Side::Bottom => "BottomCopper",
+            };
+            let polygons = gerber::plot::plot_layer(&gerber.commands, arc_tolerance)?;
+            let copper = profiles(&difference(&polygons, &cutouts)?, &circles);
+            prisms.extend(prisms_of(name, copper, layer.z_bottom, layer.z_top));
         }
+
+        let barrels = profiles(&plated_barrels(&holes, arc_tolerance)?, &circles);
+        let (z_bottom, z_top) = barrel_z_range(&stackup);
+        prisms.extend(prisms_of("PlatedBarrels", barrels, z_bottom, z_top));
     }
 
-    // Process silkscreen layers
-    if let Some(path) = top_silk_file {
-        println!("Processing top silkscreen layer: {:?}", path);
-        if let Ok(commands) = read_and_parse_gerber(path.to_str().unwrap()) {
-            match build_silkscreen_mesh(&commands, true, Some(thickness)) {
-                Ok(mesh) => {
-                    println!(
-                        "Top silkscreen mesh created with {} vertices and {} faces",
-                        mesh.vertices.len(),
-                        mesh.faces.len()
-                    );
-                    pcb_model.meshes.push(mesh);
-                }
-                Err(e) => println!("Warning: Failed to create top silkscreen mesh: {}", e),
+    Ok(SolidModel {
+        prisms,
+        metadata: board_metadata(job.as_ref(), thickness),
+    })
+}
+
+/// The copper layers of a board with their layer of the stackup, from top
+/// to bottom. Layers the stackup has no copper layer for are skipped.
+fn copper_layers<'a>(
+    layers: &'a [(PathBuf, LayerInfo, GerberFile)],
+    stackup: &'a Stackup,
+) -> Vec<(&'a PathBuf, &'a GerberFile, &'a StackupLayer)> {
+    let mut copper_layers: Vec<_> = layers
+        .iter()
+        .filter(|(_, info, _)| info.layer_type == LayerType::Copper)
+        .filter_map(|(path, info, gerber)| {
+            let layer = stackup.copper(info.side, info.copper_index);
+            if layer.is_none() {
+                println!("Skipping copper layer outside the stackup: {:?}", path);
             }
-        }
+            Some((path, gerber, layer?))
+        })
+        .collect();
+    copper_layers.sort_by_key(|(_, _, layer)| layer.copper_index);
+    copper_layers
+}
+
+/// The parsed input files of a board, with the settings resolved from the
+/// options and the job file.
+struct Board {
+    /// Parsed job file, if any
+    job: Option<gerber::job::JobFile>,
+    /// Gerber layers with their identified layer
+    layers: Vec<(PathBuf, LayerInfo, GerberFile)>,
+    /// Holes of every drill file
+    holes: Vec<excellon::types::Hole>,
+    /// Board thickness in mm
+    thickness: f64,
+    /// Maximum chord deviation of arcs in mm
+    arc_tolerance: f64,
+    /// Layer stackup of the board
+    stackup: Stackup,
+}
+
+/// Reads an input directory: the job file, the Gerber layers and the drill
+/// files, and resolves the board thickness, arc tolerance and stackup.
+///
+/// # Returns
+///
+/// * `Result<Board, String>` - The board input, or an error message
+fn read_board(input_dir: &str, options: &ProcessOptions) -> Result<Board, String> {
+    let input_path = Path::new(input_dir);
+
+    // Check if the input directory exists
+    if !input_path.exists() || !input_path.is_dir() {
+        return Err(format!("Input directory does not exist: {}", input_dir));
     }
 
-    if let Some(path) = bottom_silk_file {
-        println!("Processing bottom silkscreen layer: {:?}", path);
-        if let Ok(commands) = read_and_parse_gerber(path.to_str().unwrap()) {
-            match build_silkscreen_mesh(&commands, false, Some(thickness)) {
-                Ok(mesh) => {
-                    println!(
-                        "Bottom silkscreen mesh created with {} vertices and {} faces",
-                        mesh.vertices.len(),
-                        mesh.faces.len()
-                    );
-                    pcb_model.meshes.push(mesh);
-                }
-                Err(e) => println!("Warning: Failed to create bottom silkscreen mesh: {}", e),
+    // Parse the job file and every layer file, and identify the layers
+    let InputFiles {
+        job,
+        layers,
+        drill_paths,
+    } = read_input_dir(input_path, options.strict)?;
+    let thickness = options
+        .thickness
+        .or(job.as_ref().and_then(|job| job.board_thickness))
+        .unwrap_or(DEFAULT_THICKNESS);
+    let arc_tolerance = options.arc_tolerance.unwrap_or(gerber::plot::ARC_TOLERANCE);
+    if !(arc_tolerance > 0.0 && arc_tolerance.is_finite()) {
+        return Err(format!("Invalid arc tolerance: {}", arc_tolerance));
+    }
+
+    // Drill files (plated and non-plated holes)
+    let mut holes = Vec::new();
+    for path in drill_paths {
+        let drill = read_and_parse_drill(path.to_str().unwrap(), options.strict, arc_tolerance)?;
+        holes.extend(drill.holes);
+    }
+    if !holes.is_empty() {
+        println!("Found {} drill hole(s)", holes.len());
+    }
+
+    // Z positions come from the stackup file, the job file stackup or a
+    // default stackup with enough copper layers for the files found
+    let stackup = match (&options.stackup, job.as_ref()) {
+        (Some(path), _) => {
+            let content = fs::read_to_string(path)
+                .map_err(|e| format!("Error reading stackup file {}: {}", path, e))?;
+            let entries = gerber::job::parse_job_file(&content)
+                .map_err(|e| format!("Error parsing stackup file {}: {}", path, e))?
+                .stackup;
+            if entries.is_empty() {
+                return Err(format!("No MaterialStackup found in {}", path));
             }
+            Stackup::from_entries(&entries, thickness)
+        }
+        (None, Some(job)) if !job.stackup.is_empty() => {
+            Stackup::from_entries(&job.stackup, thickness)
+        }
+        _ => {
+            let copper_layers = job
+                .as_ref()
+                .and_then(|job| job.layer_count)
+                .into_iter()
+                .chain(layers.iter().filter_map(|(_, info, _)| info.copper_index))
+                .max()
+                .unwrap_or(2);
+            Stackup::with_copper_layers(copper_layers.max(2), thickness)
+        }
+    };
+    println!("Stackup with {} copper layer(s)", stackup.copper_count());
+
+    Ok(Board {
+        job,
+        layers,
+        holes,
+        thickness,
+        arc_tolerance,
+        stackup,
+    })
+}
+
+/// The files of an input directory.
+struct InputFiles {
+    /// Parsed job file, if any
+    job: Option<gerber::job::JobFile>,
+    /// Gerber layers with their identified layer
+    layers: Vec<(PathBuf, LayerInfo, GerberFile)>,
+    /// Paths of the drill files
+    drill_paths: Vec<PathBuf>,
+}
+
+/// Reads the job file and every Gerber layer of an input directory.
+///
+/// Each layer is identified from the job file, then the X2 `.FileFunction`
+/// attribute, then the file name; unrecognized files are skipped.
+///
+/// # Returns
+///
+/// * `Result<InputFiles, String>` - The job file, the identified layers and
+///   the drill file paths, or an error message
+fn read_input_dir(
+    input_path: &Path,
+    strict: bool,
+) -> Result<InputFiles, String> {
+    // Find and process Gerber files
+    let entries =
+        fs::read_dir(input_path).map_err(|e| format!("Error reading directory: {}", e))?;
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    // The job file is the authoritative manifest when there is one
+    let job = match paths.iter().find(|path| is_job_file(path)) {
+        Some(path) => Some(read_and_parse_job(path.to_str().unwrap())?),
+        None => None,
+    };
+
+    let (drill_paths, paths): (Vec<PathBuf>, Vec<PathBuf>) = paths
+        .into_iter()
+        .filter(|path| is_gerber_file(path) || is_drill_file(path))
+        .partition(|path| is_drill_file(path));
+
+    let mut layers: Vec<(PathBuf, LayerInfo, GerberFile)> = Vec::new();
+    for path in paths {
+        let gerber = read_and_parse_gerber(path.to_str().unwrap(), strict)?;
+        let from_job = job.as_ref().and_then(|job| layer_from_job(job, &path));
+        match from_job.or_else(|| identify_layer(&path, Some(&gerber))) {
+            Some(info) => layers.push((path, info, gerber)),
+            None => println!("Skipping unrecognized layer: {:?}", path),
         }
     }
 
-    Ok(pcb_model)
+    Ok(InputFiles {
+        job,
+        layers,
+        drill_paths,
+    })
 }
 
 /// Reads a Gerber file and parses its content into commands.
 ///
+/// Skipped blocks are reported as a warning; in strict mode they make the
+/// whole file fail instead.
+///
 /// # Arguments
 ///
 /// * `file_path` - Path to the Gerber file
+/// * `strict` - Fail on any block that cannot be parsed
 ///
 /// # Returns
 ///
-/// * `Result<Vec<gerber::types::Command>, String>` - The parsed Gerber commands or an error message
-pub fn read_and_parse_gerber(file_path: &str) -> Result<Vec<gerber::types::Command>, String> {
+/// * `Result<gerber::types::GerberFile, String>` - The parsed Gerber file or an error message
+pub fn read_and_parse_gerber(
+    file_path: &str,
+    strict: bool,
+) -> Result<gerber::types::GerberFile, String> {
     // Convert to absolute path for better debugging
     let absolute_path = Path::new(file_path)
         .canonicalize()
@@ -218,18 +567,132 @@ pub fn read_and_parse_gerber(file_path: &str) -> Result<Vec<gerber::types::Comma
     println!("Successfully read Gerber file: {}", file_path);
 
     // Parse the Gerber content
-    let commands = gerber::parse::parse_gerber(&content)?;
-    println!("Successfully parsed {} Gerber commands", commands.len());
+    let gerber = gerber::parse::parse_gerber_file(&content, strict)
+        .map_err(|e| format!("Error parsing {}: {}", file_path, e))?;
+    println!("Successfully parsed {} Gerber commands", gerber.commands.len());
+    if !gerber.diagnostics.is_empty() {
+        println!(
+            "Warning: skipped {} block(s); run 'pcbgen info --input {}' for details",
+            gerber.diagnostics.len(),
+            file_path
+        );
+    }
+
+    Ok(gerber)
+}
+
+/// Reads a Gerber job file.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the `.gbrjob` file
+///
+/// # Returns
+///
+/// * `Result<gerber::job::JobFile, String>` - The job description or an error message
+pub fn read_and_parse_job(file_path: &str) -> Result<gerber::job::JobFile, String> {
+    println!("Reading job file: {}", file_path);
+    let content = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("Error reading file {}: {}", file_path, e))?;
+    let job = gerber::job::parse_job_file(&content)
+        .map_err(|e| format!("Error parsing {}: {}", file_path, e))?;
+
+    let folder = Path::new(file_path).parent().unwrap_or(Path::new("."));
+    for entry in &job.files {
+        if !folder.join(&entry.path).exists() {
+            println!("Warning: {} is listed in the job file but missing", entry.path);
+        }
+    }
+    println!("Successfully parsed job file listing {} file(s)", job.files.len());
+
+    Ok(job)
+}
+
+/// Board metadata from the job file, if any, and the thickness actually used.
+fn board_metadata(job: Option<&gerber::job::JobFile>, thickness: f64) -> BoardMetadata {
+    match job {
+        Some(job) => BoardMetadata {
+            project: job.project.clone(),
+            revision: job.revision.clone(),
+            generator: job.generator.clone(),
+            size: job.size,
+            layer_count: job.layer_count,
+            thickness,
+            finish: job.finish.clone(),
+        },
+        None => BoardMetadata {
+            thickness,
+            ..BoardMetadata::default()
+        },
+    }
+}
+
+/// Image polarity of a layer file, from the job file or the `.FilePolarity`
+/// attribute of the file itself.
+fn file_polarity(
+    job: Option<&gerber::job::JobFile>,
+    file_path: &Path,
+    gerber: &GerberFile,
+) -> Option<Polarity> {
+    let from_job = job.and_then(|job| {
+        let file_name = file_path.file_name()?.to_str()?;
+        job.file(file_name)?.polarity
+    });
+    from_job.or_else(|| {
+        match gerber.file_attribute(".FilePolarity")?.first()?.as_str() {
+            "Positive" => Some(Polarity::Positive),
+            "Negative" => Some(Polarity::Negative),
+            _ => None,
+        }
+    })
+}
+
+/// Reads an Excellon drill file and parses its holes.
+///
+/// Holes the file does not declare as plated or non-plated are taken as
+/// non-plated when the file name mentions "npth", plated otherwise.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the drill file
+/// * `strict` - Fail on any line that cannot be parsed
+/// * `arc_tolerance` - Maximum chord deviation of routed arcs in mm
+///
+/// # Returns
+///
+/// * `Result<excellon::types::DrillFile, String>` - The parsed drill file or an error message
+pub fn read_and_parse_drill(
+    file_path: &str,
+    strict: bool,
+    arc_tolerance: f64,
+) -> Result<excellon::types::DrillFile, String> {
+    println!("Reading drill file: {}", file_path);
+    let content = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("Error reading file {}: {}", file_path, e))?;
 
-    Ok(commands)
+    let default_plating = plating_from_file_name(Path::new(file_path));
+    let drill = excellon::parse::parse_excellon(&content, default_plating, strict, arc_tolerance)
+        .map_err(|e| format!("Error parsing {}: {}", file_path, e))?;
+    println!(
+        "Successfully parsed {} hole(s) ({} plated) with {} tool(s)",
+        drill.holes.len(),
+        drill.plated_count(),
+        drill.tools.len()
+    );
+    if !drill.diagnostics.is_empty() {
+        println!(
+            "Warning: skipped {} line(s); run 'pcbgen info --input {}' for details",
+            drill.diagnostics.len(),
+            file_path
+        );
+    }
+
+    Ok(drill)
 }
 
 /// Creates a 3D mesh representing the PCB outline from the Edge Cuts layer.
 ///
-/// This function:
-/// 1. Extracts 2D outline points from Gerber commands
-/// 2. Handles linear segments and arc segments
-/// 3. Extrudes the 2D outline into a 3D mesh with proper thickness
+/// Shorthand for [`build_board_mesh`] without drill holes.
 ///
 /// # Arguments
 ///
@@ -243,322 +706,741 @@ pub fn build_edge_cuts_mesh(
     commands: &[gerber::types::Command],
     thickness: Option<f64>,
 ) -> Result<Mesh, String> {
-    use gerber::types::{Command, InterpolationMode, Point};
-    use intermediate::model::{Face, Point3D, Vertex};
+    build_board_mesh(commands, &[], thickness)
+}
+
+/// Creates the board solid from the Edge Cuts layer, with drill holes cut through it.
+///
+/// This function:
+/// 1. Chains the Edge Cuts segments and arcs into the board outline and
+///    cutouts, leaving out open contours (see [`board_outline`])
+/// 2. Subtracts a round hole for every drill hit and a stadium-shaped hole
+///    for every slot; plated holes are enlarged by [`PLATING_THICKNESS`] to
+///    leave room for the barrel
+/// 3. Extrudes the 2D outline into a 3D mesh with proper thickness
+///
+/// Overlapping holes merge, and holes crossing the board edge notch it.
+///
+/// # Arguments
+///
+/// * `commands` - The parsed Gerber commands from the Edge Cuts layer
+/// * `holes` - Drill holes to cut through the board
+/// * `thickness` - PCB thickness in mm (optional, defaults to 1.6mm)
+///
+/// # Returns
+///
+/// * `Result<Mesh, String>` - A 3D mesh representing the PCB board
+pub fn build_board_mesh(
+    commands: &[gerber::types::Command],
+    holes: &[excellon::types::Hole],
+    thickness: Option<f64>,
+) -> Result<Mesh, String> {
+    let arc_tolerance = gerber::plot::ARC_TOLERANCE;
+    let (outline, _) = board_outline(commands, arc_tolerance)?;
+    build_board_mesh_from_outline(&outline, holes, thickness, arc_tolerance)
+}
 
+/// Creates the board solid from an outline already extracted with
+/// [`board_outline`], with drill holes cut through it.
+///
+/// # Arguments
+///
+/// * `outline` - The board polygons with their cutouts
+/// * `holes` - Drill holes to cut through the board
+/// * `thickness` - PCB thickness in mm (optional, defaults to 1.6mm)
+/// * `arc_tolerance` - Maximum chord deviation of the hole walls in mm
+///
+/// # Returns
+///
+/// * `Result<Mesh, String>` - A 3D mesh representing the PCB board
+pub fn build_board_mesh_from_outline(
+    outline: &[Polygon],
+    holes: &[excellon::types::Hole],
+    thickness: Option<f64>,
+    arc_tolerance: f64,
+) -> Result<Mesh, String> {
     // PCB parameters
     let pcb_thickness = thickness.unwrap_or(1.6); // Use provided thickness or default to 1.6mm
-    const POINTS_PER_ARC: usize = 16; // Number of points to use when approximating arcs
 
-    // Collect 2D outline points from the Gerber commands
-    let mut outline_points: Vec<Point> = Vec::new();
-    let mut current_x = 0.0;
-    let mut current_y = 0.0;
+    let board = difference(outline, &drill_cutouts(holes, true, arc_tolerance))?;
+
+    // Convert 2D outline to 3D mesh by extruding
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
+        layer_type: LayerType::EdgeCuts,
+        side: None,
+    };
+    extrude_polygons(&mut mesh, &board, 0.0, pcb_thickness);
+
+    println!(
+        "Edge cuts mesh created with {} vertices and {} faces ({} holes)",
+        mesh.vertices.len(),
+        mesh.faces.len(),
+        holes.len()
+    );
+
+    Ok(mesh)
+}
+
+/// Extracts the board outline from the Edge Cuts layer.
+///
+/// Draws and arcs between moves form paths, which are chained into closed
+/// contours whatever order and direction they were written in, bridging
+/// gaps up to [`OUTLINE_TOLERANCE`] (see
+/// [`geometry::contour::chain_paths`]). The outermost contours are board
+/// outlines and the contours inside them are cutouts. Contours that cannot
+/// be closed are left out, with a diagnostic each. Arcs follow the
+/// quadrant mode of the file (see [`gerber::plot::arc_draw_points`]), so a
+/// single full-circle arc makes a round board.
+///
+/// # Arguments
+///
+/// * `commands` - The parsed Gerber commands from the Edge Cuts layer
+/// * `arc_tolerance` - Maximum chord deviation of arcs in mm
+///
+/// # Returns
+///
+/// * `Result<(Vec<Polygon>, Vec<Diagnostic>), String>` - The board polygons
+///   with their cutouts and the contours that are not closed, or an error
+///   message when no contour is closed
+pub fn board_outline(
+    commands: &[gerber::types::Command],
+    arc_tolerance: f64,
+) -> Result<(Vec<Polygon>, Vec<Diagnostic>), String> {
+    use geometry::contour::{chain_paths, nest_loops};
+
+    // Flatten the paths drawn between moves
+    let paths: Vec<Vec<gerber::types::Point>> = outline_strokes(commands)
+        .into_iter()
+        .map(|strokes| {
+            let mut path = Vec::new();
+            for stroke in strokes {
+                if path.is_empty() {
+                    path.push(stroke.start.clone());
+                }
+                match stroke.center {
+                    Some(center) => path.extend(gerber::plot::arc_points(
+                        &stroke.start,
+                        &center,
+                        &stroke.end,
+                        stroke.clockwise,
+                        arc_tolerance,
+                    )),
+                    // Skip draws that do not move
+                    None if stroke.end.x == stroke.start.x && stroke.end.y == stroke.start.y => {}
+                    None => path.push(stroke.end),
+                }
+            }
+            path
+        })
+        .collect();
+
+    let (loops, open) = chain_paths(paths, OUTLINE_TOLERANCE);
+    let diagnostics = open
+        .iter()
+        .map(|chain| {
+            let (start, end) = (&chain[0], &chain[chain.len() - 1]);
+            Diagnostic {
+                line: 0,
+                column: 0,
+                text: format!(
+                    "({:.3}, {:.3}) to ({:.3}, {:.3})",
+                    start.x, start.y, end.x, end.y
+                ),
+                kind: DiagnosticKind::Malformed,
+                reason: "Edge Cuts contour is not closed".to_string(),
+            }
+        })
+        .collect();
+
+    let polygons = nest_loops(loops);
+    if polygons.is_empty() {
+        return Err("No closed board outline found in the Edge Cuts layer".to_string());
+    }
+
+    Ok((polygons, diagnostics))
+}
+
+/// Builds the board outline of the Edge Cuts layer of an input directory.
+///
+/// Contours that are not closed are reported as a warning; in strict mode
+/// they make the conversion fail.
+///
+/// # Arguments
+///
+/// * `path` - Path of the Edge Cuts file, for messages
+/// * `commands` - The parsed Gerber commands of the Edge Cuts layer
+/// * `arc_tolerance` - Maximum chord deviation of arcs in mm
+/// * `strict` - Fail on any contour that is not closed
+///
+/// # Returns
+///
+/// * `Result<Vec<Polygon>, String>` - The board polygons with their cutouts, or an error message
+fn edge_cuts_outline(
+    path: &Path,
+    commands: &[gerber::types::Command],
+    arc_tolerance: f64,
+    strict: bool,
+) -> Result<Vec<Polygon>, String> {
+    println!("Processing Edge Cuts layer: {:?}", path);
+    let (outline, diagnostics) = board_outline(commands, arc_tolerance)?;
+    if strict && !diagnostics.is_empty() {
+        let details: Vec<String> = diagnostics.iter().map(|d| format!("  {}", d)).collect();
+        return Err(format!(
+            "{} Edge Cuts contour(s) are not closed in strict mode:\n{}",
+            diagnostics.len(),
+            details.join("\n")
+        ));
+    }
+    if !diagnostics.is_empty() {
+        println!(
+            "Warning: left out {} open Edge Cuts contour(s); run 'pcbgen info --input {}' for details",
+            diagnostics.len(),
+            path.display()
+        );
+    }
+
+    Ok(outline)
+}
+
+/// Outlines of the drill holes, as polygons to subtract from a layer.
+///
+/// A round hole is a slot whose ends coincide. With `barrels`, plated holes
+/// are enlarged by [`PLATING_THICKNESS`] to make room for the barrels of
+/// [`plated_barrels`]; without them, every hole keeps its finished size.
+/// The round ends are flattened with `arc_tolerance` as the maximum chord
+/// deviation.
+fn drill_cutouts(
+    holes: &[excellon::types::Hole],
+    barrels: bool,
+    arc_tolerance: f64,
+) -> Vec<Polygon> {
+    use excellon::types::Plating;
+    use geometry::shapes::stadium;
+
+    holes
+        .iter()
+        .map(|hole| {
+            let radius = match hole.plating {
+                Plating::Plated if barrels => hole.diameter / 2.0 + PLATING_THICKNESS,
+                _ => hole.diameter / 2.0,
+            };
+            let end = hole.end.as_ref().unwrap_or(&hole.position);
+            let segments = gerber::plot::circle_segments(radius, arc_tolerance);
+            Polygon::new(stadium(&hole.position, end, radius, segments))
+        })
+        .collect()
+}
+
+/// Circles of the arcs drawn in an Edge Cuts layer, with the chord angle
+/// they were split at by [`board_outline`].
+fn outline_circles(commands: &[gerber::types::Command], arc_tolerance: f64) -> Vec<Circle> {
+    outline_strokes(commands)
+        .into_iter()
+        .flatten()
+        .filter_map(|stroke| {
+            let center = stroke.center?;
+            let radius = (stroke.start.x - center.x).hypot(stroke.start.y - center.y);
+            Some(Circle {
+                center,
+                radius,
+                max_step: gerber::plot::arc_step(radius, arc_tolerance),
+            })
+        })
+        .collect()
+}
+
+/// A draw of the Edge Cuts layer, in mm.
+struct OutlineStroke {
+    start: gerber::types::Point,
+    end: gerber::types::Point,
+    /// Center of an arc, `None` for a straight draw
+    center: Option<gerber::types::Point>,
+    clockwise: bool,
+}
+
+/// Replays the draws of an Edge Cuts layer, converted to mm, and groups
+/// them into the paths drawn between moves.
+///
+/// Arc centers follow the quadrant mode of the file (see
+/// [`gerber::plot::arc_center`]); a single-quadrant arc that ends where it
+/// starts draws a straight line to its end, that is nothing.
+fn outline_strokes(commands: &[gerber::types::Command]) -> Vec<Vec<OutlineStroke>> {
+    use gerber::plot::{MM_PER_INCH, arc_center};
+    use gerber::types::{Command, InterpolationMode, Point, QuadrantMode};
+
+    let mut paths = Vec::new();
+    let mut path = Vec::new();
+    let mut current = Point { x: 0.0, y: 0.0 };
     let mut current_mode = InterpolationMode::Linear;
-    let mut start_point: Option<Point> = None;
+    let mut quadrant_mode = QuadrantMode::Multi;
+    let mut scale = 1.0;
+    let scaled = |point: &Point, scale: f64| Point {
+        x: point.x * scale,
+        y: point.y * scale,
+    };
 
-    // First pass: collect all points from the edge cuts outline
     for cmd in commands {
         match cmd {
+            Command::SetUnitsMM => scale = 1.0,
+            Command::SetUnitsInch => scale = MM_PER_INCH,
             Command::Move { point } => {
-                current_x = point.x;
-                current_y = point.y;
-
-                // If this is the first point, record it as the start point
-                if start_point.is_none() {
-                    start_point = Some(Point {
-                        x: current_x,
-                        y: current_y,
-                    });
-                }
-
-                // Add the point to our outline
-                outline_points.push(Point {
-                    x: current_x,
-                    y: current_y,
-                });
+                paths.push(std::mem::take(&mut path));
+                current = scaled(point, scale);
             }
             Command::Draw { point } => {
-                current_x = point.x;
-                current_y = point.y;
-                outline_points.push(Point {
-                    x: current_x,
-                    y: current_y,
+                let end = scaled(point, scale);
+                path.push(OutlineStroke {
+                    start: std::mem::replace(&mut current, end.clone()),
+                    end,
+                    center: None,
+                    clockwise: false,
                 });
             }
             Command::ArcDraw {
                 end_point,
                 center_offset,
             } => {
-                // For arcs, we need to generate points along the arc path
-                let start_x = current_x;
-                let start_y = current_y;
-                let end_x = end_point.x;
-                let end_y = end_point.y;
-                let center_x = start_x + center_offset.x;
-                let center_y = start_y + center_offset.y;
-
-                // Calculate start and end angles
-                let start_angle = (start_y - center_y).atan2(start_x - center_x);
-                let end_angle = (end_y - center_y).atan2(end_x - center_x);
-
-                // Calculate radius
-                let radius = ((start_x - center_x).powi(2) + (start_y - center_y).powi(2)).sqrt();
-
-                // Generate points along the arc
-                let mut angle_diff = end_angle - start_angle;
-
-                // Adjust angle difference based on the interpolation mode
-                match current_mode {
-                    InterpolationMode::ClockwiseCircular => {
-                        if angle_diff > 0.0 {
-                            angle_diff -= 2.0 * std::f64::consts::PI;
-                        }
-                    }
-                    InterpolationMode::CounterClockwiseCircular => {
-                        if angle_diff < 0.0 {
-                            angle_diff += 2.0 * std::f64::consts::PI;
-                        }
-                    }
-                    _ => {}
-                }
-
-                // Generate points along the arc
-                for i in 1..=POINTS_PER_ARC {
-                    let angle = start_angle + angle_diff * (i as f64 / POINTS_PER_ARC as f64);
-                    let x = center_x + radius * angle.cos();
-                    let y = center_y + radius * angle.sin();
-                    outline_points.push(Point { x, y });
-                }
-
-                // Update current position
-                current_x = end_x;
-                current_y = end_y;
+                let end = scaled(end_point, scale);
+                let offset = scaled(center_offset, scale);
+                let clockwise = matches!(current_mode, InterpolationMode::ClockwiseCircular);
+                path.push(OutlineStroke {
+                    center: arc_center(&current, &end, &offset, clockwise, quadrant_mode),
+                    start: std::mem::replace(&mut current, end.clone()),
+                    end,
+                    clockwise,
+                });
             }
             Command::SetInterpolationMode(mode) => {
                 current_mode = mode.clone();
             }
+            Command::SetQuadrantMode(mode) => {
+                quadrant_mode = *mode;
+            }
             _ => {} // Ignore other commands
         }
     }
+    paths.push(path);
+
+    paths
+}
 
-    // Make sure the outline is closed
-    if let Some(start) = start_point {
-        if let Some(last) = outline_points.last() {
-            if (start.x != last.x) || (start.y != last.y) {
-                outline_points.push(start);
+/// Circles of the hole walls, at both ends of slots: the drilled wall, and
+/// with `barrels` for plated holes the outside of the barrel that
+/// [`drill_cutouts`] cuts, split into as many chords as `arc_tolerance`
+/// calls for.
+fn drill_circles(
+    holes: &[excellon::types::Hole],
+    barrels: bool,
+    arc_tolerance: f64,
+) -> Vec<Circle> {
+    use excellon::types::Plating;
+
+    let mut circles = Vec::new();
+    for hole in holes {
+        let radius = hole.diameter / 2.0;
+        let radii: &[f64] = match hole.plating {
+            Plating::Plated if barrels => &[radius, radius + PLATING_THICKNESS],
+            _ => &[radius],
+        };
+        for center in std::iter::once(&hole.position).chain(&hole.end) {
+            for &radius in radii {
+                let segments = gerber::plot::circle_segments(radius, arc_tolerance);
+                circles.push(Circle {
+                    center: center.clone(),
+                    radius,
+                    max_step: 2.0 * std::f64::consts::PI / segments as f64,
+                });
             }
         }
     }
+    circles
+}
 
-    // Convert 2D outline to 3D mesh by extruding
-    let mut vertices = Vec::new();
-    let mut faces = Vec::new();
-
-    // Check if we have enough points
-    if outline_points.len() < 3 {
-        return Err("Not enough points to create a valid mesh".to_string());
+/// Creates a 3D mesh representing a solder mask layer.
+///
+/// Mask Gerbers are negative: what they plot are the openings where the mask
+/// is absent. The mask is therefore the board outline minus the openings
+/// and the drill holes; openings running off the board edge are clipped.
+///
+/// # Arguments
+///
+/// * `outline` - Board polygons, see [`board_outline`]
+/// * `commands` - The parsed Gerber commands from the solder mask layer
+/// * `holes` - Drill holes through the board
+/// * `side` - Side of the board the mask is on
+/// * `z_bottom` - Z coordinate of the bottom of the mask
+/// * `z_top` - Z coordinate of the top of the mask
+/// * `arc_tolerance` - Maximum chord deviation of arcs in mm
+///
+/// # Returns
+///
+/// * `Result<Mesh, String>` - A 3D mesh representing the solder mask
+pub fn build_soldermask_mesh(
+    outline: &[Polygon],
+    commands: &[gerber::types::Command],
+    holes: &[excellon::types::Hole],
+    side: Side,
+    z_bottom: f64,
+    z_top: f64,
+    arc_tolerance: f64,
+) -> Result<Mesh, String> {
+    if outline.is_empty() {
+        return Err("The board outline is empty".to_string());
     }
 
-    // Create top and bottom vertices
-    for point in &outline_points {
-        // Top vertex
-        vertices.push(Vertex {
-            position: Point3D {
-                x: point.x,
-                y: point.y,
-                z: pcb_thickness,
-            },
-            normal: Point3D {
-                x: 0.0,
-                y: 0.0,
-                z: 1.0,
-            },
-        });
+    // Drill holes are cut through the mask too
+    let openings = union(
+        &gerber::plot::plot_layer(commands, arc_tolerance)?,
+        &drill_cutouts(holes, true, arc_tolerance),
+    )?;
+    let mask = difference(outline, &openings)?;
 
-        // Bottom vertex
-        vertices.push(Vertex {
-            position: Point3D {
-                x: point.x,
-                y: point.y,
-                z: 0.0,
-            },
-            normal: Point3D {
-                x: 0.0,
-                y: 0.0,
-                z: -1.0,
-            },
-        });
-    }
-
-    // Create top face (simple triangle fan)
-    let num_points = outline_points.len();
-    let mut top_face = Face {
+    let mut mesh = Mesh {
         vertices: Vec::new(),
+        faces: Vec::new(),
+        layer_type: LayerType::Soldermask,
+        side: Some(side),
     };
+    extrude_polygons(&mut mesh, &mask, z_bottom, z_top);
 
-    for i in 0..num_points {
-        top_face.vertices.push(i * 2); // Even indices are top vertices
-    }
-    faces.push(top_face);
+    Ok(mesh)
+}
 
-    // Create bottom face (reversed)
-    let mut bottom_face = Face {
-        vertices: Vec::new(),
-    };
+/// Options for generating a solder paste stencil.
+#[derive(Debug, Clone)]
+pub struct StencilOptions {
+    /// Side of the board the stencil is for
+    pub side: Side,
+    /// Thickness of the stencil plate in mm
+    pub thickness: f64,
+    /// Width of the plate around the board outline in mm
+    pub margin: f64,
+    /// Width of a stiffening frame along the plate edge in mm, if any
+    pub frame_width: Option<f64>,
+    /// Height of the frame above the plate in mm
+    pub frame_height: f64,
+    /// Diameter of the registration holes in the plate corners in mm, if any
+    pub registration_hole: Option<f64>,
+    /// Fail on any Gerber block that cannot be parsed instead of skipping it,
+    /// on any Edge Cuts contour that is not closed, and on any layer that
+    /// cannot be plotted instead of leaving it out
+    pub strict: bool,
+    /// Maximum chord deviation of arcs in mm
+    pub arc_tolerance: f64,
+}
 
-    for i in (0..num_points).rev() {
-        bottom_face.vertices.push(i * 2 + 1); // Odd indices are bottom vertices
+impl Default for StencilOptions {
+    fn default() -> Self {
+        StencilOptions {
+            side: Side::Top,
+            thickness: 0.12,
+            margin: 10.0,
+            frame_width: None,
+            frame_height: 1.0,
+            registration_hole: None,
+            strict: false,
+            arc_tolerance: gerber::plot::ARC_TOLERANCE,
+        }
     }
-    faces.push(bottom_face);
-
-    // Create side faces (quads connecting top and bottom)
-    for i in 0..num_points {
-        let next_i = (i + 1) % num_points;
-
-        let quad = Face {
-            vertices: vec![
-                i * 2,          // Current top
-                i * 2 + 1,      // Current bottom
-                next_i * 2 + 1, // Next bottom
-                next_i * 2,     // Next top
-            ],
-        };
+}
 
-        faces.push(quad);
+/// Generates a solder paste stencil from the paste layer of a board.
+///
+/// This function:
+/// 1. Reads the Gerber files and finds the Edge Cuts and paste layers
+/// 2. Builds the stencil plate with [`build_stencil_mesh`]
+///
+/// # Arguments
+///
+/// * `input_dir` - Directory containing Gerber files
+/// * `options` - Stencil options (side, plate thickness, frame, registration holes)
+///
+/// # Returns
+///
+/// * `Result<PCBModel, String>` - A model holding the stencil on success, or an error message
+pub fn generate_stencil(input_dir: &str, options: &StencilOptions) -> Result<PCBModel, String> {
+    let input_path = Path::new(input_dir);
+    if !input_path.exists() || !input_path.is_dir() {
+        return Err(format!("Input directory does not exist: {}", input_dir));
     }
 
-    let mesh = Mesh {
-        vertices,
-        faces,
-        layer_type: LayerType::EdgeCuts,
+    let InputFiles { job, layers, .. } = read_input_dir(input_path, options.strict)?;
+    let find_layer = |layer_type: LayerType, side: Option<Side>| {
+        layers
+            .iter()
+            .find(|(_, info, _)| info.layer_type == layer_type && info.side == side)
     };
 
+    let Some((edge_cuts_path, _, edge_cuts)) = find_layer(LayerType::EdgeCuts, None) else {
+        return Err("Edge Cuts layer not found. This is required for the PCB outline.".to_string());
+    };
+    let Some((path, _, paste)) = find_layer(LayerType::Paste, Some(options.side)) else {
+        return Err(format!("No {:?} solder paste layer found", options.side));
+    };
+    println!("Processing solder paste layer: {:?}", path);
+
+    let outline = edge_cuts_outline(
+        edge_cuts_path,
+        &edge_cuts.commands,
+        options.arc_tolerance,
+        options.strict,
+    )?;
+    let mut mesh = build_stencil_mesh(&outline, &paste.commands, options)?;
+    mesh.post_process();
     println!(
-        "Edge cuts mesh created with {} vertices and {} faces",
+        "Stencil mesh created with {} vertices and {} faces",
         mesh.vertices.len(),
         mesh.faces.len()
     );
 
-    Ok(mesh)
+    Ok(PCBModel {
+        meshes: vec![mesh],
+        units: Units::Millimeters,
+        metadata: board_metadata(job.as_ref(), options.thickness),
+    })
 }
 
-/// Creates a 3D mesh representing a copper layer (top or bottom).
+/// Creates a solder paste stencil: a plate with the paste apertures cut out.
 ///
-/// Currently implements a placeholder visualization, but in a complete implementation
-/// would trace copper tracks and pads with proper thickness.
+/// The plate is the bounding box of the board outline grown by the margin,
+/// from Z = 0 to the stencil thickness. An optional frame runs along the
+/// plate edge on top of it, and optional registration holes are cut in the
+/// corners, centered in the margin left free by the frame. Overlapping
+/// apertures merge, and apertures are clipped to the plate.
 ///
 /// # Arguments
 ///
-/// * `_commands` - The parsed Gerber commands from the copper layer
-/// * `is_top` - Whether this is the top copper layer (`true`) or bottom (`false`)
-/// * `thickness` - PCB thickness in mm (optional, defaults to 1.6mm)
+/// * `outline` - Board polygons, see [`board_outline`]
+/// * `commands` - The parsed Gerber commands from the paste layer
+/// * `options` - Stencil options (plate thickness, margin, frame, registration holes)
 ///
 /// # Returns
 ///
-/// * `Result<Mesh, String>` - A 3D mesh representing the copper layer
-pub fn build_copper_mesh(
-    _commands: &[gerber::types::Command],
-    is_top: bool,
-    thickness: Option<f64>,
+/// * `Result<Mesh, String>` - A 3D mesh of the stencil
+pub fn build_stencil_mesh(
+    outline: &[Polygon],
+    commands: &[gerber::types::Command],
+    options: &StencilOptions,
 ) -> Result<Mesh, String> {
-    use intermediate::model::{Face, Point3D, Vertex};
-
-    // This is a placeholder implementation for copper layers
-    // For a complete implementation, we would trace the copper traces and pads
+    use geometry::shapes::regular_polygon;
+    use gerber::types::Point;
 
-    // Constants
-    let pcb_thickness = thickness.unwrap_or(1.6); // Use provided thickness or default to 1.6mm
-    #[allow(dead_code)]
-    const COPPER_THICKNESS: f64 = 0.035; // Standard copper thickness in mm
-
-    // For now, just create a simple rectangular placeholder mesh for copper
-    let z_position = if is_top { pcb_thickness } else { 0.0 };
+    if outline.is_empty() {
+        return Err("The board outline is empty".to_string());
+    }
+    if options.thickness <= 0.0 {
+        return Err(format!("Invalid stencil thickness: {}", options.thickness));
+    }
+    if !(options.arc_tolerance > 0.0 && options.arc_tolerance.is_finite()) {
+        return Err(format!("Invalid arc tolerance: {}", options.arc_tolerance));
+    }
+    let frame = options.frame_width.unwrap_or(0.0);
+    if options.margin < 0.0 || frame < 0.0 || frame > options.margin {
+        return Err(format!(
+            "The frame ({} mm) must fit in the margin ({} mm)",
+            frame, options.margin
+        ));
+    }
 
-    // Create a small rectangle to represent copper
-    let mut vertices = Vec::new();
-    let mut faces = Vec::new();
+    let (mut x0, mut y0, mut x1, mut y1) = outline.iter().flat_map(|polygon| &polygon.outer).fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(x0, y0, x1, y1), p| (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y)),
+    );
+    x0 -= options.margin;
+    y0 -= options.margin;
+    x1 += options.margin;
+    y1 += options.margin;
+    let rectangle = |inset: f64| {
+        vec![
+            Point {
+                x: x0 + inset,
+                y: y0 + inset,
+            },
+            Point {
+                x: x1 - inset,
+                y: y0 + inset,
+            },
+            Point {
+                x: x1 - inset,
+                y: y1 - inset,
+            },
+            Point {
+                x: x0 + inset,
+                y: y1 - inset,
+            },
+        ]
+    };
+    let plate = rectangle(0.0);
 
-    // Dummy vertices for a small copper square near the center
-    let size = 10.0;
-    let center_x = 150.0;
-    let center_y = -90.0;
+    let mut cutouts = gerber::plot::plot_layer(commands, options.arc_tolerance)?;
 
-    // Add 4 corners of the square
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x - size,
-            y: center_y - size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
-    });
-
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x + size,
-            y: center_y - size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
-    });
+    if let Some(diameter) = options.registration_hole {
+        if diameter <= 0.0 || diameter >= options.margin - frame {
+            return Err(format!(
+                "Registration holes of {} mm do not fit in the {} mm margin",
+                diameter,
+                options.margin - frame
+            ));
+        }
+        let inset = frame + (options.margin - frame) / 2.0;
+        let segments = gerber::plot::circle_segments(diameter / 2.0, options.arc_tolerance);
+        for corner in rectangle(inset) {
+            cutouts.push(Polygon::new(regular_polygon(&corner, diameter / 2.0, segments, 0.0)));
+        }
+    }
 
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x + size,
-            y: center_y + size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
-    });
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
+        layer_type: LayerType::Paste,
+        side: Some(options.side),
+    };
+    let plate_area = difference(&[Polygon::new(plate.clone())], &cutouts)?;
+    extrude_polygons(&mut mesh, &plate_area, 0.0, options.thickness);
+
+    if frame > 0.0 {
+        let top = options.thickness;
+        extrude_outline_with_holes(
+            &mut mesh,
+            &plate,
+            &[rectangle(frame)],
+            top,
+            top + options.frame_height,
+        );
+    }
 
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x - size,
-            y: center_y + size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
-    });
+    Ok(mesh)
+}
 
-    // Add a face with the 4 vertices
-    faces.push(Face {
-        vertices: vec![0, 1, 2, 3],
-    });
+/// Creates the copper barrels lining plated drill holes.
+///
+/// Each barrel is a tube [`PLATING_THICKNESS`] thick around the finished
+/// hole or slot, running from the bottom of the bottom copper to the top of
+/// the top copper of the stackup so that it joins the pads on both sides.
+/// The rings of holes that overlap merge, so the barrels form closed,
+/// non-overlapping solids.
+///
+/// # Arguments
+///
+/// * `holes` - Drill holes; non-plated holes are ignored
+/// * `stackup` - Layer stackup of the board
+/// * `arc_tolerance` - Maximum chord deviation of the barrel walls in mm
+///
+/// # Returns
+///
+/// * `Result<Mesh, String>` - A copper mesh with one barrel per plated hole
+pub fn build_plated_barrels_mesh(
+    holes: &[excellon::types::Hole],
+    stackup: &Stackup,
+    arc_tolerance: f64,
+) -> Result<Mesh, String> {
+    let (z_bottom, z_top) = barrel_z_range(stackup);
 
-    let mesh = Mesh {
-        vertices,
-        faces,
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
         layer_type: LayerType::Copper,
+        side: None,
     };
+    extrude_polygons(&mut mesh, &plated_barrels(holes, arc_tolerance)?, z_bottom, z_top);
 
     Ok(mesh)
 }
 
+/// Z range of the plated barrels: from the bottom of the bottom copper to
+/// the top of the top copper. A single copper layer sits on the core, so
+/// the barrels still go down to the bottom of the board at Z = 0.
+fn barrel_z_range(stackup: &Stackup) -> (f64, f64) {
+    let (stack_bottom, stack_top) = stackup.z_range();
+    let bottom = stackup
+        .copper(Some(Side::Bottom), None)
+        .map_or(stack_bottom, |layer| layer.z_bottom);
+    let top = stackup
+        .copper(Some(Side::Top), None)
+        .map_or(stack_top, |layer| layer.z_top);
+    (bottom.min(0.0), top)
+}
+
+/// Cross sections of the barrels of the plated holes: rings
+/// [`PLATING_THICKNESS`] wide around each hole or slot, merged where they
+/// overlap. Both walls are flattened with `arc_tolerance` as the maximum
+/// chord deviation.
+fn plated_barrels(
+    holes: &[excellon::types::Hole],
+    arc_tolerance: f64,
+) -> Result<Vec<Polygon>, String> {
+    use geometry::shapes::stadium;
+
+    let (mut outer, mut inner) = (Vec::new(), Vec::new());
+    for hole in holes {
+        if hole.plating != excellon::types::Plating::Plated {
+            continue;
+        }
+        let radius = hole.diameter / 2.0;
+        let end = hole.end.as_ref().unwrap_or(&hole.position);
+        for (radius, walls) in [(radius + PLATING_THICKNESS, &mut outer), (radius, &mut inner)] {
+            let segments = gerber::plot::circle_segments(radius, arc_tolerance);
+            walls.push(Polygon::new(stadium(&hole.position, end, radius, segments)));
+        }
+    }
+    difference(&outer, &inner)
+}
+
+/// Creates a 3D mesh representing a copper layer (top or bottom).
+///
+/// The copper commands are composed with [`gerber::plot::plot_layer`]: every
+/// draw is stroked with the selected aperture, every flash stamps the aperture
+/// shape, and regions are filled. Each resulting polygon is extruded between
+/// the Z coordinates of the outer copper layer of a default two-layer
+/// [`Stackup`]: on top of the board for the top layer and below it for the
+/// bottom layer.
+///
+/// # Arguments
+///
+/// * `commands` - The parsed Gerber commands from the copper layer
+/// * `is_top` - Whether this is the top copper layer (`true`) or bottom (`false`)
+/// * `thickness` - PCB thickness in mm (optional, defaults to 1.6mm)
+///
+/// # Returns
+///
+/// * `Result<Mesh, String>` - A 3D mesh representing the copper layer
+pub fn build_copper_mesh(
+    commands: &[gerber::types::Command],
+    is_top: bool,
+    thickness: Option<f64>,
+) -> Result<Mesh, String> {
+    let pcb_thickness = thickness.unwrap_or(1.6); // Use provided thickness or default to 1.6mm
+
+    let stackup = Stackup::with_copper_layers(2, pcb_thickness);
+    let side = if is_top { Side::Top } else { Side::Bottom };
+    let layer = stackup
+        .copper(Some(side), None)
+        .ok_or("The stackup has no outer copper layer")?;
+    let (z_bottom, z_top) = (layer.z_bottom, layer.z_top);
+
+    build_layer_mesh(
+        commands,
+        LayerType::Copper,
+        side,
+        z_bottom,
+        z_top,
+        gerber::plot::ARC_TOLERANCE,
+    )
+}
+
 /// Creates a 3D mesh representing a silkscreen layer (top or bottom).
 ///
-/// Currently implements a placeholder visualization, but in a complete implementation
-/// would trace silkscreen text and symbols with proper height.
+/// Strokes, arcs, flashes and regions are composed with
+/// [`gerber::plot::plot_layer`] and extruded as a thin raised layer. The ink
+/// sits at the Z coordinates of the outer silkscreen layer of a default
+/// two-layer [`Stackup`], over the solder mask on top of the board for the
+/// top layer and underneath it for the bottom layer.
 ///
 /// # Arguments
 ///
-/// * `_commands` - The parsed Gerber commands from the silkscreen layer
+/// * `commands` - The parsed Gerber commands from the silkscreen layer
 /// * `is_top` - Whether this is the top silkscreen layer (`true`) or bottom (`false`)
 /// * `thickness` - PCB thickness in mm (optional, defaults to 1.6mm)
 ///
@@ -566,121 +1448,481 @@ pub fn build_copper_mesh(
 ///
 /// * `Result<Mesh, String>` - A 3D mesh representing the silkscreen layer
 pub fn build_silkscreen_mesh(
-    _commands: &[gerber::types::Command],
+    commands: &[gerber::types::Command],
     is_top: bool,
     thickness: Option<f64>,
 ) -> Result<Mesh, String> {
-    use intermediate::model::{Face, Point3D, Vertex};
-
-    // This is a placeholder implementation for silkscreen layers
-    // For a complete implementation, we would trace the silkscreen text and symbols
-
-    // Constants
     let pcb_thickness = thickness.unwrap_or(1.6); // Use provided thickness or default to 1.6mm
-    const SILKSCREEN_THICKNESS: f64 = 0.01; // Standard silkscreen thickness in mm
 
-    // For now, just create a simple rectangular placeholder mesh for silkscreen
-    let z_position = if is_top {
-        pcb_thickness + SILKSCREEN_THICKNESS
-    } else {
-        -SILKSCREEN_THICKNESS
+    let stackup = Stackup::with_copper_layers(2, pcb_thickness);
+    let side = if is_top { Side::Top } else { Side::Bottom };
+    let layer = stackup
+        .outer(StackupLayerKind::Silkscreen, side)
+        .ok_or("The stackup has no silkscreen layer")?;
+    let (z_bottom, z_top) = (layer.z_bottom, layer.z_top);
+
+    build_layer_mesh(
+        commands,
+        LayerType::Silkscreen,
+        side,
+        z_bottom,
+        z_top,
+        gerber::plot::ARC_TOLERANCE,
+    )
+}
+
+/// Creates a 3D mesh for any plotted layer between two Z coordinates.
+///
+/// The commands are composed with [`gerber::plot::plot_layer`], so that
+/// overlapping objects merge and clear objects erase, and every resulting
+/// polygon is extruded from `z_bottom` to `z_top`, typically the extents of
+/// the layer in the board [`Stackup`].
+///
+/// # Arguments
+///
+/// * `commands` - The parsed Gerber commands of the layer
+/// * `layer_type` - Layer type of the resulting mesh
+/// * `side` - Side of the board the layer is on
+/// * `z_bottom` - Z coordinate of the bottom of the layer
+/// * `z_top` - Z coordinate of the top of the layer
+/// * `arc_tolerance` - Maximum chord deviation of arcs in mm
+///
+/// # Returns
+///
+/// * `Result<Mesh, String>` - A 3D mesh representing the layer
+pub fn build_layer_mesh(
+    commands: &[gerber::types::Command],
+    layer_type: LayerType,
+    side: Side,
+    z_bottom: f64,
+    z_top: f64,
+    arc_tolerance: f64,
+) -> Result<Mesh, String> {
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
+        layer_type,
+        side: Some(side),
     };
 
-    // Create a small rectangle to represent silkscreen
-    let mut vertices = Vec::new();
-    let mut faces = Vec::new();
+    let polygons = gerber::plot::plot_layer(commands, arc_tolerance)?;
+    extrude_polygons(&mut mesh, &polygons, z_bottom, z_top);
 
-    // Dummy vertices for a small silkscreen square
-    let size = 5.0;
-    let center_x = 200.0;
-    let center_y = -90.0;
+    Ok(mesh)
+}
 
-    // Add 4 corners of the square
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x - size,
-            y: center_y - size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
-    });
+/// Extrudes a closed 2D outline into a prism and appends it to a mesh.
+///
+/// Each outline point becomes a top and a bottom vertex (interleaved). The
+/// caps are triangulated by ear clipping (see [`geometry::triangulate::triangulate`])
+/// and every wall quad is split into two triangles, so the mesh only holds
+/// triangles.
+///
+/// # Arguments
+///
+/// * `mesh` - The mesh to append the prism to
+/// * `outline` - Outline points, counter-clockwise for outward-facing walls
+/// * `z_bottom` - Z coordinate of the bottom cap
+/// * `z_top` - Z coordinate of the top cap
+pub fn extrude_outline(
+    mesh: &mut Mesh,
+    outline: &[gerber::types::Point],
+    z_bottom: f64,
+    z_top: f64,
+) {
+    use intermediate::model::{Point3D, Vertex};
+
+    if outline.len() < 3 {
+        return;
+    }
 
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x + size,
-            y: center_y - size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
-    });
+    let base = mesh.vertices.len();
+    let num_points = outline.len();
 
-    vertices.push(Vertex {
-        position: Point3D {
-            x: center_x + size,
-            y: center_y + size,
-            z: z_position,
-        },
-        normal: Point3D {
-            x: 0.0,
-            y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
-        },
+    // Create top and bottom vertices
+    for point in outline {
+        // Top vertex
+        mesh.vertices.push(Vertex {
+            position: Point3D {
+                x: point.x,
+                y: point.y,
+                z: z_top,
+            },
+            normal: Point3D {
+                x: 0.0,
+                y: 0.0,
+                z: 1.0,
+            },
+        });
+
+        // Bottom vertex
+        mesh.vertices.push(Vertex {
+            position: Point3D {
+                x: point.x,
+                y: point.y,
+                z: z_bottom,
+            },
+            normal: Point3D {
+                x: 0.0,
+                y: 0.0,
+                z: -1.0,
+            },
+        });
+    }
+
+    // Caps (even indices are top vertices, odd indices bottom vertices)
+    push_cap_triangles(mesh, base, outline);
+
+    // Side faces (quads connecting top and bottom)
+    for i in 0..num_points {
+        let next_i = (i + 1) % num_points;
+        push_wall_triangles(mesh, base + i * 2, base + next_i * 2);
+    }
+}
+
+/// Appends the triangles of a top and a bottom cap to a mesh.
+///
+/// The cap vertices are interleaved from `base`: top vertex `base + 2i`
+/// and bottom vertex `base + 2i + 1` for outline point `i`. The bottom
+/// triangles are reversed so that they face down.
+fn push_cap_triangles(mesh: &mut Mesh, base: usize, outline: &[gerber::types::Point]) {
+    use intermediate::model::Face;
+
+    for [a, b, c] in geometry::triangulate::triangulate(outline) {
+        mesh.faces.push(Face {
+            vertices: vec![base + a * 2, base + b * 2, base + c * 2],
+        });
+        mesh.faces.push(Face {
+            vertices: vec![base + c * 2 + 1, base + b * 2 + 1, base + a * 2 + 1],
+        });
+    }
+}
+
+/// Appends a wall quad between two top vertices and the bottom vertices
+/// following them as two triangles.
+fn push_wall_triangles(mesh: &mut Mesh, top: usize, next_top: usize) {
+    use intermediate::model::Face;
+
+    mesh.faces.push(Face {
+        vertices: vec![top, top + 1, next_top + 1],
     });
+    mesh.faces.push(Face {
+        vertices: vec![top, next_top + 1, next_top],
+    });
+}
+
+/// Extrudes an outline with holes into a solid and appends it to a mesh.
+///
+/// The caps are the outline with the holes bridged in (see
+/// [`geometry::polygon::bridge_holes`]), triangulated by ear clipping. The
+/// walls of the outline face outwards and the walls of the holes face into
+/// the holes, each quad split into two triangles.
+///
+/// # Arguments
+///
+/// * `mesh` - The mesh to append the solid to
+/// * `outline` - Outer outline points, counter-clockwise
+/// * `holes` - Hole outlines lying inside the outline
+/// * `z_bottom` - Z coordinate of the bottom cap
+/// * `z_top` - Z coordinate of the top cap
+pub fn extrude_outline_with_holes(
+    mesh: &mut Mesh,
+    outline: &[gerber::types::Point],
+    holes: &[Vec<gerber::types::Point>],
+    z_bottom: f64,
+    z_top: f64,
+) {
+    use intermediate::model::{Point3D, Vertex};
+
+    if holes.is_empty() {
+        extrude_outline(mesh, outline, z_bottom, z_top);
+        return;
+    }
+    if outline.len() < 3 {
+        return;
+    }
 
-    vertices.push(Vertex {
+    let vertex = |point: &gerber::types::Point, z: f64, normal_z: f64| Vertex {
         position: Point3D {
-            x: center_x - size,
-            y: center_y + size,
-            z: z_position,
+            x: point.x,
+            y: point.y,
+            z,
         },
         normal: Point3D {
             x: 0.0,
             y: 0.0,
-            z: if is_top { 1.0 } else { -1.0 },
+            z: normal_z,
         },
-    });
+    };
 
-    // Add a face with the 4 vertices
-    faces.push(Face {
-        vertices: vec![0, 1, 2, 3],
-    });
+    // Caps: interleaved top and bottom vertices of the bridged polygon
+    let cap = geometry::polygon::bridge_holes(outline, holes);
+    let base = mesh.vertices.len();
+    for point in &cap {
+        mesh.vertices.push(vertex(point, z_top, 1.0));
+        mesh.vertices.push(vertex(point, z_bottom, -1.0));
+    }
+    push_cap_triangles(mesh, base, &cap);
+
+    // Walls: outline counter-clockwise, holes clockwise
+    let mut walls = vec![outline.to_vec()];
+    for hole in holes {
+        let mut hole = hole.clone();
+        if geometry::polygon::signed_area(&hole) > 0.0 {
+            hole.reverse();
+        }
+        walls.push(hole);
+    }
+    for wall in walls {
+        let base = mesh.vertices.len();
+        let n = wall.len();
+        for point in &wall {
+            mesh.vertices.push(vertex(point, z_top, 1.0));
+            mesh.vertices.push(vertex(point, z_bottom, -1.0));
+        }
+        for i in 0..n {
+            push_wall_triangles(mesh, base + i * 2, base + (i + 1) % n * 2);
+        }
+    }
+}
 
-    let mesh = Mesh {
-        vertices,
-        faces,
-        layer_type: LayerType::Silkscreen,
+/// Extrudes polygons with holes into solids and appends them to a mesh.
+///
+/// # Arguments
+///
+/// * `mesh` - The mesh to append the solids to
+/// * `polygons` - The polygons to extrude
+/// * `z_bottom` - Z coordinate of the bottom caps
+/// * `z_top` - Z coordinate of the top caps
+pub fn extrude_polygons(mesh: &mut Mesh, polygons: &[Polygon], z_bottom: f64, z_top: f64) {
+    for polygon in polygons {
+        extrude_outline_with_holes(mesh, &polygon.outer, &polygon.holes, z_bottom, z_top);
+    }
+}
+
+/// Returns `true` if the path is a Gerber job file (`.gbrjob`).
+pub fn is_job_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("gbrjob"))
+}
+
+/// File extensions recognized as Gerber layers, including the Protel style
+/// extensions many fab houses rename files to.
+const GERBER_EXTENSIONS: [&str; 12] = [
+    "gbr", "ger", "gtl", "gbl", "gto", "gbo", "gts", "gbs", "gtp", "gbp", "gko", "gm1",
+];
+
+/// File extensions recognized as Excellon drill files.
+const DRILL_EXTENSIONS: [&str; 3] = ["drl", "xln", "exc"];
+
+/// Returns `true` if the path has an Excellon drill file extension.
+pub fn is_drill_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            DRILL_EXTENSIONS.contains(&ext.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// Guesses the plating of a drill file from its name: KiCad and most other
+/// tools put "NPTH" in the name of the non-plated file.
+pub fn plating_from_file_name(file_path: &Path) -> excellon::types::Plating {
+    if file_path.to_string_lossy().to_lowercase().contains("npth") {
+        excellon::types::Plating::NonPlated
+    } else {
+        excellon::types::Plating::Plated
+    }
+}
+
+/// Returns `true` if the path has a Gerber file extension.
+pub fn is_gerber_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy().to_lowercase();
+            GERBER_EXTENSIONS.contains(&ext.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// Identifies the layer of a Gerber file.
+///
+/// The X2 `.FileFunction` attribute decides when the file carries one; the
+/// file name is only used as a fallback.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the Gerber file
+/// * `gerber` - The parsed file, if available
+///
+/// # Returns
+///
+/// * `Option<LayerInfo>` - The identified layer, or `None` if it is not recognized
+pub fn identify_layer(file_path: &Path, gerber: Option<&GerberFile>) -> Option<LayerInfo> {
+    gerber
+        .and_then(|gerber| gerber.file_attribute(".FileFunction"))
+        .and_then(layer_from_file_function)
+        .or_else(|| layer_from_file_name(file_path))
+}
+
+/// Identifies a file from its `FileFunction` in a Gerber job file.
+///
+/// # Arguments
+///
+/// * `job` - The parsed job file
+/// * `file_path` - Path to the Gerber file, matched by file name
+///
+/// # Returns
+///
+/// * `Option<LayerInfo>` - The layer, or `None` if the job does not list the file
+pub fn layer_from_job(job: &gerber::job::JobFile, file_path: &Path) -> Option<LayerInfo> {
+    let file_name = file_path.file_name()?.to_string_lossy();
+    let entry = job.file(&file_name)?;
+    let info = layer_from_file_function(&entry.file_function)?;
+    Some(LayerInfo {
+        source: LayerSource::JobFile,
+        ..info
+    })
+}
+
+/// Maps the values of a `.FileFunction` attribute to a layer.
+///
+/// For example `Copper,L2,Bot` is the bottom copper layer with index 2, and
+/// `Profile,NP` is the board outline. Functions without a 3D representation
+/// (drawings, assembly, ...) yield `None`.
+pub fn layer_from_file_function(values: &[String]) -> Option<LayerInfo> {
+    let side = |value: Option<&String>| match value.map(|v| v.as_str()) {
+        Some("Top") => Some(Side::Top),
+        Some("Inr") => Some(Side::Inner),
+        Some("Bot") => Some(Side::Bottom),
+        _ => None,
+    };
+    let layer = |layer_type, side| LayerInfo {
+        layer_type,
+        side,
+        copper_index: None,
+        source: LayerSource::FileFunction,
     };
 
-    Ok(mesh)
+    match values.first()?.as_str() {
+        "Copper" => {
+            let copper_index = values
+                .get(1)
+                .and_then(|value| value.strip_prefix('L'))
+                .and_then(|index| index.parse().ok());
+            Some(LayerInfo {
+                copper_index,
+                ..layer(LayerType::Copper, side(values.get(2)))
+            })
+        }
+        "Profile" => Some(layer(LayerType::EdgeCuts, None)),
+        // Job files spell these SolderMask and SolderPaste
+        "Soldermask" | "SolderMask" => Some(layer(LayerType::Soldermask, side(values.get(1)))),
+        "Legend" => Some(layer(LayerType::Silkscreen, side(values.get(1)))),
+        "Paste" | "SolderPaste" => Some(layer(LayerType::Paste, side(values.get(1)))),
+        "Plated" | "NonPlated" => Some(layer(LayerType::Drill, None)),
+        _ => None,
+    }
 }
 
-/// Helper function to identify the likely layer type based on file name
-pub fn identify_layer_type(file_path: &Path) -> LayerType {
-    let file_name = file_path.file_name().unwrap().to_string_lossy().to_lowercase();
-    
-    if file_name.contains("edge") || file_name.contains("outline") || file_name.contains("cuts") {
-        LayerType::EdgeCuts
-    } else if file_name.contains("f.cu") || file_name.contains("f_cu") || file_name.contains("top.cu") {
-        LayerType::Copper
-    } else if file_name.contains("b.cu") || file_name.contains("b_cu") || file_name.contains("bottom.cu") {
-        LayerType::Copper
-    } else if file_name.contains("f.silk") || file_name.contains("f_silk") || file_name.contains("top.silk") {
-        LayerType::Silkscreen
-    } else if file_name.contains("b.silk") || file_name.contains("b_silk") || file_name.contains("bottom.silk") {
-        LayerType::Silkscreen
+/// Guesses the layer of a file from common KiCad and Protel naming conventions.
+fn layer_from_file_name(file_path: &Path) -> Option<LayerInfo> {
+    let file_name = file_path.file_name()?.to_string_lossy().to_lowercase();
+    let extension = file_path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let matches = |patterns: &[&str]| patterns.iter().any(|p| file_name.contains(p));
+    let layer = |layer_type, side| LayerInfo {
+        layer_type,
+        side,
+        copper_index: None,
+        source: LayerSource::FileName,
+    };
+
+    // Protel extensions encode the layer and side directly
+    let by_extension = match extension.as_str() {
+        "gtl" => Some(layer(LayerType::Copper, Some(Side::Top))),
+        "gbl" => Some(layer(LayerType::Copper, Some(Side::Bottom))),
+        "gto" => Some(layer(LayerType::Silkscreen, Some(Side::Top))),
+        "gbo" => Some(layer(LayerType::Silkscreen, Some(Side::Bottom))),
+        "gts" => Some(layer(LayerType::Soldermask, Some(Side::Top))),
+        "gbs" => Some(layer(LayerType::Soldermask, Some(Side::Bottom))),
+        "gtp" => Some(layer(LayerType::Paste, Some(Side::Top))),
+        "gbp" => Some(layer(LayerType::Paste, Some(Side::Bottom))),
+        "gko" | "gm1" => Some(layer(LayerType::EdgeCuts, None)),
+        "drl" | "xln" | "exc" => Some(layer(LayerType::Drill, None)),
+        _ => None,
+    };
+    if let Some(mut info) = by_extension {
+        if info.layer_type == LayerType::Copper && info.side == Some(Side::Top) {
+            info.copper_index = Some(1);
+        }
+        return Some(info);
+    }
+
+    // KiCad style names: F.Cu, B_SilkS, Top.Mask, ...
+    let side_of = |layer: &str| {
+        let named = |prefixes: [&str; 3]| {
+            prefixes
+                .iter()
+                .any(|prefix| file_name.contains(&format!("{}{}", prefix, layer)))
+        };
+        if named(["f.", "f_", "top."]) {
+            Some(Side::Top)
+        } else if named(["b.", "b_", "bottom."]) {
+            Some(Side::Bottom)
+        } else {
+            None
+        }
+    };
+
+    if matches(&["edge", "outline", "cuts"]) {
+        Some(layer(LayerType::EdgeCuts, None))
+    } else if let Some(inner) = inner_copper_index(&file_name) {
+        Some(LayerInfo {
+            copper_index: Some(inner + 1),
+            ..layer(LayerType::Copper, Some(Side::Inner))
+        })
+    } else if let Some(side) = side_of("cu") {
+        Some(LayerInfo {
+            copper_index: (side == Side::Top).then_some(1),
+            ..layer(LayerType::Copper, Some(side))
+        })
+    } else if let Some(side) = side_of("silk") {
+        Some(layer(LayerType::Silkscreen, Some(side)))
+    } else if let Some(side) = side_of("mask") {
+        Some(layer(LayerType::Soldermask, Some(side)))
     } else {
-        LayerType::EdgeCuts // Default
+        side_of("paste").map(|side| layer(LayerType::Paste, Some(side)))
     }
 }
 
+/// Extracts `n` from KiCad inner copper names like `In1.Cu` or `In1_Cu`.
+fn inner_copper_index(file_name: &str) -> Option<u32> {
+    file_name.match_indices("in").find_map(|(start, _)| {
+        let rest = &file_name[start + 2..];
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let suffix = &rest[digits.len()..];
+        if !digits.is_empty() && (suffix.starts_with(".cu") || suffix.starts_with("_cu")) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// Helper function to identify the likely layer type based on file name
+///
+/// Returns `None` when the name does not match any known convention. Prefer
+/// [`identify_layer`], which also looks at the `.FileFunction` attribute.
+pub fn identify_layer_type(file_path: &Path) -> Option<LayerType> {
+    layer_from_file_name(file_path).map(|info| info.layer_type)
+}
+
 /// Helper function to analyze Gerber commands and return statistics
 pub fn analyze_gerber_commands(commands: &[gerber::types::Command]) -> (usize, usize, usize, usize) {
     use gerber::types::Command;
@@ -3,7 +3,19 @@
 //! Command-line interface for the Gerber to USDZ converter.
 
 use clap::{Parser, Subcommand, ValueEnum};
-use pcbgen::{analyze_gerber_commands, identify_layer_type, open_file, process_gerber_files};
+use pcbgen::excellon::parse::parse_excellon;
+use pcbgen::gerber::job::{JobFile, parse_job_file};
+use pcbgen::gerber::plot::ARC_TOLERANCE;
+use pcbgen::gerber::types::{Attributes, Command, Diagnostic};
+use pcbgen::intermediate::model::{LayerInfo, LayerSource, LayerType, Side};
+use pcbgen::intermediate::stackup::Stackup;
+use pcbgen::usdz::export::StlOptions;
+use pcbgen::{
+    DEFAULT_THICKNESS, ProcessOptions, StencilOptions, analyze_gerber_commands, board_outline,
+    generate_stencil,
+    identify_layer, is_drill_file, is_gerber_file, is_job_file, layer_from_job, open_file,
+    plating_from_file_name, process_board_solids, process_gerber_files_with_options,
+};
 use std::path::Path;
 
 /// pcbgen - Turn flat PCB files into beautiful 3D models
@@ -38,9 +50,9 @@ enum Commands {
         #[arg(short, long, value_enum, default_value_t = Format::Obj)]
         format: Format,
 
-        /// PCB thickness in mm
-        #[arg(short, long, default_value_t = 1.6)]
-        thickness: f64,
+        /// PCB thickness in mm [default: from the job file, else 1.6]
+        #[arg(short, long)]
+        thickness: Option<f64>,
 
         /// Enable colored visualization
         #[arg(short, long)]
@@ -49,6 +61,74 @@ enum Commands {
         /// Automatically open the model after creation
         #[arg(short, long)]
         preview: bool,
+
+        /// Fail if any Gerber block cannot be parsed, an Edge Cuts contour is not closed or a
+        /// layer cannot be plotted
+        #[arg(long)]
+        strict: bool,
+
+        /// Stackup file (JSON with a MaterialStackup array, as in a .gbrjob)
+        #[arg(long, value_name = "FILE")]
+        stackup: Option<String>,
+
+        /// Maximum deviation of arc segments from the true arc in mm
+        #[arg(long, value_name = "MM", default_value_t = ARC_TOLERANCE)]
+        arc_tolerance: f64,
+
+        /// Write ASCII instead of binary STL
+        #[arg(long)]
+        ascii: bool,
+
+        /// Write one STL file per layer instead of a single solid
+        #[arg(long)]
+        per_layer: bool,
+
+        /// Add the copper layers and plated hole barrels to STEP exports
+        #[arg(long)]
+        copper: bool,
+    },
+
+    /// Generate a solder paste stencil (STL) from a paste layer
+    Stencil {
+        /// Directory containing Gerber files
+        #[arg(short, long)]
+        input: String,
+
+        /// Output file path (without extension)
+        #[arg(short, long, default_value = "output/stencil")]
+        output: String,
+
+        /// Board side of the paste layer
+        #[arg(short, long, value_enum, default_value_t = BoardSide::Top)]
+        side: BoardSide,
+
+        /// Stencil plate thickness in mm
+        #[arg(short, long, default_value_t = 0.12)]
+        thickness: f64,
+
+        /// Width of the plate around the board outline in mm
+        #[arg(short, long, default_value_t = 10.0)]
+        margin: f64,
+
+        /// Add a stiffening frame of this width (mm) along the plate edge
+        #[arg(long, value_name = "WIDTH")]
+        frame: Option<f64>,
+
+        /// Height of the frame above the plate in mm
+        #[arg(long, default_value_t = 1.0)]
+        frame_height: f64,
+
+        /// Cut registration holes of this diameter (mm) in the plate corners
+        #[arg(long, value_name = "DIAMETER")]
+        registration_holes: Option<f64>,
+
+        /// Fail if any Gerber block cannot be parsed or an Edge Cuts contour is not closed
+        #[arg(long)]
+        strict: bool,
+
+        /// Maximum deviation of arc segments from the true arc in mm
+        #[arg(long, value_name = "MM", default_value_t = ARC_TOLERANCE)]
+        arc_tolerance: f64,
     },
 
     /// Inspect and analyze Gerber files without conversion
@@ -60,6 +140,10 @@ enum Commands {
         /// Show detailed layer information
         #[arg(short, long)]
         detailed: bool,
+
+        /// Treat any Gerber block that cannot be parsed as an error
+        #[arg(long)]
+        strict: bool,
     },
 }
 
@@ -71,6 +155,21 @@ enum Format {
     Usdz,
     /// STL format - Industry standard for 3D printing and CAD
     Stl,
+    /// glTF 2.0 (.gltf with a .bin) - For web viewers, with metallic copper
+    Gltf,
+    /// Binary glTF 2.0 - The same in a single file
+    Glb,
+    /// 3MF format - For multi-material 3D printing, one colored object per layer
+    #[value(name = "3mf")]
+    ThreeMf,
+    /// STEP AP214 - The board solid with exact holes, for mechanical CAD
+    Step,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum BoardSide {
+    Top,
+    Bottom,
 }
 
 /// Main entry point for the application.
@@ -111,9 +210,15 @@ fn main() {
         input: String::from("."),
         output: String::from("output/pcb_model"),
         format: Format::Obj,
-        thickness: 1.6,
+        thickness: None,
         colors: false,
         preview: false,
+        strict: false,
+        stackup: None,
+        arc_tolerance: ARC_TOLERANCE,
+        ascii: false,
+        per_layer: false,
+        copper: false,
     }) {
         Commands::Convert {
             input,
@@ -122,23 +227,71 @@ fn main() {
             thickness,
             colors,
             preview,
+            strict,
+            stackup,
+            arc_tolerance,
+            ascii,
+            per_layer,
+            copper,
         } => {
+            let options = ProcessOptions {
+                thickness,
+                strict,
+                stackup,
+                arc_tolerance: Some(arc_tolerance),
+            };
+            let stl = StlOptions { ascii, per_layer };
             convert_command(
-                &input, &output, format, thickness, colors, preview, log_level, cli.quiet,
+                &input, &output, format, &options, stl, copper, colors, preview, log_level,
+                cli.quiet,
             );
         }
-        Commands::Info { input, detailed } => {
-            info_command(&input, detailed, log_level, cli.quiet);
+        Commands::Stencil {
+            input,
+            output,
+            side,
+            thickness,
+            margin,
+            frame,
+            frame_height,
+            registration_holes,
+            strict,
+            arc_tolerance,
+        } => {
+            let options = StencilOptions {
+                side: match side {
+                    BoardSide::Top => Side::Top,
+                    BoardSide::Bottom => Side::Bottom,
+                },
+                thickness,
+                margin,
+                frame_width: frame,
+                frame_height,
+                registration_hole: registration_holes,
+                strict,
+                arc_tolerance,
+            };
+            stencil_command(&input, &output, &options, log_level, cli.quiet);
+        }
+        Commands::Info {
+            input,
+            detailed,
+            strict,
+        } => {
+            info_command(&input, detailed, strict, log_level, cli.quiet);
         }
     }
 }
 
 /// The convert subcommand - processes Gerber files and exports a 3D model
+#[allow(clippy::too_many_arguments)]
 fn convert_command(
     input: &str,
     output: &str,
     format: Format,
-    thickness: f64,
+    options: &ProcessOptions,
+    stl: StlOptions,
+    copper: bool,
     colors: bool,
     preview: bool,
     log_level: u8,
@@ -147,7 +300,16 @@ fn convert_command(
     if log_level > 0 {
         println!("\nInput directory: {}", input);
         println!("Converting to: {}.{:?}", output, format);
-        println!("PCB thickness: {}mm", thickness);
+        match options.thickness {
+            Some(thickness) => println!("PCB thickness: {}mm", thickness),
+            None => println!("PCB thickness: from the job file, else {}mm", DEFAULT_THICKNESS),
+        }
+        if let Some(stackup) = &options.stackup {
+            println!("Stackup file: {}", stackup);
+        }
+        if let Some(tolerance) = options.arc_tolerance {
+            println!("Arc tolerance: {}mm", tolerance);
+        }
         
         if colors {
             println!("Color visualization enabled");
@@ -160,8 +322,14 @@ fn convert_command(
         println!("\nScanning for Gerber files...");
     }
 
+    // STEP describes the board with exact solids rather than meshes
+    if format == Format::Step {
+        step_command(input, output, options, copper, preview, quiet);
+        return;
+    }
+
     // Process Gerber files and build a 3D model
-    let pcb_model = process_gerber_files(input, thickness).unwrap_or_else(|e| {
+    let pcb_model = process_gerber_files_with_options(input, options).unwrap_or_else(|e| {
         eprintln!("\nError processing Gerber files: {}", e);
         eprintln!("Try using 'pcbgen info' to analyze your Gerber files before conversion.");
         std::process::exit(1);
@@ -169,18 +337,32 @@ fn convert_command(
 
     // Print model info if not in quiet mode
     if log_level > 0 {
+        let metadata = &pcb_model.metadata;
+        if let Some(project) = &metadata.project {
+            match &metadata.revision {
+                Some(revision) => println!("\nProject: {} ({})", project, revision),
+                None => println!("\nProject: {}", project),
+            }
+        }
+        if let Some((x, y)) = metadata.size {
+            println!("Board size: {} x {} mm", x, y);
+        }
+        println!("Board thickness: {}mm", metadata.thickness);
+
         println!("\nPCB Model created successfully with:");
         println!("   - {} mesh components", pcb_model.meshes.len());
         
         // Count different layer types
         let mut edge_cuts = 0;
         let mut copper = 0;
+        let mut soldermask = 0;
         let mut silkscreen = 0;
         
         for mesh in &pcb_model.meshes {
             match mesh.layer_type {
                 pcbgen::intermediate::model::LayerType::EdgeCuts => edge_cuts += 1,
                 pcbgen::intermediate::model::LayerType::Copper => copper += 1,
+                pcbgen::intermediate::model::LayerType::Soldermask => soldermask += 1,
                 pcbgen::intermediate::model::LayerType::Silkscreen => silkscreen += 1,
                 _ => {}
             }
@@ -188,6 +370,7 @@ fn convert_command(
         
         println!("   - {} Edge Cuts layer(s)", edge_cuts);
         println!("   - {} Copper layer(s)", copper);
+        println!("   - {} Soldermask layer(s)", soldermask);
         println!("   - {} Silkscreen layer(s)", silkscreen);
         
         println!("\nExporting model...");
@@ -203,7 +386,7 @@ fn convert_command(
                         println!("\nSuccessfully exported model to {}", output_path);
                         println!("   Format: OBJ with materials (.mtl)");
                         if colors {
-                            println!("   Colors: Enabled (Edge Cuts=Green, Top Copper=Red, Bottom Copper=Blue)");
+                            println!("   Colors: Enabled (Edge Cuts=Green, Top Copper=Red, Inner Copper=Orange, Bottom Copper=Blue, Soldermask=Dark green)");
                         }
                     }
 
@@ -236,19 +419,184 @@ fn convert_command(
                         open_file(&output_path);
                     }
                 }
-                Err(e) => eprintln!("Error: {}", e),
+                Err(e) => {
+                    eprintln!("Error exporting to USDZ: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Format::Gltf | Format::Glb => {
+            let (output_path, result, description) = if format == Format::Glb {
+                let output_path = format!("{}.glb", output);
+                let result = pcbgen::usdz::gltf::export_to_glb(&pcb_model, &output_path);
+                (output_path, result, "GLB (binary glTF 2.0)")
+            } else {
+                let output_path = format!("{}.gltf", output);
+                let result = pcbgen::usdz::gltf::export_to_gltf(&pcb_model, &output_path);
+                (output_path, result, "glTF 2.0 with a .bin buffer")
+            };
+            match result {
+                Ok(_) => {
+                    if !quiet {
+                        println!("\nSuccessfully exported model to {}", output_path);
+                        println!("   Format: {} with PBR materials", description);
+                    }
+
+                    // Open the file if preview is requested
+                    if preview {
+                        if !quiet {
+                            println!("Opening model in default viewer...");
+                        }
+                        open_file(&output_path);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error exporting to glTF: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Format::ThreeMf => {
+            let output_path = format!("{}.3mf", output);
+            match pcbgen::usdz::threemf::export_to_3mf(&pcb_model, &output_path) {
+                Ok(_) => {
+                    if !quiet {
+                        println!("\nSuccessfully exported model to {}", output_path);
+                        println!("   Format: 3MF with one colored object per layer");
+                    }
+
+                    // Open the file if preview is requested
+                    if preview {
+                        if !quiet {
+                            println!("Opening model in default viewer...");
+                        }
+                        open_file(&output_path);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error exporting to 3MF: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
+        Format::Step => unreachable!("STEP is exported by step_command"),
         Format::Stl => {
-            eprintln!("STL export format not yet implemented");
-            eprintln!("Please use OBJ or USDZ format for now. STL support coming soon!");
+            let output_path = format!("{}.stl", output);
+            match pcbgen::usdz::export::export_to_stl(&pcb_model, &output_path, &stl) {
+                Ok(paths) => {
+                    if !quiet {
+                        let encoding = if stl.ascii { "ASCII" } else { "binary" };
+                        if stl.per_layer {
+                            println!("\nSuccessfully exported {} layers:", paths.len());
+                            for path in &paths {
+                                println!("   - {}", path);
+                            }
+                            println!("   Format: STL ({}), one file per layer", encoding);
+                        } else {
+                            println!("\nSuccessfully exported model to {}", output_path);
+                            println!("   Format: STL ({})", encoding);
+                        }
+                    }
+
+                    // Open the file if preview is requested
+                    if preview && let Some(path) = paths.first() {
+                        if !quiet {
+                            println!("Opening model in default viewer...");
+                        }
+                        open_file(path);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error exporting to STL: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Exports the board solid to STEP, for the convert subcommand
+fn step_command(
+    input: &str,
+    output: &str,
+    options: &ProcessOptions,
+    copper: bool,
+    preview: bool,
+    quiet: bool,
+) {
+    let model = process_board_solids(input, options, copper).unwrap_or_else(|e| {
+        eprintln!("\nError processing Gerber files: {}", e);
+        eprintln!("Try using 'pcbgen info' to analyze your Gerber files before conversion.");
+        std::process::exit(1);
+    });
+
+    let output_path = format!("{}.step", output);
+    match pcbgen::usdz::step::export_to_step(&model, &output_path) {
+        Ok(_) => {
+            if !quiet {
+                println!("\nSuccessfully exported model to {}", output_path);
+                println!("   Format: STEP AP214 with {} solid(s)", model.prisms.len());
+            }
+
+            // Open the file if preview is requested
+            if preview {
+                if !quiet {
+                    println!("Opening model in default viewer...");
+                }
+                open_file(&output_path);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error exporting to STEP: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The stencil subcommand - turns a paste layer into a printable stencil
+fn stencil_command(
+    input: &str,
+    output: &str,
+    options: &StencilOptions,
+    log_level: u8,
+    quiet: bool,
+) {
+    if log_level > 0 {
+        println!("\nInput directory: {}", input);
+        println!("Generating {:?} stencil: {}.stl", options.side, output);
+        println!("Plate thickness: {}mm, margin: {}mm", options.thickness, options.margin);
+        if let Some(frame) = options.frame_width {
+            println!("Frame: {}mm wide, {}mm high", frame, options.frame_height);
+        }
+        if let Some(diameter) = options.registration_hole {
+            println!("Registration holes: {}mm", diameter);
+        }
+        println!("Arc tolerance: {}mm", options.arc_tolerance);
+        println!("\nScanning for Gerber files...");
+    }
+
+    let stencil = generate_stencil(input, options).unwrap_or_else(|e| {
+        eprintln!("\nError generating stencil: {}", e);
+        std::process::exit(1);
+    });
+
+    let output_path = format!("{}.stl", output);
+    match pcbgen::usdz::export::export_to_stl(&stencil, &output_path, &StlOptions::default()) {
+        Ok(_) => {
+            if !quiet {
+                println!("\nSuccessfully exported stencil to {}", output_path);
+                println!("   Format: STL (binary)");
+            }
+        }
+        Err(e) => {
+            eprintln!("Error exporting to STL: {}", e);
             std::process::exit(1);
         }
     }
 }
 
 /// The info subcommand - analyzes Gerber files and displays information
-fn info_command(input: &str, detailed: bool, log_level: u8, _quiet: bool) {
+fn info_command(input: &str, detailed: bool, strict: bool, log_level: u8, _quiet: bool) {
     let input_path = Path::new(input);
 
     if !input_path.exists() {
@@ -256,16 +604,25 @@ fn info_command(input: &str, detailed: bool, log_level: u8, _quiet: bool) {
         std::process::exit(1);
     }
 
-    if input_path.is_file() {
+    if input_path.is_file() && is_drill_file(input_path) {
+        println!("\nAnalyzing drill file: {}", input);
+        print_drill_info(input_path, strict, true, "  ");
+    } else if input_path.is_file() {
         // Analyze a single Gerber file
         println!("\nAnalyzing Gerber file: {}", input);
 
         match std::fs::read_to_string(input) {
-            Ok(content) => match pcbgen::gerber::parse::parse_gerber(&content) {
-                Ok(commands) => {
+            Ok(content) => match pcbgen::gerber::parse::parse_gerber_file(&content, strict) {
+                Ok(gerber) => {
+                    let layer = identify_layer(input_path, Some(&gerber));
+                    let commands = gerber.commands;
                     println!("  Valid Gerber file with {} commands", commands.len());
+                    print_diagnostics(&gerber.diagnostics, "  ");
+                    print_open_contours(layer.as_ref(), &commands, "  ");
 
                     if detailed {
+                        print_file_attributes(&gerber.file_attributes, "    ");
+
                         let (move_count, draw_count, arc_count, other_count) =
                             analyze_gerber_commands(&commands);
 
@@ -291,17 +648,37 @@ fn info_command(input: &str, detailed: bool, log_level: u8, _quiet: bool) {
         });
 
         let mut gerber_files = Vec::new();
+        let mut drill_files = Vec::new();
+        let mut job_files = Vec::new();
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if is_gerber_file(&path) {
+                gerber_files.push(path);
+            } else if is_drill_file(&path) {
+                drill_files.push(path);
+            } else if is_job_file(&path) {
+                job_files.push(path);
+            }
+        }
 
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Some(ext) = path.extension() {
-                    if ext == "gbr" || ext == "GBR" {
-                        gerber_files.push(path);
-                    }
+        // The job file, if any, decides the layer of the files it lists
+        let job = job_files.first().and_then(|path| {
+            println!("Job file: {}", path.file_name().unwrap().to_string_lossy());
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| parse_job_file(&content));
+            match parsed {
+                Ok(job) => {
+                    print_job_summary(&job, "  ");
+                    Some(job)
+                }
+                Err(e) => {
+                    println!("  Not a valid job file: {}", e);
+                    None
                 }
             }
-        }
+        });
 
         if gerber_files.is_empty() {
             println!("No Gerber files found in directory");
@@ -312,39 +689,203 @@ fn info_command(input: &str, detailed: bool, log_level: u8, _quiet: bool) {
                 let file_name = file.file_name().unwrap().to_string_lossy();
                 println!("  {}", file_name);
 
+                // Every file is parsed for its diagnostics; the layer,
+                // attributes and statistics are only shown in detail
+                let content = match std::fs::read_to_string(&file) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        println!("    Error reading file: {}", e);
+                        continue;
+                    }
+                };
+                let gerber = match pcbgen::gerber::parse::parse_gerber_file(&content, strict) {
+                    Ok(gerber) => gerber,
+                    Err(e) => {
+                        println!("    Not a valid Gerber file: {}", e);
+                        continue;
+                    }
+                };
                 if detailed {
-                    // Analyze each file if detailed info is requested
-                    match std::fs::read_to_string(&file) {
-                        Ok(content) => {
-                            match pcbgen::gerber::parse::parse_gerber(&content) {
-                                Ok(commands) => {
-                                    println!(
-                                        "    Valid Gerber file with {} commands",
-                                        commands.len()
-                                    );
-
-                                    // Try to identify layer type
-                                    let layer_type = identify_layer_type(&file);
-                                    println!("    Likely layer type: {:?}", layer_type);
-
-                                    if log_level > 1 {
-                                        let (move_count, draw_count, arc_count, other_count) =
-                                            analyze_gerber_commands(&commands);
-
-                                        println!("    Command statistics:");
-                                        println!("      Move commands: {}", move_count);
-                                        println!("      Draw commands: {}", draw_count);
-                                        println!("      Arc commands: {}", arc_count);
-                                        println!("      Other commands: {}", other_count);
-                                    }
-                                }
-                                Err(e) => println!("    Not a valid Gerber file: {}", e),
-                            }
-                        }
-                        Err(e) => println!("    Error reading file: {}", e),
+                    println!("    Valid Gerber file with {} commands", gerber.commands.len());
+                }
+                print_diagnostics(&gerber.diagnostics, "    ");
+
+                // Identify the layer, from the job file or X2 attributes if
+                // present; the Edge Cuts layer is checked for open contours
+                let from_job = job.as_ref().and_then(|job| layer_from_job(job, &file));
+                let layer = from_job.or_else(|| identify_layer(&file, Some(&gerber)));
+                print_open_contours(layer.as_ref(), &gerber.commands, "    ");
+
+                if detailed {
+                    match layer {
+                        Some(info) => println!("    Layer: {}", describe_layer(&info)),
+                        None => println!("    Layer: unrecognized"),
+                    }
+                    print_file_attributes(&gerber.file_attributes, "    ");
+
+                    if log_level > 1 {
+                        let (move_count, draw_count, arc_count, other_count) =
+                            analyze_gerber_commands(&gerber.commands);
+
+                        println!("    Command statistics:");
+                        println!("      Move commands: {}", move_count);
+                        println!("      Draw commands: {}", draw_count);
+                        println!("      Arc commands: {}", arc_count);
+                        println!("      Other commands: {}", other_count);
                     }
                 }
             }
         }
+
+        if !drill_files.is_empty() {
+            println!("Found {} drill files:", drill_files.len());
+            for file in drill_files {
+                println!("  {}", file.file_name().unwrap().to_string_lossy());
+                print_drill_info(&file, strict, detailed, "    ");
+            }
+        }
+    }
+}
+
+/// Prints the board-level information of a job file
+fn print_job_summary(job: &JobFile, indent: &str) {
+    if let Some(project) = &job.project {
+        println!("{}Project: {}", indent, project);
+    }
+    if let Some(generator) = &job.generator {
+        println!("{}Generated by: {}", indent, generator);
+    }
+    if let Some((x, y)) = job.size {
+        println!("{}Board size: {} x {} mm", indent, x, y);
+    }
+    if let Some(layers) = job.layer_count {
+        println!("{}Copper layers: {}", indent, layers);
+    }
+    if let Some(thickness) = job.board_thickness {
+        println!("{}Board thickness: {}mm", indent, thickness);
+    }
+    if let Some(finish) = &job.finish {
+        println!("{}Finish: {}", indent, finish);
+    }
+    println!("{}Files listed: {}", indent, job.files.len());
+
+    if !job.stackup.is_empty() {
+        let stackup = Stackup::from_entries(
+            &job.stackup,
+            job.board_thickness.unwrap_or(DEFAULT_THICKNESS),
+        );
+        println!("{}Stackup:", indent);
+        for layer in &stackup.layers {
+            println!(
+                "{}  {:<24} {:>7.4}mm  Z {:.4} .. {:.4}",
+                indent, layer.name, layer.thickness, layer.z_bottom, layer.z_top
+            );
+        }
+    }
+}
+
+/// Parses a drill file and prints its diagnostics, and in detail its tools
+/// and hole counts
+fn print_drill_info(path: &Path, strict: bool, detailed: bool, indent: &str) {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("{}Error reading file: {}", indent, e);
+            return;
+        }
+    };
+
+    match parse_excellon(&content, plating_from_file_name(path), strict, ARC_TOLERANCE) {
+        Ok(drill) => {
+            if detailed {
+                println!(
+                    "{}Valid drill file with {} hole(s) ({} plated, {} slot(s))",
+                    indent,
+                    drill.holes.len(),
+                    drill.plated_count(),
+                    drill.holes.iter().filter(|h| h.is_slot()).count()
+                );
+            }
+            print_diagnostics(&drill.diagnostics, indent);
+            if !detailed {
+                return;
+            }
+
+            let mut tools: Vec<_> = drill.tools.iter().collect();
+            tools.sort_by_key(|(number, _)| **number);
+            for (number, diameter) in tools {
+                let hits = drill.holes.iter().filter(|h| h.tool == *number).count();
+                println!("{}  T{}: {:.3} mm, {} hit(s)", indent, number, diameter, hits);
+            }
+        }
+        Err(e) => println!("{}Not a valid drill file: {}", indent, e),
+    }
+}
+
+/// Formats an identified layer, e.g. `Copper (Bottom, L2) from .FileFunction`
+fn describe_layer(info: &LayerInfo) -> String {
+    let mut details = Vec::new();
+    if let Some(side) = info.side {
+        details.push(format!("{:?}", side));
+    }
+    if let Some(index) = info.copper_index {
+        details.push(format!("L{}", index));
+    }
+    let source = match info.source {
+        LayerSource::JobFile => "job file",
+        LayerSource::FileFunction => ".FileFunction",
+        LayerSource::FileName => "file name",
+    };
+
+    if details.is_empty() {
+        format!("{:?} from {}", info.layer_type, source)
+    } else {
+        format!("{:?} ({}) from {}", info.layer_type, details.join(", "), source)
+    }
+}
+
+/// Prints parse diagnostics, one per line, under an indented heading
+fn print_diagnostics(diagnostics: &[Diagnostic], indent: &str) {
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    println!("{}Skipped {} block(s):", indent, diagnostics.len());
+    for diagnostic in diagnostics {
+        println!("{}  {}", indent, diagnostic);
+    }
+}
+
+/// Prints the contours of an Edge Cuts layer that are not closed and are
+/// left out of the board outline
+fn print_open_contours(layer: Option<&LayerInfo>, commands: &[Command], indent: &str) {
+    if layer.is_none_or(|info| info.layer_type != LayerType::EdgeCuts) {
+        return;
+    }
+
+    match board_outline(commands, ARC_TOLERANCE) {
+        Ok((_, diagnostics)) if !diagnostics.is_empty() => {
+            println!("{}Left out {} open contour(s):", indent, diagnostics.len());
+            for diagnostic in diagnostics {
+                println!("{}  {}", indent, diagnostic);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => println!("{}{}", indent, e),
+    }
+}
+
+/// Prints Gerber X2 file attributes sorted by name
+fn print_file_attributes(attributes: &Attributes, indent: &str) {
+    if attributes.is_empty() {
+        return;
+    }
+
+    let mut names: Vec<&String> = attributes.keys().collect();
+    names.sort();
+
+    println!("{}File attributes:", indent);
+    for name in names {
+        println!("{}  {}: {}", indent, name, attributes[name].join(","));
     }
 }
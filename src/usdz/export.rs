@@ -2,23 +2,307 @@
 //!
 //! Provides functions to export the PCB model to various formats.
 
-use crate::intermediate::model::PCBModel;
+use crate::intermediate::model::{Face, Mesh, PCBModel, Point3D};
 /// Exports a PCB model to USDZ format.
 ///
-/// This is a placeholder for future implementation.
+/// The model becomes a USD stage (see [`usda_stage`]) packaged as a USDZ
+/// archive: an uncompressed zip with 64-byte aligned entries, as AR Quick
+/// Look and the USD tools expect.
 ///
 /// # Arguments
 ///
-/// * `_model` - The PCB model to export
-/// * `_output_path` - Path where the USDZ file will be written
+/// * `model` - The PCB model to export
+/// * `output_path` - Path where the USDZ file will be written
 ///
 /// # Returns
 ///
 /// * `Result<(), String>` - Success or error message
-#[allow(dead_code)]
-pub fn export_to_usdz(_model: &PCBModel, _output_path: &str) -> Result<(), String> {
-    // This is a placeholder that will be implemented later
-    Err("USDZ export not yet implemented".to_string())
+pub fn export_to_usdz(model: &PCBModel, output_path: &str) -> Result<(), String> {
+    use std::path::Path;
+
+    let stem = Path::new(output_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "pcb".to_string());
+    let layer_name = format!("{}.usda", stem);
+    let stage = usda_stage(model);
+    let archive = super::zip::stored_zip(&[(&layer_name, stage.as_bytes())])?;
+
+    std::fs::write(output_path, archive).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Builds a USD stage in the text (`.usda`) format from a PCB model.
+///
+/// Each layer is a `Mesh` prim named after it (see [`layer_name`]) with
+/// its vertex normals and display color, bound to a `UsdPreviewSurface`
+/// material from [`layer_material`]. Units are millimeters; the board,
+/// modeled with Z up, is turned to the Y-up axis of the stage.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to convert
+///
+/// # Returns
+///
+/// * `String` - The stage as USDA text
+pub fn usda_stage(model: &PCBModel) -> String {
+    let mut usda = String::new();
+    write_stage(&mut usda, model).expect("writing to a String cannot fail");
+    usda
+}
+
+/// Writes the USD stage of a PCB model, see [`usda_stage`].
+fn write_stage(usda: &mut String, model: &PCBModel) -> std::fmt::Result {
+    use std::fmt::Write;
+
+    let layers = named_layers(model);
+    writeln!(usda, "#usda 1.0")?;
+    writeln!(usda, "(")?;
+    writeln!(usda, "    defaultPrim = \"Board\"")?;
+    writeln!(usda, "    doc = \"PCB Model exported from Gerber\"")?;
+    writeln!(usda, "    metersPerUnit = 0.001")?;
+    writeln!(usda, "    upAxis = \"Y\"")?;
+    writeln!(usda, ")")?;
+    writeln!(usda)?;
+    writeln!(usda, "def Xform \"Board\" (")?;
+    writeln!(usda, "    kind = \"component\"")?;
+    writeln!(usda, ")")?;
+    writeln!(usda, "{{")?;
+    writeln!(usda, "    float xformOp:rotateX = -90")?;
+    writeln!(
+        usda,
+        "    uniform token[] xformOpOrder = [\"xformOp:rotateX\"]"
+    )?;
+
+    // One material per layer kind, shared by the meshes using it
+    writeln!(usda)?;
+    writeln!(usda, "    def Scope \"Materials\"")?;
+    writeln!(usda, "    {{")?;
+    let mut materials: Vec<&'static str> = Vec::new();
+    for (_, mesh) in &layers {
+        let material = layer_material(layer_name(mesh));
+        if materials.contains(&material.name) {
+            continue;
+        }
+        if !materials.is_empty() {
+            writeln!(usda)?;
+        }
+        materials.push(material.name);
+        let [r, g, b] = material.color;
+        writeln!(usda, "        def Material \"{}\"", material.name)?;
+        writeln!(usda, "        {{")?;
+        writeln!(
+            usda,
+            "            token outputs:surface.connect = </Board/Materials/{}/Surface.outputs:surface>",
+            material.name
+        )?;
+        writeln!(usda)?;
+        writeln!(usda, "            def Shader \"Surface\"")?;
+        writeln!(usda, "            {{")?;
+        writeln!(
+            usda,
+            "                uniform token info:id = \"UsdPreviewSurface\""
+        )?;
+        writeln!(
+            usda,
+            "                color3f inputs:diffuseColor = ({}, {}, {})",
+            r, g, b
+        )?;
+        writeln!(
+            usda,
+            "                float inputs:metallic = {}",
+            material.metallic
+        )?;
+        writeln!(
+            usda,
+            "                float inputs:roughness = {}",
+            material.roughness
+        )?;
+        writeln!(
+            usda,
+            "                float inputs:opacity = {}",
+            material.opacity
+        )?;
+        writeln!(usda, "                token outputs:surface")?;
+        writeln!(usda, "            }}")?;
+        writeln!(usda, "        }}")?;
+    }
+    writeln!(usda, "    }}")?;
+
+    for (name, mesh) in &layers {
+        let material = layer_material(layer_name(mesh));
+        let triangles: Vec<[usize; 3]> = mesh
+            .faces
+            .iter()
+            .flat_map(|face| face_triangles(mesh, face))
+            .collect();
+        let join = |items: Vec<String>| items.join(", ");
+        let vector = |p: &Point3D| format!("({}, {}, {})", p.x as f32, p.y as f32, p.z as f32);
+        let (low, high) = bounds(mesh);
+
+        writeln!(usda)?;
+        writeln!(usda, "    def Mesh \"{}\" (", name)?;
+        writeln!(
+            usda,
+            "        prepend apiSchemas = [\"MaterialBindingAPI\"]"
+        )?;
+        writeln!(usda, "    )")?;
+        writeln!(usda, "    {{")?;
+        writeln!(
+            usda,
+            "        float3[] extent = [{}, {}]",
+            vector(&low),
+            vector(&high)
+        )?;
+        writeln!(
+            usda,
+            "        int[] faceVertexCounts = [{}]",
+            join(vec!["3".to_string(); triangles.len()])
+        )?;
+        writeln!(
+            usda,
+            "        int[] faceVertexIndices = [{}]",
+            join(triangles.iter().flatten().map(|i| i.to_string()).collect())
+        )?;
+        writeln!(
+            usda,
+            "        rel material:binding = </Board/Materials/{}>",
+            material.name
+        )?;
+        writeln!(
+            usda,
+            "        normal3f[] normals = [{}] (",
+            join(mesh.vertices.iter().map(|v| vector(&v.normal)).collect())
+        )?;
+        writeln!(usda, "            interpolation = \"vertex\"")?;
+        writeln!(usda, "        )")?;
+        writeln!(
+            usda,
+            "        point3f[] points = [{}]",
+            join(mesh.vertices.iter().map(|v| vector(&v.position)).collect())
+        )?;
+        let [r, g, b] = material.color;
+        writeln!(
+            usda,
+            "        color3f[] primvars:displayColor = [({}, {}, {})] (",
+            r, g, b
+        )?;
+        writeln!(usda, "            interpolation = \"constant\"")?;
+        writeln!(usda, "        )")?;
+        writeln!(usda, "        uniform token subdivisionScheme = \"none\"")?;
+        writeln!(usda, "    }}")?;
+    }
+    writeln!(usda, "}}")
+}
+
+/// Appearance of a layer, in the metallic-roughness model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerMaterial {
+    /// Material name
+    pub name: &'static str,
+    /// Base color, linear RGB from 0 to 1
+    pub color: [f32; 3],
+    /// Metalness from 0 (dielectric) to 1 (metal)
+    pub metallic: f32,
+    /// Roughness from 0 (mirror) to 1 (matte)
+    pub roughness: f32,
+    /// Opacity from 0 (transparent) to 1 (opaque)
+    pub opacity: f32,
+}
+
+impl LayerMaterial {
+    /// Base color as 8-bit sRGB, as display colors are given.
+    pub fn srgb(&self) -> [u8; 3] {
+        self.color.map(to_srgb)
+    }
+}
+
+/// Material of a layer, by its name (see [`layer_name`]).
+///
+/// Copper is a shiny metal, solder mask a glossy, slightly translucent
+/// green, silkscreen a matte white and the board an FR4 substrate.
+pub fn layer_material(layer_name: &str) -> LayerMaterial {
+    let (name, color, metallic, roughness, opacity) = match layer_name {
+        "TopCopper" | "BottomCopper" | "InnerCopper" | "PlatedBarrels" => {
+            ("Copper", [0.72, 0.45, 0.20], 1.0, 0.3, 1.0)
+        }
+        "TopSoldermask" | "BottomSoldermask" => ("Soldermask", [0.02, 0.30, 0.08], 0.0, 0.25, 0.85),
+        "TopSilkscreen" | "BottomSilkscreen" => ("Silkscreen", [0.95, 0.95, 0.95], 0.0, 0.9, 1.0),
+        "Stencil" => ("StainlessSteel", [0.75, 0.75, 0.77], 1.0, 0.35, 1.0),
+        _ => ("FR4", [0.62, 0.58, 0.38], 0.0, 0.6, 1.0),
+    };
+    LayerMaterial {
+        name,
+        color,
+        metallic,
+        roughness,
+        opacity,
+    }
+}
+
+/// Converts a linear color component to an 8-bit sRGB value.
+fn to_srgb(linear: f32) -> u8 {
+    let c = linear.clamp(0.0, 1.0);
+    let srgb = if c <= 0.003_130_8 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+/// The meshes of a model that hold faces, with unique layer names.
+///
+/// Several inner copper layers share a name; the repeats are numbered
+/// (`InnerCopper`, `InnerCopper2`, ...).
+pub fn named_layers(model: &PCBModel) -> Vec<(String, &Mesh)> {
+    let mut layers: Vec<(String, &Mesh)> = Vec::new();
+    for mesh in model.meshes.iter().filter(|mesh| !mesh.faces.is_empty()) {
+        let base = layer_name(mesh);
+        let repeats = layers
+            .iter()
+            .filter(|(_, other)| layer_name(other) == base)
+            .count();
+        let name = match repeats {
+            0 => base.to_string(),
+            n => format!("{}{}", base, n + 1),
+        };
+        layers.push((name, mesh));
+    }
+    layers
+}
+
+/// Lowest and highest corner of the bounding box of a mesh.
+pub(crate) fn bounds(mesh: &Mesh) -> (Point3D, Point3D) {
+    mesh.vertices.iter().fold(
+        (
+            Point3D {
+                x: f64::MAX,
+                y: f64::MAX,
+                z: f64::MAX,
+            },
+            Point3D {
+                x: f64::MIN,
+                y: f64::MIN,
+                z: f64::MIN,
+            },
+        ),
+        |(low, high), v| {
+            let p = &v.position;
+            (
+                Point3D {
+                    x: low.x.min(p.x),
+                    y: low.y.min(p.y),
+                    z: low.z.min(p.z),
+                },
+                Point3D {
+                    x: high.x.max(p.x),
+                    y: high.y.max(p.y),
+                    z: high.z.max(p.z),
+                },
+            )
+        },
+    )
 }
 
 /// Exports a PCB model to OBJ format.
@@ -39,12 +323,20 @@ pub fn export_to_obj(model: &PCBModel, output_path: &str, colors: bool) -> Resul
     use std::fs::File;
     use std::io::{BufWriter, Write};
     use std::path::Path;
-    use crate::intermediate::model::LayerType;
+    use crate::intermediate::model::{LayerType, Side};
     
     let file = File::create(output_path).map_err(|e| format!("Failed to create file: {}", e))?;
     let mut writer = BufWriter::new(file);
     
     writeln!(writer, "# PCB Model exported from Gerber").map_err(|e| format!("Write error: {}", e))?;
+    let metadata = &model.metadata;
+    if let Some(project) = &metadata.project {
+        writeln!(writer, "# Project: {}", project).map_err(|e| format!("Write error: {}", e))?;
+    }
+    if let Some((x, y)) = metadata.size {
+        writeln!(writer, "# Board size: {} x {} mm", x, y).map_err(|e| format!("Write error: {}", e))?;
+    }
+    writeln!(writer, "# Board thickness: {} mm", metadata.thickness).map_err(|e| format!("Write error: {}", e))?;
     
     // If colors mode is enabled, create material info
     if colors {
@@ -88,6 +380,22 @@ pub fn export_to_obj(model: &PCBModel, output_path: &str, colors: bool) -> Resul
         writeln!(mtl_writer, "d 1.0").map_err(|e| format!("Write error: {}", e))?;
         writeln!(mtl_writer, "illum 2").map_err(|e| format!("Write error: {}", e))?;
         
+        // Inner Copper - Orange
+        writeln!(mtl_writer, "newmtl InnerCopper").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "Ka 0.5 0.3 0.0").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "Kd 0.8 0.5 0.1").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "Ks 0.8 0.8 0.8").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "d 1.0").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "illum 2").map_err(|e| format!("Write error: {}", e))?;
+        
+        // Solder Mask - Dark green, slightly translucent
+        writeln!(mtl_writer, "newmtl Soldermask").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "Ka 0.0 0.2 0.05").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "Kd 0.0 0.35 0.1").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "Ks 0.5 0.5 0.5").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "d 0.85").map_err(|e| format!("Write error: {}", e))?;
+        writeln!(mtl_writer, "illum 2").map_err(|e| format!("Write error: {}", e))?;
+        
         // Top Silkscreen - White
         writeln!(mtl_writer, "newmtl TopSilkscreen").map_err(|e| format!("Write error: {}", e))?;
         writeln!(mtl_writer, "Ka 0.9 0.9 0.9").map_err(|e| format!("Write error: {}", e))?;
@@ -114,22 +422,16 @@ pub fn export_to_obj(model: &PCBModel, output_path: &str, colors: bool) -> Resul
         if colors {
             let material = match mesh.layer_type {
                 LayerType::EdgeCuts => "EdgeCuts",
-                LayerType::Copper => {
-                    // Determine if it's top or bottom based on vertices z position
-                    if !mesh.vertices.is_empty() && mesh.vertices[0].position.z > 0.5 {
-                        "TopCopper"
-                    } else {
-                        "BottomCopper"
-                    }
+                LayerType::Copper => match mesh.side {
+                    Some(Side::Top) => "TopCopper",
+                    Some(Side::Inner) => "InnerCopper",
+                    _ => "BottomCopper",
                 },
-                LayerType::Silkscreen => {
-                    // Determine if it's top or bottom based on vertices z position
-                    if !mesh.vertices.is_empty() && mesh.vertices[0].position.z > 0.5 {
-                        "TopSilkscreen"
-                    } else {
-                        "BottomSilkscreen"
-                    }
+                LayerType::Silkscreen => match mesh.side {
+                    Some(Side::Bottom) => "BottomSilkscreen",
+                    _ => "TopSilkscreen",
                 },
+                LayerType::Soldermask => "Soldermask",
                 _ => "EdgeCuts" // Default for other layer types
             };
             
@@ -179,4 +481,232 @@ pub fn export_to_obj(model: &PCBModel, output_path: &str, colors: bool) -> Resul
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Options for STL export.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StlOptions {
+    /// Write ASCII STL instead of binary
+    pub ascii: bool,
+    /// Write one file per layer instead of a single merged solid
+    pub per_layer: bool,
+}
+
+/// Exports a PCB model to STL format.
+///
+/// By default every mesh is written into a single binary solid. With
+/// `per_layer`, each mesh goes to its own file named after the layer (see
+/// [`named_layers`]), next to `output_path`: `board.stl` becomes
+/// `board_EdgeCuts.stl`, `board_TopCopper.stl` and so on. Polygon faces
+/// are split into triangles and each triangle gets its facet normal.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to export
+/// * `output_path` - Path where the STL file will be written
+/// * `options` - Encoding and file layout
+///
+/// # Returns
+///
+/// * `Result<Vec<String>, String>` - The paths of the files written, or an error message
+pub fn export_to_stl(
+    model: &PCBModel,
+    output_path: &str,
+    options: &StlOptions,
+) -> Result<Vec<String>, String> {
+    use std::path::Path;
+
+    if !options.per_layer {
+        let meshes: Vec<&Mesh> = model.meshes.iter().collect();
+        write_stl(output_path, "pcb", &meshes, options.ascii)?;
+        return Ok(vec![output_path.to_string()]);
+    }
+
+    let path = Path::new(output_path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut written: Vec<String> = Vec::new();
+    for (name, mesh) in named_layers(model) {
+        let layer_path = path.with_file_name(format!("{}_{}.stl", stem, name));
+        let layer_path = layer_path.to_string_lossy().to_string();
+        write_stl(&layer_path, &name, &[mesh], options.ascii)?;
+        written.push(layer_path);
+    }
+
+    Ok(written)
+}
+
+/// Name of the layer a mesh represents, from its layer type and side.
+///
+/// Copper on no side is the `PlatedBarrels` of the drill holes, which run
+/// through the whole board.
+///
+/// # Arguments
+///
+/// * `mesh` - The layer mesh
+///
+/// # Returns
+///
+/// * `&'static str` - The layer name, e.g. `EdgeCuts` or `TopSilkscreen`
+pub fn layer_name(mesh: &Mesh) -> &'static str {
+    use crate::intermediate::model::{LayerType, Side};
+
+    match (&mesh.layer_type, mesh.side) {
+        (LayerType::EdgeCuts, _) => "EdgeCuts",
+        (LayerType::Copper, None) => "PlatedBarrels",
+        (LayerType::Copper, Some(Side::Top)) => "TopCopper",
+        (LayerType::Copper, Some(Side::Bottom)) => "BottomCopper",
+        (LayerType::Copper, Some(Side::Inner)) => "InnerCopper",
+        (LayerType::Soldermask, Some(Side::Bottom)) => "BottomSoldermask",
+        (LayerType::Soldermask, _) => "TopSoldermask",
+        (LayerType::Silkscreen, Some(Side::Bottom)) => "BottomSilkscreen",
+        (LayerType::Silkscreen, _) => "TopSilkscreen",
+        (LayerType::Paste, _) => "Stencil",
+        (LayerType::Drill, _) => "Drill",
+    }
+}
+
+/// Writes meshes into one STL solid.
+fn write_stl(output_path: &str, name: &str, meshes: &[&Mesh], ascii: bool) -> Result<(), String> {
+    use std::fs::File;
+    use std::io::{BufWriter, Write};
+
+    let triangles: Vec<[&Point3D; 3]> = meshes
+        .iter()
+        .flat_map(|mesh| {
+            mesh.faces.iter().flat_map(move |face| {
+                face_triangles(mesh, face)
+                    .into_iter()
+                    .map(move |[a, b, c]| {
+                        [
+                            &mesh.vertices[a].position,
+                            &mesh.vertices[b].position,
+                            &mesh.vertices[c].position,
+                        ]
+                    })
+            })
+        })
+        .collect();
+
+    let file = File::create(output_path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut writer = BufWriter::new(file);
+    let write_error = |e: std::io::Error| format!("Write error: {}", e);
+
+    if ascii {
+        writeln!(writer, "solid {}", name).map_err(write_error)?;
+        for [a, b, c] in triangles {
+            let normal = triangle_normal(a, b, c);
+            writeln!(
+                writer,
+                "  facet normal {:e} {:e} {:e}",
+                normal.x, normal.y, normal.z
+            )
+            .map_err(write_error)?;
+            writeln!(writer, "    outer loop").map_err(write_error)?;
+            for point in [a, b, c] {
+                writeln!(
+                    writer,
+                    "      vertex {:e} {:e} {:e}",
+                    point.x, point.y, point.z
+                )
+                .map_err(write_error)?;
+            }
+            writeln!(writer, "    endloop").map_err(write_error)?;
+            writeln!(writer, "  endfacet").map_err(write_error)?;
+        }
+        writeln!(writer, "endsolid {}", name).map_err(write_error)?;
+        return writer.flush().map_err(write_error);
+    }
+
+    // 80 byte header, then the triangle count. The header must not start
+    // with "solid", or readers take the file for ASCII
+    let mut header = [0u8; 80];
+    let title = b"PCB Model exported from Gerber";
+    header[..title.len()].copy_from_slice(title);
+    writer.write_all(&header).map_err(write_error)?;
+    writer
+        .write_all(&(triangles.len() as u32).to_le_bytes())
+        .map_err(write_error)?;
+
+    for [a, b, c] in triangles {
+        let normal = triangle_normal(a, b, c);
+        for point in [&normal, a, b, c] {
+            for value in [point.x, point.y, point.z] {
+                writer
+                    .write_all(&(value as f32).to_le_bytes())
+                    .map_err(write_error)?;
+            }
+        }
+        // Attribute byte count, unused
+        writer.write_all(&[0, 0]).map_err(write_error)?;
+    }
+
+    writer.flush().map_err(write_error)
+}
+
+/// Splits a polygon face into triangles with the same winding.
+///
+/// The face is projected onto the coordinate plane it is most parallel to
+/// and ear-clipped there, so concave faces and caps with bridged holes come
+/// out right.
+pub(crate) fn face_triangles(mesh: &Mesh, face: &Face) -> Vec<[usize; 3]> {
+    use crate::geometry::triangulate::triangulate;
+    use crate::gerber::types::Point;
+
+    let indices = &face.vertices;
+    if indices.len() == 3 {
+        return vec![[indices[0], indices[1], indices[2]]];
+    }
+
+    // Newell's method gives the face normal even for concave polygons
+    let n = indices.len();
+    let (mut nx, mut ny, mut nz) = (0.0, 0.0, 0.0);
+    for i in 0..n {
+        let a = &mesh.vertices[indices[i]].position;
+        let b = &mesh.vertices[indices[(i + 1) % n]].position;
+        nx += (a.y - b.y) * (a.z + b.z);
+        ny += (a.z - b.z) * (a.x + b.x);
+        nz += (a.x - b.x) * (a.y + b.y);
+    }
+
+    let projected: Vec<Point> = indices
+        .iter()
+        .map(|&i| {
+            let p = &mesh.vertices[i].position;
+            if nz.abs() >= nx.abs() && nz.abs() >= ny.abs() {
+                Point { x: p.x, y: p.y }
+            } else if nx.abs() >= ny.abs() {
+                Point { x: p.y, y: p.z }
+            } else {
+                Point { x: p.z, y: p.x }
+            }
+        })
+        .collect();
+
+    triangulate(&projected)
+        .into_iter()
+        .map(|[a, b, c]| [indices[a], indices[b], indices[c]])
+        .collect()
+}
+
+/// Unit normal of a triangle with counter-clockwise winding.
+fn triangle_normal(a: &Point3D, b: &Point3D, c: &Point3D) -> Point3D {
+    let (ux, uy, uz) = (b.x - a.x, b.y - a.y, b.z - a.z);
+    let (vx, vy, vz) = (c.x - a.x, c.y - a.y, c.z - a.z);
+    let (x, y, z) = (uy * vz - uz * vy, uz * vx - ux * vz, ux * vy - uy * vx);
+    let length = (x * x + y * y + z * z).sqrt();
+    if length == 0.0 {
+        return Point3D {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+    }
+    Point3D {
+        x: x / length,
+        y: y / length,
+        z: z / length,
+    }
+}
@@ -0,0 +1,284 @@
+//! glTF 2.0 export.
+//!
+//! Each layer becomes a named node with its own mesh and a
+//! metallic-roughness material from [`layer_material`], so that copper
+//! renders as metal. The model is written either as a `.gltf` JSON file
+//! with its geometry in a `.bin` file next to it, or as a single binary
+//! `.glb` file.
+//!
+//! The board is modeled in millimeters with Z up; glTF works in meters
+//! with Y up, so a root node scales and turns the layers.
+
+use crate::intermediate::model::PCBModel;
+use crate::usdz::export::{bounds, face_triangles, layer_material, layer_name, named_layers};
+
+/// Buffer view target for vertex attributes.
+const ARRAY_BUFFER: u32 = 34962;
+
+/// Buffer view target for vertex indices.
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Accessor component type of 32-bit floats.
+const FLOAT: u32 = 5126;
+
+/// Accessor component type of 32-bit unsigned integers.
+const UNSIGNED_INT: u32 = 5125;
+
+/// Exports a PCB model to glTF 2.0, as a `.gltf` file and a `.bin` file.
+///
+/// The `.bin` file takes the name of the `.gltf` file with its extension
+/// replaced, and is referenced from it by a relative URI.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to export
+/// * `output_path` - Path where the `.gltf` file will be written
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Success or error message
+pub fn export_to_gltf(model: &PCBModel, output_path: &str) -> Result<(), String> {
+    use std::path::Path;
+
+    let bin_path = Path::new(output_path).with_extension("bin");
+    let bin_name = bin_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (json, buffer) = gltf_document(model, Some(&bin_name));
+    std::fs::write(&bin_path, buffer).map_err(|e| format!("Failed to write file: {}", e))?;
+    std::fs::write(output_path, json).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Exports a PCB model to a single binary glTF 2.0 (`.glb`) file.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to export
+/// * `output_path` - Path where the `.glb` file will be written
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Success or error message
+pub fn export_to_glb(model: &PCBModel, output_path: &str) -> Result<(), String> {
+    let (json, mut buffer) = gltf_document(model, None);
+
+    // Chunks are 4-byte aligned: JSON padded with spaces, binary with zeros
+    let mut json = json.into_bytes();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    while !buffer.len().is_multiple_of(4) {
+        buffer.push(0);
+    }
+    let length = 12 + 8 + json.len() + 8 + buffer.len();
+    let length = u32::try_from(length).map_err(|_| "The model is too large for GLB".to_string())?;
+
+    let mut glb = Vec::with_capacity(length as usize);
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&length.to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&buffer);
+
+    std::fs::write(output_path, glb).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Builds the glTF JSON document of a model and its binary buffer.
+///
+/// Every layer holds three buffer views: positions and normals as 32-bit
+/// floats and triangle indices as 32-bit integers.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to convert
+/// * `buffer_uri` - URI of the external buffer file, or `None` for the
+///   embedded buffer of a GLB file
+///
+/// # Returns
+///
+/// * `(String, Vec<u8>)` - The JSON document and the buffer
+pub fn gltf_document(model: &PCBModel, buffer_uri: Option<&str>) -> (String, Vec<u8>) {
+    let layers = named_layers(model);
+
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut buffer_views: Vec<String> = Vec::new();
+    let mut accessors: Vec<String> = Vec::new();
+    let mut meshes: Vec<String> = Vec::new();
+    let mut nodes: Vec<String> = Vec::new();
+    let mut materials: Vec<(&'static str, String)> = Vec::new();
+
+    for (name, mesh) in &layers {
+        let material = layer_material(layer_name(mesh));
+        let material_index = match materials.iter().position(|(m, _)| *m == material.name) {
+            Some(index) => index,
+            None => {
+                let [r, g, b] = material.color;
+                let blend = if material.opacity < 1.0 {
+                    r#","alphaMode":"BLEND""#
+                } else {
+                    ""
+                };
+                materials.push((
+                    material.name,
+                    format!(
+                        r#"{{"name":"{}","pbrMetallicRoughness":{{"baseColorFactor":[{},{},{},{}],"metallicFactor":{},"roughnessFactor":{}}}{}}}"#,
+                        material.name,
+                        r,
+                        g,
+                        b,
+                        material.opacity,
+                        material.metallic,
+                        material.roughness,
+                        blend
+                    ),
+                ));
+                materials.len() - 1
+            }
+        };
+
+        let triangles: Vec<[usize; 3]> = mesh
+            .faces
+            .iter()
+            .flat_map(|face| face_triangles(mesh, face))
+            .collect();
+        let (low, high) = bounds(mesh);
+        let count = mesh.vertices.len();
+
+        // Positions, normals and indices, one buffer view each
+        let mut view = |bytes: Vec<u8>, target: u32| {
+            buffer_views.push(format!(
+                r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+                buffer.len(),
+                bytes.len(),
+                target
+            ));
+            buffer.extend_from_slice(&bytes);
+            buffer_views.len() - 1
+        };
+        let positions = view(
+            mesh.vertices
+                .iter()
+                .flat_map(|v| [v.position.x, v.position.y, v.position.z])
+                .flat_map(|value| (value as f32).to_le_bytes())
+                .collect(),
+            ARRAY_BUFFER,
+        );
+        let normals = view(
+            mesh.vertices
+                .iter()
+                .flat_map(|v| [v.normal.x, v.normal.y, v.normal.z])
+                .flat_map(|value| (value as f32).to_le_bytes())
+                .collect(),
+            ARRAY_BUFFER,
+        );
+        let indices = view(
+            triangles
+                .iter()
+                .flatten()
+                .flat_map(|&i| (i as u32).to_le_bytes())
+                .collect(),
+            ELEMENT_ARRAY_BUFFER,
+        );
+
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            positions,
+            FLOAT,
+            count,
+            low.x as f32,
+            low.y as f32,
+            low.z as f32,
+            high.x as f32,
+            high.y as f32,
+            high.z as f32
+        ));
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"VEC3"}}"#,
+            normals, FLOAT, count
+        ));
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":{},"count":{},"type":"SCALAR"}}"#,
+            indices,
+            UNSIGNED_INT,
+            triangles.len() * 3
+        ));
+        let first = accessors.len() - 3;
+
+        meshes.push(format!(
+            r#"{{"name":"{}","primitives":[{{"attributes":{{"POSITION":{},"NORMAL":{}}},"indices":{},"material":{}}}]}}"#,
+            name,
+            first,
+            first + 1,
+            first + 2,
+            material_index
+        ));
+        nodes.push(format!(
+            r#"{{"name":"{}","mesh":{}}}"#,
+            name,
+            meshes.len() - 1
+        ));
+    }
+
+    // The root node turns millimeters into meters and Z up into Y up; glTF
+    // does not allow an empty list of children
+    let children = match nodes.len() {
+        0 => String::new(),
+        n => {
+            let children: Vec<String> = (1..=n).map(|i| i.to_string()).collect();
+            format!(r#","children":[{}]"#, children.join(","))
+        }
+    };
+    let root = format!(
+        r#"{{"name":"Board","rotation":[{},0,0,{}],"scale":[0.001,0.001,0.001]{}}}"#,
+        -std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+        children
+    );
+    let buffer_json = match buffer_uri {
+        Some(uri) => format!(
+            r#"{{"byteLength":{},"uri":"{}"}}"#,
+            buffer.len(),
+            escape_uri(uri)
+        ),
+        None => format!(r#"{{"byteLength":{}}}"#, buffer.len()),
+    };
+
+    let mut json = String::new();
+    json.push_str(r#"{"asset":{"version":"2.0","generator":"pcbgen"},"#);
+    json.push_str(r#""scene":0,"scenes":[{"name":"PCB","nodes":[0]}],"#);
+    json.push_str(&format!(
+        r#""nodes":[{}],"#,
+        [vec![root], nodes].concat().join(",")
+    ));
+    if !meshes.is_empty() {
+        let materials: Vec<String> = materials.into_iter().map(|(_, json)| json).collect();
+        json.push_str(&format!(r#""meshes":[{}],"#, meshes.join(",")));
+        json.push_str(&format!(r#""materials":[{}],"#, materials.join(",")));
+        json.push_str(&format!(r#""accessors":[{}],"#, accessors.join(",")));
+        json.push_str(&format!(r#""bufferViews":[{}],"#, buffer_views.join(",")));
+        json.push_str(&format!(r#""buffers":[{}],"#, buffer_json));
+    }
+    json.pop();
+    json.push('}');
+
+    (json, buffer)
+}
+
+/// Percent-encodes the characters of a file name that are not allowed in a
+/// URI, or that JSON would need escaped.
+fn escape_uri(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
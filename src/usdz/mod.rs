@@ -1,6 +1,18 @@
 //! USDZ export module.
 //!
 //! This module provides functionality to export the 3D PCB model
-//! to USDZ format (for AR/VR visualization) and OBJ format (for debugging).
+//! to USDZ format (for AR/VR visualization), glTF (for web viewers), OBJ
+//! format (for debugging), STL format (for 3D printing), 3MF (for
+//! multi-material 3D printing) and STEP (for mechanical CAD).
+//!
+//! - `export.rs`: The exporters and the layer names and materials they share
+//! - `gltf.rs`: glTF 2.0 and GLB export with metallic-roughness materials
+//! - `step.rs`: STEP AP214 export of the board solid with exact holes and arcs
+//! - `threemf.rs`: 3MF export with one colored object per layer
+//! - `zip.rs`: Uncompressed, 64-byte aligned zip archives for USDZ packages
 
-pub mod export;
\ No newline at end of file
+pub mod export;
+pub mod gltf;
+pub mod step;
+pub mod threemf;
+pub mod zip;
\ No newline at end of file
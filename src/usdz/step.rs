@@ -0,0 +1,506 @@
+//! STEP (ISO 10303-21, AP214) export of the board solid.
+//!
+//! Mechanical CAD tools exchange solids as boundary representations: faces
+//! on exact surfaces, bounded by edges on exact curves. Every [`Prism`] of a
+//! [`SolidModel`] becomes a closed solid with planar top and bottom faces, a
+//! planar side face for each straight edge of its profile and a cylindrical
+//! one for each arc, so that holes and rounded corners stay round. The
+//! solids make up the shape of a single part, colored by layer.
+
+use crate::intermediate::profile::{Prism, ProfileEdge, SolidModel};
+use crate::usdz::export::layer_material;
+
+/// Schema of the AP214 automotive design protocol.
+const SCHEMA: &str = "AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }";
+
+/// Distance in mm below which CAD tools take points as coincident.
+const UNCERTAINTY: f64 = 1e-5;
+
+/// Exports the board solids to a STEP AP214 file.
+///
+/// # Arguments
+///
+/// * `model` - The board solids, see [`crate::process_board_solids`]
+/// * `output_path` - Path where the STEP file will be written
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Success or error message
+pub fn export_to_step(model: &SolidModel, output_path: &str) -> Result<(), String> {
+    let file_name = std::path::Path::new(output_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    std::fs::write(output_path, step_document(model, &file_name))
+        .map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Builds the STEP file of the board solids.
+///
+/// The file holds one product, named after the project, whose shape is an
+/// advanced B-rep with a manifold solid per prism, in millimeters.
+///
+/// # Arguments
+///
+/// * `model` - The board solids
+/// * `file_name` - Name of the file, recorded in its header
+///
+/// # Returns
+///
+/// * `String` - The STEP file content
+pub fn step_document(model: &SolidModel, file_name: &str) -> String {
+    let project = model.metadata.project.as_deref().unwrap_or("PCB");
+    let mut step = Step::default();
+
+    // Product structure
+    let application = step.add(
+        "APPLICATION_CONTEXT('core data for automotive mechanical design processes')".to_string(),
+    );
+    step.add(format!(
+        "APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{})",
+        application
+    ));
+    let product_context = step.add(format!("PRODUCT_CONTEXT('',#{},'mechanical')", application));
+    let definition_context = step.add(format!(
+        "PRODUCT_DEFINITION_CONTEXT('part definition',#{},'design')",
+        application
+    ));
+    let name = step_string(project);
+    let product = step.add(format!(
+        "PRODUCT('{}','{}','',(#{}))",
+        name, name, product_context
+    ));
+    step.add(format!(
+        "PRODUCT_RELATED_PRODUCT_CATEGORY('part',$,(#{}))",
+        product
+    ));
+    let formation = step.add(format!("PRODUCT_DEFINITION_FORMATION('','',#{})", product));
+    let definition = step.add(format!(
+        "PRODUCT_DEFINITION('design','',#{},#{})",
+        formation, definition_context
+    ));
+    let shape = step.add(format!("PRODUCT_DEFINITION_SHAPE('','',#{})", definition));
+
+    // Millimeters and radians
+    let length = step.add("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))".to_string());
+    let angle = step.add("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))".to_string());
+    let solid_angle =
+        step.add("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())".to_string());
+    let uncertainty = step.add(format!(
+        "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE({}),#{},'distance_accuracy_value','confusion accuracy')",
+        real(UNCERTAINTY),
+        length
+    ));
+    let context = step.add(format!(
+        "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('Context #1','3D Context with UNIT and UNCERTAINTY'))",
+        uncertainty, length, angle, solid_angle
+    ));
+
+    // The solids, each with the color of its layer
+    let origin = step.placement([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]);
+    let mut items = vec![origin];
+    let mut styles: Vec<(&'static str, usize)> = Vec::new();
+    let mut styled = Vec::new();
+    for prism in &model.prisms {
+        let solid = write_prism(&mut step, prism);
+        items.push(solid);
+
+        let material = layer_material(&prism.name);
+        let style = match styles.iter().find(|(name, _)| *name == material.name) {
+            Some(&(_, style)) => style,
+            None => {
+                let [r, g, b] = material.srgb().map(|c| c as f64 / 255.0);
+                let colour = step.add(format!(
+                    "COLOUR_RGB('{}',{},{},{})",
+                    material.name,
+                    real(r),
+                    real(g),
+                    real(b)
+                ));
+                let fill = step.add(format!("FILL_AREA_STYLE_COLOUR('',#{})", colour));
+                let area = step.add(format!("FILL_AREA_STYLE('',(#{}))", fill));
+                let surface = step.add(format!("SURFACE_STYLE_FILL_AREA(#{})", area));
+                let side = step.add(format!("SURFACE_SIDE_STYLE('',(#{}))", surface));
+                let usage = step.add(format!("SURFACE_STYLE_USAGE(.BOTH.,#{})", side));
+                let style = step.add(format!("PRESENTATION_STYLE_ASSIGNMENT((#{}))", usage));
+                styles.push((material.name, style));
+                style
+            }
+        };
+        styled.push(step.add(format!("STYLED_ITEM('color',(#{}),#{})", style, solid)));
+    }
+
+    let representation = step.add(format!(
+        "ADVANCED_BREP_SHAPE_REPRESENTATION('{}',({}),#{})",
+        name,
+        references(&items),
+        context
+    ));
+    step.add(format!(
+        "SHAPE_DEFINITION_REPRESENTATION(#{},#{})",
+        shape, representation
+    ));
+    step.add(format!(
+        "MECHANICAL_DESIGN_GEOMETRIC_PRESENTATION_REPRESENTATION('',({}),#{})",
+        references(&styled),
+        context
+    ));
+
+    let mut document = String::new();
+    document.push_str("ISO-10303-21;\nHEADER;\n");
+    document.push_str("FILE_DESCRIPTION(('PCB board solid'),'2;1');\n");
+    document.push_str(&format!(
+        "FILE_NAME('{}','{}',(''),(''),'pcbgen {}','pcbgen','');\n",
+        step_string(file_name),
+        timestamp(),
+        env!("CARGO_PKG_VERSION")
+    ));
+    document.push_str(&format!("FILE_SCHEMA(('{}'));\n", SCHEMA));
+    document.push_str("ENDSEC;\nDATA;\n");
+    document.push_str(&step.data);
+    document.push_str("ENDSEC;\nEND-ISO-10303-21;\n");
+    document
+}
+
+/// Writes a prism as a manifold solid and returns its entity.
+///
+/// Every profile edge starts at a vertex on the bottom face and one on the
+/// top face, joined by a vertical edge. The side face of an edge is bounded
+/// by its bottom edge, the vertical edge at its end, its top edge and the
+/// vertical edge at its start; a full circle has no vertical edge, and its
+/// cylinder is bounded by the two circles alone.
+fn write_prism(step: &mut Step, prism: &Prism) -> usize {
+    let (z_bottom, z_top) = (prism.z_bottom, prism.z_top);
+    let mut faces = Vec::new();
+    let (mut top_bounds, mut bottom_bounds) = (Vec::new(), Vec::new());
+
+    for (contour_index, contour) in prism.profile.contours().enumerate() {
+        let n = contour.len();
+        let vertices = |step: &mut Step, z: f64| -> Vec<usize> {
+            contour
+                .iter()
+                .map(|edge| {
+                    let point = step.point([edge.start().x, edge.start().y, z]);
+                    step.add(format!("VERTEX_POINT('',#{})", point))
+                })
+                .collect()
+        };
+        let bottom_vertices = vertices(step, z_bottom);
+        let top_vertices = vertices(step, z_top);
+        let bottom_edges: Vec<usize> = (0..n)
+            .map(|i| {
+                edge_curve(
+                    step,
+                    &contour[i],
+                    z_bottom,
+                    bottom_vertices[i],
+                    bottom_vertices[(i + 1) % n],
+                )
+            })
+            .collect();
+        let top_edges: Vec<usize> = (0..n)
+            .map(|i| {
+                edge_curve(
+                    step,
+                    &contour[i],
+                    z_top,
+                    top_vertices[i],
+                    top_vertices[(i + 1) % n],
+                )
+            })
+            .collect();
+
+        if n == 1 {
+            // A full circle: the cylinder between the bottom and top circles
+            let bottom = step.edge_loop(&[(bottom_edges[0], true)]);
+            let top = step.edge_loop(&[(top_edges[0], false)]);
+            let bounds = [
+                step.add(format!("FACE_BOUND('',#{},.T.)", bottom)),
+                step.add(format!("FACE_BOUND('',#{},.T.)", top)),
+            ];
+            let (surface, same_sense) = side_surface(step, &contour[0], z_bottom);
+            faces.push(step.add(format!(
+                "ADVANCED_FACE('',({}),#{},{})",
+                references(&bounds),
+                surface,
+                logical(same_sense)
+            )));
+        } else {
+            let verticals: Vec<usize> = (0..n)
+                .map(|i| {
+                    let start = contour[i].start();
+                    let line = step.line(
+                        [start.x, start.y, z_bottom],
+                        [0.0, 0.0, 1.0],
+                        z_top - z_bottom,
+                    );
+                    step.add(format!(
+                        "EDGE_CURVE('',#{},#{},#{},.T.)",
+                        bottom_vertices[i], top_vertices[i], line
+                    ))
+                })
+                .collect();
+            for i in 0..n {
+                let side = step.edge_loop(&[
+                    (bottom_edges[i], true),
+                    (verticals[(i + 1) % n], true),
+                    (top_edges[i], false),
+                    (verticals[i], false),
+                ]);
+                let bound = step.add(format!("FACE_OUTER_BOUND('',#{},.T.)", side));
+                let (surface, same_sense) = side_surface(step, &contour[i], z_bottom);
+                faces.push(step.add(format!(
+                    "ADVANCED_FACE('',(#{}),#{},{})",
+                    bound,
+                    surface,
+                    logical(same_sense)
+                )));
+            }
+        }
+
+        // Seen from above the top face runs along the profile, and the
+        // bottom face against it
+        let bound = if contour_index == 0 {
+            "FACE_OUTER_BOUND"
+        } else {
+            "FACE_BOUND"
+        };
+        let top: Vec<(usize, bool)> = top_edges.iter().map(|&edge| (edge, true)).collect();
+        let top = step.edge_loop(&top);
+        top_bounds.push(step.add(format!("{}('',#{},.T.)", bound, top)));
+        let bottom: Vec<(usize, bool)> = bottom_edges
+            .iter()
+            .rev()
+            .map(|&edge| (edge, false))
+            .collect();
+        let bottom = step.edge_loop(&bottom);
+        bottom_bounds.push(step.add(format!("{}('',#{},.T.)", bound, bottom)));
+    }
+
+    for (bounds, z, normal) in [(&top_bounds, z_top, 1.0), (&bottom_bounds, z_bottom, -1.0)] {
+        let placement = step.placement([0.0, 0.0, z], [0.0, 0.0, normal], [1.0, 0.0, 0.0]);
+        let plane = step.add(format!("PLANE('',#{})", placement));
+        faces.push(step.add(format!(
+            "ADVANCED_FACE('',({}),#{},.T.)",
+            references(bounds),
+            plane
+        )));
+    }
+
+    let shell = step.add(format!("CLOSED_SHELL('',({}))", references(&faces)));
+    step.add(format!(
+        "MANIFOLD_SOLID_BREP('{}',#{})",
+        step_string(&prism.name),
+        shell
+    ))
+}
+
+/// Writes a profile edge at height `z` between two vertices.
+///
+/// Arcs lie on a circle whose axis points up when they run
+/// counter-clockwise and down otherwise, so that every edge follows its
+/// curve.
+fn edge_curve(step: &mut Step, edge: &ProfileEdge, z: f64, start: usize, end: usize) -> usize {
+    let curve = match edge {
+        ProfileEdge::Line { start, end } => {
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let length = dx.hypot(dy);
+            step.line(
+                [start.x, start.y, z],
+                [dx / length, dy / length, 0.0],
+                length,
+            )
+        }
+        ProfileEdge::Arc {
+            center,
+            radius,
+            clockwise,
+            ..
+        } => {
+            let axis = if *clockwise { -1.0 } else { 1.0 };
+            let placement =
+                step.placement([center.x, center.y, z], [0.0, 0.0, axis], [1.0, 0.0, 0.0]);
+            step.add(format!("CIRCLE('',#{},{})", placement, real(*radius)))
+        }
+    };
+    step.add(format!("EDGE_CURVE('',#{},#{},#{},.T.)", start, end, curve))
+}
+
+/// Writes the surface of the side face of a profile edge.
+///
+/// The material lies left of the profile edges, so a straight edge's plane
+/// faces right of it. A cylinder faces away from its axis, which is outward
+/// for counter-clockwise arcs and inward for clockwise ones.
+///
+/// # Returns
+///
+/// * `(usize, bool)` - The surface, and whether the face normal follows it
+fn side_surface(step: &mut Step, edge: &ProfileEdge, z: f64) -> (usize, bool) {
+    match edge {
+        ProfileEdge::Line { start, end } => {
+            let (dx, dy) = (end.x - start.x, end.y - start.y);
+            let length = dx.hypot(dy);
+            let placement = step.placement(
+                [start.x, start.y, z],
+                [dy / length, -dx / length, 0.0],
+                [dx / length, dy / length, 0.0],
+            );
+            (step.add(format!("PLANE('',#{})", placement)), true)
+        }
+        ProfileEdge::Arc {
+            center,
+            radius,
+            clockwise,
+            ..
+        } => {
+            let placement =
+                step.placement([center.x, center.y, z], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]);
+            let cylinder = step.add(format!(
+                "CYLINDRICAL_SURFACE('',#{},{})",
+                placement,
+                real(*radius)
+            ));
+            (cylinder, !clockwise)
+        }
+    }
+}
+
+/// The data section of a STEP file, with its entities numbered in order.
+#[derive(Default)]
+struct Step {
+    data: String,
+    count: usize,
+}
+
+impl Step {
+    /// Appends an entity and returns its number.
+    fn add(&mut self, entity: String) -> usize {
+        self.count += 1;
+        self.data
+            .push_str(&format!("#{}={};\n", self.count, entity));
+        self.count
+    }
+
+    fn point(&mut self, [x, y, z]: [f64; 3]) -> usize {
+        self.add(format!(
+            "CARTESIAN_POINT('',({},{},{}))",
+            real(x),
+            real(y),
+            real(z)
+        ))
+    }
+
+    fn direction(&mut self, [x, y, z]: [f64; 3]) -> usize {
+        self.add(format!(
+            "DIRECTION('',({},{},{}))",
+            real(x),
+            real(y),
+            real(z)
+        ))
+    }
+
+    fn placement(&mut self, origin: [f64; 3], axis: [f64; 3], reference: [f64; 3]) -> usize {
+        let origin = self.point(origin);
+        let axis = self.direction(axis);
+        let reference = self.direction(reference);
+        self.add(format!(
+            "AXIS2_PLACEMENT_3D('',#{},#{},#{})",
+            origin, axis, reference
+        ))
+    }
+
+    fn line(&mut self, origin: [f64; 3], direction: [f64; 3], length: f64) -> usize {
+        let origin = self.point(origin);
+        let direction = self.direction(direction);
+        let vector = self.add(format!("VECTOR('',#{},{})", direction, real(length)));
+        self.add(format!("LINE('',#{},#{})", origin, vector))
+    }
+
+    /// Writes a loop of edges, each used along (`true`) or against its curve.
+    fn edge_loop(&mut self, edges: &[(usize, bool)]) -> usize {
+        let oriented: Vec<usize> = edges
+            .iter()
+            .map(|&(edge, along)| {
+                self.add(format!(
+                    "ORIENTED_EDGE('',*,*,#{},{})",
+                    edge,
+                    logical(along)
+                ))
+            })
+            .collect();
+        self.add(format!("EDGE_LOOP('',({}))", references(&oriented)))
+    }
+}
+
+/// A list of entity references, `#1,#2,...`.
+fn references(entities: &[usize]) -> String {
+    entities
+        .iter()
+        .map(|entity| format!("#{}", entity))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A STEP boolean.
+fn logical(value: bool) -> &'static str {
+    if value { ".T." } else { ".F." }
+}
+
+/// A STEP real, which always has a decimal point.
+fn real(value: f64) -> String {
+    // Drop the noise of trigonometry and the sign of negative zero
+    let value = if value.abs() < 1e-12 { 0.0 } else { value };
+    let text = value.to_string();
+    if text.contains('.') { text } else { text + "." }
+}
+
+/// A STEP string: apostrophes and backslashes doubled, and characters
+/// beyond ASCII written as `\X2\` hex sequences.
+fn step_string(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\'' => escaped.push_str("''"),
+            '\\' => escaped.push_str("\\\\"),
+            ' '..='~' => escaped.push(c),
+            _ if (c as u32) <= 0xFFFF => escaped.push_str(&format!("\\X2\\{:04X}\\X0\\", c as u32)),
+            _ => escaped.push_str(&format!("\\X4\\{:08X}\\X0\\", c as u32)),
+        }
+    }
+    escaped
+}
+
+/// The current UTC time in ISO 8601 format, for the file header.
+fn timestamp() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
@@ -0,0 +1,265 @@
+//! 3MF export for multi-material 3D printing.
+//!
+//! A 3MF file is a zip package holding an XML model. Every layer becomes
+//! an object of its own, assigned a base material with the color of the
+//! layer, and all objects are placed in a single build, so that
+//! multi-material printers can print a board replica with green mask,
+//! white silkscreen and metallic-looking pads.
+//!
+//! 3MF requires every object to be a closed, manifold triangle mesh: each
+//! edge is shared by exactly two triangles that run along it in opposite
+//! directions. Objects are checked before they are written.
+
+use crate::intermediate::model::{Mesh, PCBModel};
+use crate::usdz::export::{
+    LayerMaterial, face_triangles, layer_material, layer_name, named_layers,
+};
+
+/// Relationship type of the 3D model part of a package.
+const MODEL_RELATIONSHIP: &str = "http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel";
+
+/// XML namespace of the 3MF core specification.
+const CORE_NAMESPACE: &str = "http://schemas.microsoft.com/3dmanufacturing/core/2015/02";
+
+/// Exports a PCB model to a 3MF package.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to export
+/// * `output_path` - Path where the 3MF file will be written
+///
+/// # Returns
+///
+/// * `Result<(), String>` - Success, or an error message when a layer is not
+///   a manifold mesh or the file cannot be written
+pub fn export_to_3mf(model: &PCBModel, output_path: &str) -> Result<(), String> {
+    let content_types = concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">"#,
+        "\n",
+        r#"  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>"#,
+        "\n",
+        r#"  <Default Extension="model" ContentType="application/vnd.ms-package.3dmanufacturing-3dmodel+xml"/>"#,
+        "\n",
+        "</Types>\n"
+    );
+    let relationships = format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            "\n",
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+            "\n",
+            r#"  <Relationship Target="/3D/3dmodel.model" Id="rel0" Type="{}"/>"#,
+            "\n",
+            "</Relationships>\n"
+        ),
+        MODEL_RELATIONSHIP
+    );
+    let model_xml = model_xml(model)?;
+
+    let archive = super::zip::stored_zip(&[
+        ("[Content_Types].xml", content_types.as_bytes()),
+        ("_rels/.rels", relationships.as_bytes()),
+        ("3D/3dmodel.model", model_xml.as_bytes()),
+    ])?;
+    std::fs::write(output_path, archive).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Builds the 3MF model XML of a PCB model.
+///
+/// The base materials come first, one per layer material (see
+/// [`layer_material`]), then one object per layer named after it (see
+/// [`named_layers`]), and a build placing every object. Vertices are shared
+/// between the triangles of an object by position, whatever their normals.
+///
+/// # Arguments
+///
+/// * `model` - The PCB model to convert
+///
+/// # Returns
+///
+/// * `Result<String, String>` - The model XML, or an error message naming the
+///   first layer that is not a manifold mesh
+pub fn model_xml(model: &PCBModel) -> Result<String, String> {
+    let mut materials: Vec<LayerMaterial> = Vec::new();
+    let mut objects: Vec<Object> = Vec::new();
+
+    for (name, mesh) in named_layers(model) {
+        let (vertices, triangles) = shared_vertices(mesh);
+        check_manifold(&triangles)
+            .map_err(|e| format!("{} is not a manifold mesh: {}", name, e))?;
+
+        let material = layer_material(layer_name(mesh));
+        let material = match materials.iter().position(|m| *m == material) {
+            Some(index) => index,
+            None => {
+                materials.push(material);
+                materials.len() - 1
+            }
+        };
+        objects.push(Object {
+            name,
+            material,
+            vertices,
+            triangles,
+        });
+    }
+
+    let mut xml = String::new();
+    write_model(&mut xml, model, &materials, &objects).expect("writing to a String cannot fail");
+    Ok(xml)
+}
+
+/// A layer as a 3MF object: its base material index and indexed triangles.
+struct Object {
+    name: String,
+    material: usize,
+    vertices: Vec<[f64; 3]>,
+    triangles: Vec<[usize; 3]>,
+}
+
+/// Writes the 3MF model XML, see [`model_xml`].
+fn write_model(
+    xml: &mut String,
+    model: &PCBModel,
+    materials: &[LayerMaterial],
+    objects: &[Object],
+) -> std::fmt::Result {
+    use std::fmt::Write;
+
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        xml,
+        r#"<model unit="millimeter" xml:lang="en-US" xmlns="{}">"#,
+        CORE_NAMESPACE
+    )?;
+    writeln!(xml, r#"  <metadata name="Application">pcbgen</metadata>"#)?;
+    if let Some(project) = &model.metadata.project {
+        writeln!(
+            xml,
+            r#"  <metadata name="Title">{}</metadata>"#,
+            escape_xml(project)
+        )?;
+    }
+    writeln!(xml, "  <resources>")?;
+
+    // The base materials are resource 1, the objects follow from 2
+    writeln!(xml, r#"    <basematerials id="1">"#)?;
+    for material in materials {
+        let [r, g, b] = material.srgb();
+        let alpha = (material.opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+        writeln!(
+            xml,
+            r##"      <base name="{}" displaycolor="#{:02X}{:02X}{:02X}{:02X}"/>"##,
+            material.name, r, g, b, alpha
+        )?;
+    }
+    writeln!(xml, "    </basematerials>")?;
+
+    for (i, object) in objects.iter().enumerate() {
+        writeln!(
+            xml,
+            r#"    <object id="{}" name="{}" type="model" pid="1" pindex="{}">"#,
+            i + 2,
+            object.name,
+            object.material
+        )?;
+        writeln!(xml, "      <mesh>")?;
+        writeln!(xml, "        <vertices>")?;
+        for [x, y, z] in &object.vertices {
+            writeln!(
+                xml,
+                r#"          <vertex x="{}" y="{}" z="{}"/>"#,
+                *x as f32, *y as f32, *z as f32
+            )?;
+        }
+        writeln!(xml, "        </vertices>")?;
+        writeln!(xml, "        <triangles>")?;
+        for [v1, v2, v3] in &object.triangles {
+            writeln!(
+                xml,
+                r#"          <triangle v1="{}" v2="{}" v3="{}"/>"#,
+                v1, v2, v3
+            )?;
+        }
+        writeln!(xml, "        </triangles>")?;
+        writeln!(xml, "      </mesh>")?;
+        writeln!(xml, "    </object>")?;
+    }
+    writeln!(xml, "  </resources>")?;
+
+    writeln!(xml, "  <build>")?;
+    for i in 0..objects.len() {
+        writeln!(xml, r#"    <item objectid="{}"/>"#, i + 2)?;
+    }
+    writeln!(xml, "  </build>")?;
+    writeln!(xml, "</model>")
+}
+
+/// The positions and triangles of a mesh with one vertex per position.
+///
+/// Meshes hold a vertex per normal at hard edges; 3MF only knows
+/// positions, and its triangles must connect through shared vertices.
+/// Triangles that collapse onto fewer than three vertices are dropped.
+fn shared_vertices(mesh: &Mesh) -> (Vec<[f64; 3]>, Vec<[usize; 3]>) {
+    use std::collections::HashMap;
+
+    let mut positions: Vec<[f64; 3]> = Vec::new();
+    let mut index: HashMap<[u64; 3], usize> = HashMap::new();
+    let remap: Vec<usize> = mesh
+        .vertices
+        .iter()
+        .map(|v| {
+            let p = [v.position.x, v.position.y, v.position.z];
+            *index.entry(p.map(f64::to_bits)).or_insert_with(|| {
+                positions.push(p);
+                positions.len() - 1
+            })
+        })
+        .collect();
+
+    let triangles = mesh
+        .faces
+        .iter()
+        .flat_map(|face| face_triangles(mesh, face))
+        .map(|triangle| triangle.map(|i| remap[i]))
+        .filter(|[a, b, c]| a != b && b != c && c != a)
+        .collect();
+
+    (positions, triangles)
+}
+
+/// Checks that every edge of a triangle mesh is used exactly once in each
+/// direction, i.e. the mesh is closed, manifold and consistently oriented.
+fn check_manifold(triangles: &[[usize; 3]]) -> Result<(), String> {
+    use std::collections::HashMap;
+
+    let mut edges: HashMap<(usize, usize), u32> = HashMap::new();
+    for triangle in triangles {
+        for i in 0..3 {
+            *edges
+                .entry((triangle[i], triangle[(i + 1) % 3]))
+                .or_default() += 1;
+        }
+    }
+    let bad = edges
+        .iter()
+        .filter(|&(&(a, b), &count)| count != 1 || edges.get(&(b, a)) != Some(&1))
+        .count();
+    match bad {
+        0 => Ok(()),
+        n => Err(format!(
+            "{} edges are not shared by exactly two opposite triangles",
+            n
+        )),
+    }
+}
+
+/// Escapes the characters that are special in XML text and attributes.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,125 @@
+//! Uncompressed zip archives, as used by USDZ packages.
+//!
+//! A USDZ package is a zip archive whose entries are stored without
+//! compression and whose file data starts on a 64-byte boundary, so that
+//! viewers can map the files in place. The alignment is reached by padding
+//! the extra field of each local file header.
+
+/// Alignment of the file data in bytes.
+pub const ALIGNMENT: usize = 64;
+
+/// Header ID of the padding extra field (the one the USD tools write).
+const PADDING_HEADER_ID: u16 = 0x1986;
+
+/// Builds a zip archive of stored (uncompressed) entries with 64-byte
+/// aligned file data.
+///
+/// # Arguments
+///
+/// * `entries` - File names and contents, in archive order
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, String>` - The archive bytes, or an error message when
+///   it would exceed the limits of a zip without the Zip64 extensions
+pub fn stored_zip(entries: &[(&str, &[u8])]) -> Result<Vec<u8>, String> {
+    let mut archive: Vec<u8> = Vec::new();
+    let mut central: Vec<u8> = Vec::new();
+
+    for (name, data) in entries {
+        let offset = archive.len();
+        let crc = crc32(data);
+        let size = u32::try_from(data.len()).map_err(|_| format!("{} is too large", name))?;
+        let name_length = u16::try_from(name.len()).map_err(|_| format!("{} is too long", name))?;
+
+        // Pad the extra field so the data starts on the alignment; an extra
+        // field is at least its 4 byte header
+        let unpadded = offset + 30 + name.len();
+        let mut extra = (ALIGNMENT - unpadded % ALIGNMENT) % ALIGNMENT;
+        if extra > 0 && extra < 4 {
+            extra += ALIGNMENT;
+        }
+
+        // Local file header
+        push_u32(&mut archive, 0x0403_4b50);
+        push_u16(&mut archive, 10); // Version needed to extract (1.0)
+        push_u16(&mut archive, 0); // Flags
+        push_u16(&mut archive, 0); // Compression: stored
+        push_u16(&mut archive, 0); // Modification time
+        push_u16(&mut archive, 0x21); // Modification date (1980-01-01)
+        push_u32(&mut archive, crc);
+        push_u32(&mut archive, size); // Compressed size
+        push_u32(&mut archive, size); // Uncompressed size
+        push_u16(&mut archive, name_length);
+        push_u16(&mut archive, extra as u16);
+        archive.extend_from_slice(name.as_bytes());
+        if extra > 0 {
+            push_u16(&mut archive, PADDING_HEADER_ID);
+            push_u16(&mut archive, (extra - 4) as u16);
+            archive.resize(archive.len() + extra - 4, 0);
+        }
+        archive.extend_from_slice(data);
+
+        // Central directory record
+        push_u32(&mut central, 0x0201_4b50);
+        push_u16(&mut central, 10); // Version made by
+        push_u16(&mut central, 10); // Version needed to extract
+        push_u16(&mut central, 0); // Flags
+        push_u16(&mut central, 0); // Compression: stored
+        push_u16(&mut central, 0); // Modification time
+        push_u16(&mut central, 0x21); // Modification date
+        push_u32(&mut central, crc);
+        push_u32(&mut central, size);
+        push_u32(&mut central, size);
+        push_u16(&mut central, name_length);
+        push_u16(&mut central, 0); // Extra field length
+        push_u16(&mut central, 0); // Comment length
+        push_u16(&mut central, 0); // Disk number
+        push_u16(&mut central, 0); // Internal attributes
+        push_u32(&mut central, 0); // External attributes
+        push_u32(
+            &mut central,
+            u32::try_from(offset).map_err(|_| "The archive is too large".to_string())?,
+        );
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset =
+        u32::try_from(archive.len()).map_err(|_| "The archive is too large".to_string())?;
+    let count = u16::try_from(entries.len()).map_err(|_| "Too many entries".to_string())?;
+    let central_size = central.len() as u32;
+    archive.extend_from_slice(&central);
+
+    // End of central directory record
+    push_u32(&mut archive, 0x0605_4b50);
+    push_u16(&mut archive, 0); // Number of this disk
+    push_u16(&mut archive, 0); // Disk where the central directory starts
+    push_u16(&mut archive, count); // Entries on this disk
+    push_u16(&mut archive, count); // Entries in total
+    push_u32(&mut archive, central_size);
+    push_u32(&mut archive, central_offset);
+    push_u16(&mut archive, 0); // Comment length
+
+    Ok(archive)
+}
+
+/// CRC-32 (IEEE 802.3) checksum of the data, as stored in zip headers.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn push_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
@@ -2,6 +2,7 @@ use pcbgen::{
     identify_layer_type,
     analyze_gerber_commands,
     gerber::parse,
+    gerber::plot::ARC_TOLERANCE,
 };
 use std::path::Path;
 
@@ -11,22 +12,26 @@ fn test_layer_type_detection() {
     let edge_cuts = Path::new("test_edge_cuts.gbr");
     assert_eq!(
         format!("{:?}", identify_layer_type(edge_cuts)),
-        "EdgeCuts"
+        "Some(EdgeCuts)"
     );
     
     // Test copper layers detection
     let top_copper = Path::new("test_f.cu.gbr");
     assert_eq!(
         format!("{:?}", identify_layer_type(top_copper)),
-        "Copper"
+        "Some(Copper)"
     );
     
     // Test silkscreen layers detection
     let top_silk = Path::new("test_f_silk.gbr");
     assert_eq!(
         format!("{:?}", identify_layer_type(top_silk)),
-        "Silkscreen"
+        "Some(Silkscreen)"
     );
+
+    // Unknown names are no longer assumed to be the board outline
+    let unknown = Path::new("test_drawing.gbr");
+    assert!(identify_layer_type(unknown).is_none());
 }
 
 #[test]
@@ -60,4 +65,1521 @@ G04 End of file*
     assert!(draws >= 1, "Should have at least 1 draw command");
     assert_eq!(arcs, 0, "Should have no arc commands");
     assert!(others > 0, "Should have some other commands");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_copper_mesh_from_commands() {
+    let content = r#"
+%FSLAX46Y46*%
+%MOMM*%
+%ADD10C,0.250000*%
+%ADD11R,1.000000X2.000000*%
+G01*
+D10*
+X1000000Y1000000D02*
+X5000000Y1000000D01*
+D11*
+X8000000Y8000000D03*
+G36*
+X0Y0D02*
+X2000000Y0D01*
+X2000000Y2000000D01*
+X0Y0D01*
+G37*
+M02*
+    "#;
+
+    let commands = parse::parse_gerber(content).expect("Failed to parse Gerber content");
+    let shapes = pcbgen::gerber::plot::plot_commands(&commands, ARC_TOLERANCE).unwrap();
+    assert_eq!(shapes.len(), 3, "Expected one stroke, one flash and one region");
+
+    let top = pcbgen::build_copper_mesh(&commands, true, Some(1.6)).unwrap();
+    assert!(!top.faces.is_empty());
+    assert!(top.vertices.iter().all(|v| v.position.z >= 1.6 - 1e-9));
+    assert!(top.vertices.iter().any(|v| v.position.z > 1.6));
+
+    // The flashed rectangle spans x 7.5..8.5 and y 7..9
+    let max_y = top
+        .vertices
+        .iter()
+        .map(|v| v.position.y)
+        .fold(f64::MIN, f64::max);
+    assert!((max_y - 9.0).abs() < 1e-9);
+
+    let bottom = pcbgen::build_copper_mesh(&commands, false, Some(1.6)).unwrap();
+    assert!(bottom.vertices.iter().all(|v| v.position.z <= 0.0));
+}
+
+#[test]
+fn test_silkscreen_mesh_from_commands() {
+    let content = r#"
+%FSLAX46Y46*%
+%MOMM*%
+%ADD10C,0.150000*%
+D10*
+X0Y0D02*
+X3000000Y0D01*
+G75*
+G03*
+X0Y3000000I-3000000J0D01*
+G01*
+M02*
+    "#;
+
+    let commands = parse::parse_gerber(content).expect("Failed to parse Gerber content");
+
+    let top = pcbgen::build_silkscreen_mesh(&commands, true, Some(1.6)).unwrap();
+    assert!(!top.faces.is_empty());
+    assert!(top.vertices.iter().all(|v| v.position.z > 1.6));
+
+    // The arc bulges out to the full 3 mm radius around the origin
+    let max_radius = top
+        .vertices
+        .iter()
+        .map(|v| v.position.x.hypot(v.position.y))
+        .fold(f64::MIN, f64::max);
+    assert!(max_radius > 3.0 && max_radius < 3.1);
+
+    let bottom = pcbgen::build_silkscreen_mesh(&commands, false, Some(1.6)).unwrap();
+    assert!(bottom.vertices.iter().all(|v| v.position.z < 0.0));
+}
+
+#[test]
+fn test_aperture_macro_parsing_and_flash() {
+    use pcbgen::gerber::types::{Aperture, Command};
+
+    let content = r#"
+%FSLAX46Y46*%
+%MOMM*%
+%AMRoundRect*
+0 Rectangle with rounded corners*
+0 $1 Rounding radius*
+0 $2 $3 $4 $5 $6 $7 $8 $9 X,Y pos of 4 corners*
+4,1,4,$2,$3,$4,$5,$6,$7,$8,$9,$2,$3,0*
+1,1,$1+$1,$2,$3*
+1,1,$1+$1,$4,$5*
+1,1,$1+$1,$6,$7*
+1,1,$1+$1,$8,$9*
+20,1,$1+$1,$2,$3,$4,$5,0*
+20,1,$1+$1,$4,$5,$6,$7,0*
+20,1,$1+$1,$6,$7,$8,$9,0*
+20,1,$1+$1,$8,$9,$2,$3,0*%
+%AMThermal*
+$4=$1x0.5*
+7,0,0,$1,$4,$2,45*%
+%ADD10RoundRect,0.250000X-0.450000X-0.262500X0.450000X-0.262500X0.450000X0.262500X-0.450000X0.262500X0*%
+%ADD11Thermal,2.0X0.2*%
+D10*
+X1000000Y1000000D03*
+D11*
+X5000000Y5000000D03*
+M02*
+    "#;
+
+    let commands = parse::parse_gerber(content).expect("Failed to parse Gerber content");
+
+    let macros = commands
+        .iter()
+        .filter(|c| matches!(c, Command::DefineMacro(_)))
+        .count();
+    assert_eq!(macros, 2);
+    assert!(commands.iter().any(|c| matches!(
+        c,
+        Command::DefineAperture { code: 10, aperture: Aperture::Macro { name, params } }
+            if name == "RoundRect" && params.len() == 10
+    )));
+
+    let expr = parse::parse_macro_expression("-($1+2)x3/$2").unwrap();
+    let mut vars = std::collections::HashMap::new();
+    vars.insert(1, 1.0);
+    vars.insert(2, 2.0);
+    assert_eq!(pcbgen::gerber::macros::evaluate_expression(&expr, &vars), Ok(-4.5));
+
+    // RoundRect primitives merge into 1 outline; Thermal: 4 segments
+    let shapes = pcbgen::gerber::plot::plot_commands(&commands, ARC_TOLERANCE).unwrap();
+    assert_eq!(shapes.len(), 1 + 4);
+
+    // The rounded rectangle spans 0.3..1.7 in x around the flash point: its
+    // corners at ±0.45 grown by the 0.25 rounding radius
+    let round_rect_max_x = shapes[..1]
+        .iter()
+        .flatten()
+        .map(|p| p.x)
+        .fold(f64::MIN, f64::max);
+    assert!((round_rect_max_x - 1.7).abs() < 1e-9);
+
+    // Thermal segments stay within the outer diameter (composed outlines
+    // are snapped to a 1 nm grid)
+    assert!(shapes[1..].iter().flatten().all(|p| {
+        ((p.x - 5.0).powi(2) + (p.y - 5.0).powi(2)).sqrt() <= 1.0 + 1e-6
+    }));
+
+    // Exposure off erases the earlier primitives, and clear polarity
+    // erases the earlier objects
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%AMDONUT*\n1,1,2,0,0*\n1,0,1,0,0*%\n%ADD10DONUT*%\n%ADD11R,4X4*%\n%ADD12R,2X2*%\nD10*\nX0Y0D03*\nD11*\nX10000000Y0D03*\n%LPC*%\nD12*\nX10000000Y0D03*\nM02*\n";
+    let commands = parse::parse_gerber_file(content, true).unwrap().commands;
+    let shapes = pcbgen::gerber::plot::plot_commands(&commands, ARC_TOLERANCE).unwrap();
+    assert_eq!(shapes.len(), 2);
+    let donut = pcbgen::geometry::polygon::signed_area(&shapes[0]);
+    assert!((donut - 0.75 * std::f64::consts::PI).abs() < 0.05);
+    let layer = pcbgen::gerber::plot::plot_layer(&commands, ARC_TOLERANCE).unwrap();
+    assert_eq!(layer.len(), 2);
+    assert!(layer.iter().all(|polygon| polygon.holes.len() == 1));
+    assert!((pcbgen::geometry::polygon::total_area(&layer) - donut - 12.0).abs() < 1e-9);
+
+    // A division by zero or a polygon with too many vertices fails the
+    // layer instead of dropping the pad
+    let expr = parse::parse_macro_expression("$1/$2").unwrap();
+    assert!(pcbgen::gerber::macros::evaluate_expression(&expr, &vars).is_ok());
+    vars.insert(2, 0.0);
+    assert!(pcbgen::gerber::macros::evaluate_expression(&expr, &vars).is_err());
+    for (primitive, params) in [("1,1,$1/$2,0,0", "1X0"), ("5,1,20,0,0,1,0", "1")] {
+        let content = format!(
+            "%FSLAX46Y46*%\n%MOMM*%\n%AMBAD*\n{}*%\n%ADD11BAD,{}*%\nD11*\nX0Y0D03*\nM02*\n",
+            primitive, params
+        );
+        let commands = parse::parse_gerber_file(&content, true).unwrap().commands;
+        let error = pcbgen::gerber::plot::plot_commands(&commands, ARC_TOLERANCE).unwrap_err();
+        assert!(error.contains("BAD"));
+    }
+}
+
+#[test]
+fn test_standard_apertures_with_holes() {
+    use pcbgen::geometry::polygon::signed_area;
+    use pcbgen::gerber::types::{Aperture, Command};
+
+    let content = r#"
+%FSLAX46Y46*%
+%MOMM*%
+%ADD10O,2.000000X1.000000*%
+%ADD11P,1.000000X6X30*%
+%ADD12C,2.000000X1.000000*%
+%ADD13R,2.000000X2.000000X0.500000*%
+D10*
+X0Y0D03*
+D11*
+X0Y0D03*
+D12*
+X0Y0D03*
+D13*
+X0Y0D03*
+M02*
+    "#;
+
+    let commands = parse::parse_gerber(content).expect("Failed to parse Gerber content");
+    assert!(commands.iter().any(|c| matches!(
+        c,
+        Command::DefineAperture { code: 11, aperture: Aperture::Polygon { vertices: 6, .. } }
+    )));
+    assert!(commands.iter().any(|c| matches!(
+        c,
+        Command::DefineAperture { code: 12, aperture: Aperture::Circle { hole: Some(_), .. } }
+    )));
+
+    let shapes = pcbgen::gerber::plot::plot_commands(&commands, ARC_TOLERANCE).unwrap();
+    // Every aperture is a single outline, holes bridged in
+    assert_eq!(shapes.len(), 4);
+    assert!(shapes.iter().all(|s| signed_area(s) > 0.0));
+
+    let area = |range: std::ops::Range<usize>| -> f64 {
+        shapes[range].iter().map(|s| signed_area(s)).sum()
+    };
+    let pi = std::f64::consts::PI;
+
+    // Stadium: 1x1 square plus a 1 mm circle
+    assert!((area(0..1) - (1.0 + pi * 0.25)).abs() < 0.01);
+    // Hexagon of circumradius 0.5
+    assert!((area(1..2) - 1.5 * 3f64.sqrt() * 0.25).abs() < 1e-9);
+    // Annulus 2 mm / 1 mm
+    assert!((area(2..3) - pi * (1.0 - 0.25)).abs() < 0.02);
+    // 2x2 square with a 0.5 mm hole
+    assert!((area(3..4) - (4.0 - pi * 0.0625)).abs() < 0.01);
+
+    // A hole wider than the aperture is reported, and clipped to the
+    // aperture rather than dropping it: the corners of the square remain
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10R,1X1X1.2*%\nD10*\nX0Y0D03*\nM02*\n";
+    let gerber = parse::parse_gerber_file(content, false).unwrap();
+    assert_eq!(gerber.diagnostics.len(), 1);
+    assert!(gerber.diagnostics[0].reason.contains("does not fit"));
+    let corners = pcbgen::gerber::plot::plot_layer(&gerber.commands, ARC_TOLERANCE).unwrap();
+    assert_eq!(corners.len(), 4);
+    let corner_area = pcbgen::geometry::polygon::total_area(&corners);
+    assert!(corner_area > 0.0 && corner_area < 1.0 - pi * 0.25);
+}
+
+#[test]
+fn test_parsing_is_independent_of_line_layout() {
+    let one_per_line = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX1000000Y0D01*\nX1000000Y1000000D01*\nM02*\n";
+    let single_line = "%FSLAX46Y46*%%MOMM*%%ADD10C,0.1*%D10*X0Y0D02*X1000000Y0D01*X1000000Y1000000D01*M02*";
+    let split_up = "%FSLAX46Y46*MOMM*%\r\n%ADD10C,\r\n0.1*%D10*X0Y0D02*X1000\n000Y0D01*\nX1000000Y1000000D01*M02*";
+
+    let reference = format!("{:?}", parse::parse_gerber(one_per_line).unwrap());
+    assert_eq!(format!("{:?}", parse::parse_gerber(single_line).unwrap()), reference);
+    assert_eq!(format!("{:?}", parse::parse_gerber(split_up).unwrap()), reference);
+
+    let (moves, draws, _, _) = analyze_gerber_commands(&parse::parse_gerber(single_line).unwrap());
+    assert_eq!((moves, draws), (1, 2));
+
+    let blocks = parse::tokenize("G04 comment*\n  %MOMM*%\nX1Y1D03*");
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[1].text, "%MOMM*%");
+    assert_eq!((blocks[2].line, blocks[2].column), (3, 1));
+}
+
+#[test]
+fn test_parse_diagnostics_and_strict_mode() {
+    use pcbgen::gerber::types::DiagnosticKind;
+
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10Q,0.1*%\n%ADD11C,abc*%\nD10*\nG91*\nX1000000Y1000000D01*\nM02*";
+
+    let gerber = parse::parse_gerber_file(content, false).expect("Lenient parse should succeed");
+    assert_eq!(gerber.diagnostics.len(), 3);
+
+    // %ADD10Q references a macro named Q that was never defined
+    assert!(gerber.diagnostics[0].reason.contains("macro Q"));
+
+    let malformed = &gerber.diagnostics[1];
+    assert_eq!((malformed.line, malformed.column), (4, 1));
+    assert_eq!(malformed.text, "%ADD11C,abc*%");
+    assert_eq!(malformed.kind, DiagnosticKind::Malformed);
+
+    let unsupported = &gerber.diagnostics[2];
+    assert_eq!(unsupported.line, 6);
+    assert_eq!(unsupported.kind, DiagnosticKind::Unsupported);
+
+    let error = parse::parse_gerber_file(content, true).unwrap_err();
+    assert!(error.contains("line 4, column 1"));
+    assert!(error.contains("G91*"));
+}
+
+#[test]
+fn test_x2_attribute_parsing() {
+    let content = r#"
+%TF.GenerationSoftware,KiCad,Pcbnew,8.0.8*%
+%TF.FileFunction,Copper,L1,Top*%
+%TF.FilePolarity,Positive*%
+%FSLAX46Y46*%
+%MOMM*%
+%TA.AperFunction,ViaPad*%
+%ADD10C,0.600000*%
+%TA.AperFunction,Conductor*%
+%ADD11C,0.250000*%
+%TD*%
+%ADD12C,1.000000*%
+D10*
+%TO.N,GND*%
+X1000000Y1000000D03*
+D11*
+%TO.C,R1*%
+%TO.P,R1,2*%
+X2000000Y1000000D02*
+X3000000Y1000000D01*
+%TD.C*%
+%TD.P*%
+X4000000Y1000000D01*
+%TD*%
+D12*
+X5000000Y1000000D03*
+M02*
+    "#;
+
+    let gerber = parse::parse_gerber_file(content, true).expect("Attributes should parse");
+
+    assert_eq!(
+        gerber.file_attribute(".FileFunction"),
+        Some(&["Copper".to_string(), "L1".to_string(), "Top".to_string()][..])
+    );
+    assert_eq!(gerber.aperture_function(10), Some("ViaPad"));
+    assert_eq!(gerber.aperture_function(11), Some("Conductor"));
+    assert_eq!(gerber.aperture_function(12), None);
+
+    let graphics: Vec<usize> = gerber
+        .commands
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| {
+            matches!(
+                c,
+                pcbgen::gerber::types::Command::Draw { .. } | pcbgen::gerber::types::Command::Flash { .. }
+            )
+        })
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(graphics.len(), 4);
+
+    // Via flash carries the net only
+    assert_eq!(gerber.object_attribute(graphics[0], ".N"), Some(&["GND".to_string()][..]));
+    assert_eq!(gerber.object_attribute(graphics[0], ".C"), None);
+    // First trace segment carries net, component and pin
+    assert_eq!(gerber.object_attribute(graphics[1], ".C"), Some(&["R1".to_string()][..]));
+    assert_eq!(gerber.object_attribute(graphics[1], ".P").map(|p| p.len()), Some(2));
+    // After deleting .C and .P only the net remains
+    assert_eq!(gerber.object_attribute(graphics[2], ".P"), None);
+    assert!(gerber.object_attribute(graphics[2], ".N").is_some());
+    // %TD* clears everything
+    assert!(!gerber.object_attributes.contains_key(&graphics[3]));
+}
+
+#[test]
+fn test_layer_identification_prefers_file_function() {
+    use pcbgen::identify_layer;
+    use pcbgen::intermediate::model::{LayerSource, LayerType, Side};
+
+    // A renamed file is identified by its X2 attribute, not its name
+    let content = "%TF.FileFunction,Copper,L2,Bot*%\n%FSLAX46Y46*%\n%MOMM*%\nM02*\n";
+    let gerber = parse::parse_gerber_file(content, false).expect("Failed to parse");
+    let info = identify_layer(Path::new("board-F_Cu.gbr"), Some(&gerber)).unwrap();
+    assert_eq!(info.layer_type, LayerType::Copper);
+    assert_eq!(info.side, Some(Side::Bottom));
+    assert_eq!(info.copper_index, Some(2));
+    assert_eq!(info.source, LayerSource::FileFunction);
+
+    // Without the attribute, the file name is the fallback
+    let info = identify_layer(Path::new("board.GBS"), None).unwrap();
+    assert_eq!(info.layer_type, LayerType::Soldermask);
+    assert_eq!(info.side, Some(Side::Bottom));
+    assert_eq!(info.source, LayerSource::FileName);
+
+    let info = identify_layer(Path::new("board-In2_Cu.gbr"), None).unwrap();
+    assert_eq!(info.side, Some(Side::Inner));
+    assert_eq!(info.copper_index, Some(3));
+
+    assert!(identify_layer(Path::new("board-notes.gbr"), None).is_none());
+}
+
+#[test]
+fn test_excellon_drill_parsing() {
+    use pcbgen::excellon::parse::parse_excellon;
+    use pcbgen::excellon::types::Plating;
+
+    // KiCad style: decimal inch coordinates with per-tool plating attributes
+    let content = "M48
+; #@! TF.FileFunction,MixedPlating,1,2
+FMAT,2
+INCH
+; #@! TA.AperFunction,Plated,PTH,ViaDrill
+T1C0.0118
+; #@! TA.AperFunction,NonPlated,NPTH,ComponentDrill
+T2C0.1260
+%
+G90
+G05
+T1
+X1.0Y-0.5
+X1.1
+T2
+X0.2Y0.2
+M30
+";
+    let drill = parse_excellon(content, Plating::Plated, true, ARC_TOLERANCE)
+        .expect("Failed to parse drill file");
+    assert_eq!(drill.holes.len(), 3);
+    assert!(drill.diagnostics.is_empty());
+    assert!((drill.tools[&2] - 3.2004).abs() < 1e-9);
+
+    let second = &drill.holes[1];
+    assert_eq!(second.plating, Plating::Plated);
+    assert!((second.position.x - 27.94).abs() < 1e-9);
+    assert!((second.position.y + 12.7).abs() < 1e-9, "Y is modal");
+    assert_eq!(drill.holes[2].plating, Plating::NonPlated);
+
+    // Metric with suppressed leading zeros and an explicit 3.3 format
+    let content = "M48\nMETRIC,TZ,000.000\nT01C0.800\n%\nT01\nX12500Y-3000\nM30\n";
+    let drill = parse_excellon(content, Plating::NonPlated, false, ARC_TOLERANCE).unwrap();
+    let hole = &drill.holes[0];
+    assert!((hole.position.x - 12.5).abs() < 1e-9);
+    assert!((hole.position.y + 3.0).abs() < 1e-9);
+    assert!((hole.diameter - 0.8).abs() < 1e-9);
+    assert_eq!(hole.plating, Plating::NonPlated);
+
+    // Inch with suppressed trailing zeros in the default 2.4 format
+    let content = "M48\nINCH,LZ\nT1C0.04\n%\nT1\nX015Y0225\nM30\n";
+    let drill = parse_excellon(content, Plating::Plated, false, ARC_TOLERANCE).unwrap();
+    assert!((drill.holes[0].position.x - 1.5 * 25.4).abs() < 1e-9);
+    assert!((drill.holes[0].position.y - 2.25 * 25.4).abs() < 1e-9);
+
+    // Hits with an undefined tool are reported, and fail in strict mode
+    let content = "M48\nMETRIC\n%\nT3\nX1.0Y1.0\nM30\n";
+    let drill = parse_excellon(content, Plating::Plated, false, ARC_TOLERANCE).unwrap();
+    assert!(drill.holes.is_empty());
+    assert_eq!(drill.diagnostics.len(), 2);
+    assert!(parse_excellon(content, Plating::Plated, true, ARC_TOLERANCE).is_err());
+}
+
+#[test]
+fn test_board_mesh_with_drill_holes() {
+    use pcbgen::excellon::types::{Hole, Plating};
+    use pcbgen::geometry::polygon::{bridge_holes, signed_area};
+    use pcbgen::geometry::shapes::regular_polygon;
+    use pcbgen::gerber::types::Point;
+    use pcbgen::intermediate::stackup::Stackup;
+    use pcbgen::{build_board_mesh, build_plated_barrels_mesh};
+
+    // 20 x 10 mm board
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let commands = parse::parse_gerber(content).expect("Failed to parse");
+
+    let hole = |x: f64, y: f64, diameter: f64, plating: Plating| Hole {
+        tool: 1,
+        position: Point { x, y },
+        end: None,
+        diameter,
+        plating,
+    };
+    let holes = vec![
+        hole(5.0, 5.0, 3.2, Plating::NonPlated),
+        hole(15.0, 5.0, 1.0, Plating::Plated),
+        // Crosses the board edge and notches it
+        hole(19.9, 5.0, 1.0, Plating::Plated),
+    ];
+
+    let mesh = build_board_mesh(&commands, &holes, Some(1.6)).expect("Failed to build board");
+    // The caps hold the notched outline (4 corners and 15 points along the
+    // notch) and both holes joined by bridges, triangulated; every wall is
+    // two triangles. Holes get as many points as the arc tolerance calls
+    // for: 40 around the 3.2 mm hole, 24 around the plated 1 mm hole
+    let cap = 4 + 15 + (40 + 2) + (24 + 2);
+    assert_eq!(mesh.faces.len(), 2 * (cap - 2) + 2 * (4 + 15 + 40 + 24));
+    assert!(mesh.faces.iter().all(|face| face.vertices.len() == 3));
+    assert!(mesh.vertices.iter().all(|v| v.position.x <= 20.0));
+
+    // Two plated holes make two barrels, each a ring around its hole
+    // They span the outer copper layers of the stackup
+    let z_range = |stackup: &Stackup| {
+        let barrels = build_plated_barrels_mesh(&holes, stackup, ARC_TOLERANCE).unwrap();
+        assert!(barrels.faces.len() > 4);
+        let z = barrels.vertices.iter().map(|v| v.position.z);
+        (z.clone().fold(f64::MAX, f64::min), z.fold(f64::MIN, f64::max))
+    };
+    let (z_min, z_max) = z_range(&Stackup::with_copper_layers(2, 1.6));
+    assert!((z_min + pcbgen::COPPER_THICKNESS).abs() < 1e-9);
+    assert!((z_max - (1.6 + pcbgen::COPPER_THICKNESS)).abs() < 1e-9);
+    // A single copper layer sits on the core, which the barrels still cross
+    let (z_min, z_max) = z_range(&Stackup::with_copper_layers(1, 1.6));
+    assert!(z_min.abs() < 1e-9 && (z_max - (1.6 + pcbgen::COPPER_THICKNESS)).abs() < 1e-9);
+
+    // Bridging keeps the area of the outline minus the holes
+    let outer = vec![
+        Point { x: 0.0, y: 0.0 },
+        Point { x: 20.0, y: 0.0 },
+        Point { x: 20.0, y: 10.0 },
+        Point { x: 0.0, y: 10.0 },
+    ];
+    let holes: Vec<Vec<Point>> = [3.0, 8.0, 13.0]
+        .iter()
+        .map(|x| regular_polygon(&Point { x: *x, y: 5.0 }, 1.0, 8, 0.0))
+        .collect();
+    let expected = signed_area(&outer) - holes.iter().map(|h| signed_area(h)).sum::<f64>();
+    let bridged = bridge_holes(&outer, &holes);
+    assert_eq!(bridged.len(), 4 + 3 * (8 + 2));
+    assert!((signed_area(&bridged) - expected).abs() < 1e-9);
+}
+
+#[test]
+fn test_mesh_post_processing() {
+    use pcbgen::build_board_mesh;
+    use pcbgen::excellon::types::{Hole, Plating};
+    use pcbgen::gerber::types::Point;
+    use std::collections::HashMap;
+
+    // 20 x 10 mm board with a round hole
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let commands = parse::parse_gerber(content).expect("Failed to parse");
+    let holes = vec![Hole {
+        tool: 1,
+        position: Point { x: 5.0, y: 5.0 },
+        end: None,
+        diameter: 3.2,
+        plating: Plating::NonPlated,
+    }];
+    let mut mesh = build_board_mesh(&commands, &holes, Some(1.6)).unwrap();
+    mesh.post_process();
+
+    // Every board corner is split three ways (cap and two walls); every
+    // point of the round hole two ways (cap and the smooth hole wall)
+    assert_eq!(mesh.vertices.len(), 4 * 2 * 3 + 40 * 2 * 2);
+    assert!(mesh.faces.iter().all(|face| face.vertices.len() == 3));
+
+    let key = |p: &pcbgen::intermediate::model::Point3D| {
+        ((p.x * 1e6).round() as i64, (p.y * 1e6).round() as i64, (p.z * 1e6).round() as i64)
+    };
+    for face in &mesh.faces {
+        let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[face.vertices[i]].position);
+        let (ux, uy, uz) = (b.x - a.x, b.y - a.y, b.z - a.z);
+        let (vx, vy, vz) = (c.x - a.x, c.y - a.y, c.z - a.z);
+        let n = (uy * vz - uz * vy, uz * vx - ux * vz, ux * vy - uy * vx);
+        let length = (n.0 * n.0 + n.1 * n.1 + n.2 * n.2).sqrt();
+        for &index in &face.vertices {
+            let normal = &mesh.vertices[index].normal;
+            // Walls are shaded as walls, caps as caps
+            let cos = (normal.x * n.0 + normal.y * n.1 + normal.z * n.2) / length;
+            assert!(cos >= 30f64.to_radians().cos() - 1e-9);
+            assert!(normal.z.abs() < 1e-9 || (normal.z.abs() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    // The hole walls face into the hole
+    for vertex in &mesh.vertices {
+        let (p, n) = (&vertex.position, &vertex.normal);
+        let r = (p.x - 5.0).hypot(p.y - 5.0);
+        if r < 2.0 && n.z.abs() < 1e-9 {
+            assert!((5.0 - p.x) * n.x + (5.0 - p.y) * n.y > 0.99 * r);
+        }
+    }
+
+    // No duplicates are left, and every edge is shared by exactly two faces
+    let mut seen = HashMap::new();
+    for vertex in &mesh.vertices {
+        let normal = key(&vertex.normal);
+        assert!(seen.insert((key(&vertex.position), normal), ()).is_none());
+    }
+    let mut edges: HashMap<_, i32> = HashMap::new();
+    for face in &mesh.faces {
+        for i in 0..3 {
+            let a = key(&mesh.vertices[face.vertices[i]].position);
+            let b = key(&mesh.vertices[face.vertices[(i + 1) % 3]].position);
+            *edges.entry((a, b)).or_default() += 1;
+            *edges.entry((b, a)).or_default() -= 1;
+        }
+    }
+    assert!(edges.values().all(|&count| count == 0));
+}
+
+#[test]
+fn test_edge_cuts_contours() {
+    use pcbgen::{board_outline, build_board_mesh};
+
+    // A 20 x 10 mm board drawn edge by edge, out of order and direction,
+    // with a 5 µm gap; a 2 x 2 mm cutout; and a stray open segment
+    let content = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\n\
+        X0Y0D02*\nX20000000Y0D01*\n\
+        X0Y10000000D02*\nX20000000Y10000000D01*\n\
+        X5000000Y4000000D02*\nX7000000Y4000000D01*\nX7000000Y6000000D01*\nX5000000Y6000000D01*\nX5000000Y4000000D01*\n\
+        X20000000Y0D02*\nX20000000Y10000000D01*\n\
+        X10000000Y2000000D02*\nX12000000Y2000000D01*\n\
+        X0Y10005000D02*\nX0Y0D01*\nM02*\n";
+    let commands = parse::parse_gerber(content).expect("Failed to parse");
+
+    let (outline, open) = board_outline(&commands, ARC_TOLERANCE).unwrap();
+    assert_eq!(outline.len(), 1);
+    assert_eq!((outline[0].outer.len(), outline[0].holes.len()), (4, 1));
+    assert!((outline[0].area() - (200.0 - 4.0)).abs() < 0.1);
+    // The stray segment is left out and reported
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].text, "(10.000, 2.000) to (12.000, 2.000)");
+
+    // The cutout goes through the board: walls of the outline and the cutout
+    let mesh = build_board_mesh(&commands, &[], Some(1.6)).unwrap();
+    let cap = 4 + (4 + 2);
+    assert_eq!(mesh.faces.len(), 2 * (cap - 2) + 2 * (4 + 4));
+
+    // Nothing closes: no board
+    let open = "%FSLAX46Y46*%\n%MOMM*%\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nM02*\n";
+    assert!(board_outline(&parse::parse_gerber(open).unwrap(), ARC_TOLERANCE).is_err());
+
+    // Inch units: a 1 x 0.5 inch board with a round cutout of 0.1 inch radius
+    let inch = "%FSLAX25Y25*%\n%MOIN*%\nG75*\nX0Y0D02*\nX100000Y0D01*\nX100000Y50000D01*\n\
+        X0Y50000D01*\nX0Y0D01*\nX60000Y25000D02*\nG03X60000Y25000I-10000J0D01*\nM02*\n";
+    let (outline, _) = board_outline(&parse::parse_gerber(inch).unwrap(), ARC_TOLERANCE).unwrap();
+    let (width, height, radius) = (25.4, 12.7, 2.54);
+    assert_eq!(outline[0].holes.len(), 1);
+    let hole = std::f64::consts::PI * radius * radius;
+    assert!(outline[0].outer.iter().any(|p| (p.x - width).hypot(p.y - height) < 1e-6));
+    assert!((outline[0].area() - (width * height - hole)).abs() < 0.1);
+}
+
+#[test]
+fn test_arc_tolerance_and_quadrant_modes() {
+    use pcbgen::gerber::plot::{arc_points, circle_segments, plot_commands};
+    use pcbgen::gerber::types::{Command, Point, QuadrantMode};
+    use pcbgen::{ProcessOptions, board_outline, process_gerber_files_with_options};
+    use std::f64::consts::PI;
+
+    // A round board: one full-circle arc that ends where it starts
+    let round = "%FSLAX46Y46*%\n%MOMM*%\nG75*\nX0Y0D02*\nG03X0Y0I10000000J0D01*\nM02*\n";
+    let commands = parse::parse_gerber(round).expect("Failed to parse");
+    assert!(commands
+        .iter()
+        .any(|cmd| matches!(cmd, Command::SetQuadrantMode(QuadrantMode::Multi))));
+    let (coarse, _) = board_outline(&commands, 0.05).unwrap();
+    let (fine, _) = board_outline(&commands, 0.0005).unwrap();
+    assert!(fine[0].outer.len() > coarse[0].outer.len());
+    // The area lost to the chords is below the tolerance times the perimeter
+    for (outline, tolerance) in [(&coarse, 0.05), (&fine, 0.0005)] {
+        let lost = PI * 100.0 - outline[0].area();
+        assert!(lost > 0.0 && lost < tolerance * 2.0 * PI * 10.0);
+    }
+
+    // Chords stay within the tolerance, and larger radii take more of them
+    let origin = Point { x: 0.0, y: 0.0 };
+    let quarter = |r: f64, tolerance: f64| {
+        let start = Point { x: r, y: 0.0 };
+        let end = Point { x: 0.0, y: r };
+        let mut points = vec![start.clone()];
+        points.extend(arc_points(&start, &origin, &end, false, tolerance));
+        points
+    };
+    for (r, tolerance) in [(0.1, 0.005), (1.0, 0.005), (10.0, 0.005), (10.0, 0.0001)] {
+        let points = quarter(r, tolerance);
+        for pair in points.windows(2) {
+            let mid_x = (pair[0].x + pair[1].x) / 2.0;
+            let mid_y = (pair[0].y + pair[1].y) / 2.0;
+            assert!(r - mid_x.hypot(mid_y) <= tolerance + 1e-12);
+        }
+    }
+    assert!(quarter(10.0, 0.005).len() > quarter(1.0, 0.005).len());
+    assert!(quarter(10.0, 0.0001).len() > quarter(10.0, 0.005).len());
+
+    // Circle and obround apertures and macro circles are flattened to the
+    // tolerance too
+    let flashes = "%FSLAX46Y46*%\n%MOMM*%\n%AMDOT*\n1,1,10,0,0*%\n%ADD10C,10*%\n%ADD11DOT*%\n\
+        %ADD12O,15X10*%\nD10*\nX0Y0D03*\nD11*\nX20000000Y0D03*\nD12*\nX40000000Y0D03*\nM02*\n";
+    let commands = parse::parse_gerber(flashes).unwrap();
+    for tolerance in [0.05, 0.005] {
+        let segments = circle_segments(5.0, tolerance);
+        let shapes = plot_commands(&commands, tolerance).unwrap();
+        let sizes: Vec<usize> = shapes.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [segments, segments, segments + 2]);
+    }
+    assert!(circle_segments(5.0, 0.005) > circle_segments(5.0, 0.05));
+
+    // In circular mode a missing offset is zero, so I alone draws the
+    // round board
+    let round = "%FSLAX46Y46*%\n%MOMM*%\nG75*\nX0Y0D02*\nG03X0Y0I10000000D01*\nM02*\n";
+    let (outline, _) = board_outline(&parse::parse_gerber(round).unwrap(), ARC_TOLERANCE).unwrap();
+    let lost = PI * 100.0 - outline[0].area();
+    assert!(lost > 0.0 && lost < ARC_TOLERANCE * 2.0 * PI * 10.0);
+
+    // A tolerance that is not a positive number is rejected
+    for arc_tolerance in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let options = ProcessOptions { arc_tolerance: Some(arc_tolerance), ..Default::default() };
+        assert!(process_gerber_files_with_options("gerbers", &options).is_err());
+    }
+
+    // Single-quadrant mode: unsigned offsets, the center is the one giving
+    // an arc of at most 90°
+    let single = "%FSLAX46Y46*%\n%MOMM*%\nG74*\nX0Y0D02*\nX1000000Y0D01*\n\
+        G03X0Y1000000I1000000J0D01*\nG01X0Y0D01*\nM02*\n";
+    let (outline, _) = board_outline(&parse::parse_gerber(single).unwrap(), ARC_TOLERANCE).unwrap();
+    assert!((outline[0].area() - PI / 4.0).abs() < 0.01);
+    assert!(outline[0].outer.iter().all(|p| p.x.hypot(p.y) <= 1.0 + 1e-9));
+}
+
+#[test]
+fn test_excellon_slots() {
+    use pcbgen::build_board_mesh;
+    use pcbgen::excellon::parse::parse_excellon;
+    use pcbgen::excellon::types::Plating;
+
+    // A G85 canned slot, a routed straight slot and a routed arc
+    let content = "M48
+METRIC
+T1C1.000
+%
+G90
+G05
+T1
+X5.0Y5.0G85X8.0Y5.0
+G00X12.0Y5.0
+M15
+G01X12.0Y8.0
+M16
+G00X3.0Y2.0
+M15
+G03X1.0Y2.0I-1.0J0.0
+M16
+G05
+X15.0Y2.0
+M30
+";
+    let drill = parse_excellon(content, Plating::NonPlated, true, ARC_TOLERANCE)
+        .expect("Failed to parse slots");
+
+    let g85 = &drill.holes[0];
+    assert!(g85.is_slot());
+    assert!((g85.position.x - 5.0).abs() < 1e-9);
+    assert!((g85.end.as_ref().unwrap().x - 8.0).abs() < 1e-9);
+
+    let routed = &drill.holes[1];
+    assert!((routed.position.y - 5.0).abs() < 1e-9);
+    assert!((routed.end.as_ref().unwrap().y - 8.0).abs() < 1e-9);
+
+    // The half circle is split into straight slots ending at the arc end
+    let arc: Vec<_> = drill.holes[2..drill.holes.len() - 1].iter().collect();
+    assert!(arc.len() > 1);
+    let arc_end = arc.last().unwrap().end.as_ref().unwrap();
+    assert!((arc_end.x - 1.0).abs() < 1e-9 && (arc_end.y - 2.0).abs() < 1e-9);
+
+    // A coarser arc tolerance splits the arc into fewer slots
+    let coarse = parse_excellon(content, Plating::NonPlated, true, 0.1).unwrap();
+    assert!(coarse.holes.len() < drill.holes.len());
+
+    // Back in drill mode, hits are round holes again
+    assert!(!drill.holes.last().unwrap().is_slot());
+
+    // The board gets one stadium-shaped hole for the G85 slot: 24 walls
+    // around the two caps and the two straight sides, as triangles
+    let board = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let commands = parse::parse_gerber(board).expect("Failed to parse");
+    let mesh = build_board_mesh(&commands, &drill.holes[..1], Some(1.6)).unwrap();
+    assert_eq!(mesh.faces.len(), 2 * (4 + 26 + 2 - 2) + 2 * (4 + 26));
+}
+
+#[test]
+fn test_job_file_parsing() {
+    use pcbgen::gerber::job::{Polarity, parse_job_file, parse_json};
+    use pcbgen::intermediate::model::{LayerSource, LayerType, Side};
+    use pcbgen::layer_from_job;
+
+    let content = std::fs::read_to_string("gerbers/reference-pcb-job.gbrjob")
+        .expect("Failed to read job file");
+    let job = parse_job_file(&content).expect("Failed to parse job file");
+
+    assert_eq!(job.project.as_deref(), Some("reference-pcb"));
+    assert_eq!(job.generator.as_deref(), Some("KiCad Pcbnew 8.0.8"));
+    assert_eq!(job.size, Some((189.44, 103.79)));
+    assert_eq!(job.layer_count, Some(2));
+    assert_eq!(job.board_thickness, Some(1.6));
+    assert_eq!(job.files.len(), 9);
+
+    let mask = job.file("reference-pcb-F_Mask.gbr").unwrap();
+    assert_eq!(mask.polarity, Some(Polarity::Negative));
+
+    // The job file decides the layer even when the file name says otherwise
+    let info = layer_from_job(&job, Path::new("renamed/reference-pcb-B_Paste.gbr")).unwrap();
+    assert_eq!(info.layer_type, LayerType::Paste);
+    assert_eq!(info.side, Some(Side::Bottom));
+    assert_eq!(info.source, LayerSource::JobFile);
+    assert!(layer_from_job(&job, Path::new("unlisted.gbr")).is_none());
+
+    // Escapes, nesting and malformed input
+    let value = parse_json(r#"{"a": [1, -2.5e1, true, null], "b": "x\"A"}"#).unwrap();
+    assert_eq!(value.get("a").unwrap().as_array()[1].as_f64(), Some(-25.0));
+    assert_eq!(value.get("b").unwrap().as_str(), Some("x\"A"));
+    assert!(parse_json("{\"a\": }").is_err());
+}
+
+#[test]
+fn test_job_file_thickness_and_cli_override() {
+    use pcbgen::{ProcessOptions, process_gerber_files_with_options};
+
+    // From the job file
+    let model = process_gerber_files_with_options("gerbers", &ProcessOptions::default())
+        .expect("Failed to process reference files");
+    assert_eq!(model.metadata.thickness, 1.6);
+    assert_eq!(model.metadata.project.as_deref(), Some("reference-pcb"));
+
+    // The explicit thickness wins
+    let options = ProcessOptions {
+        thickness: Some(0.8),
+        ..ProcessOptions::default()
+    };
+    let model = process_gerber_files_with_options("gerbers", &options).unwrap();
+    assert_eq!(model.metadata.thickness, 0.8);
+    let z_max = model.meshes[0]
+        .vertices
+        .iter()
+        .map(|v| v.position.z)
+        .fold(f64::MIN, f64::max);
+    assert!((z_max - 0.8).abs() < 1e-9);
+}
+
+#[test]
+fn test_stackup_z_placement() {
+    use pcbgen::gerber::job::StackupEntry;
+    use pcbgen::intermediate::model::Side;
+    use pcbgen::intermediate::stackup::{Stackup, StackupLayerKind};
+
+    // Default 4-layer board: three equal dielectrics inside the 1.6 mm core
+    let stackup = Stackup::with_copper_layers(4, 1.6);
+    assert_eq!(stackup.copper_count(), 4);
+    let top = stackup.copper(Some(Side::Top), None).unwrap();
+    assert_eq!(top.copper_index, Some(1));
+    assert!((top.z_bottom - 1.6).abs() < 1e-9);
+    let bottom = stackup.copper(Some(Side::Bottom), Some(4)).unwrap();
+    assert!(bottom.z_top.abs() < 1e-9);
+
+    let l2 = stackup.copper(Some(Side::Inner), Some(2)).unwrap();
+    let l3 = stackup.copper(Some(Side::Inner), Some(3)).unwrap();
+    assert_eq!((l2.side, l3.side), (Side::Inner, Side::Inner));
+    let dielectric = (1.6 - 2.0 * pcbgen::COPPER_THICKNESS) / 3.0;
+    assert!((l2.z_top - (1.6 - dielectric)).abs() < 1e-9);
+    assert!((l3.z_bottom - dielectric).abs() < 1e-9);
+
+    // Mask and silkscreen are stacked outside the outer copper
+    let mask = stackup.outer(StackupLayerKind::Soldermask, Side::Top).unwrap();
+    let silk = stackup.outer(StackupLayerKind::Silkscreen, Side::Bottom).unwrap();
+    assert!((mask.z_bottom - top.z_top).abs() < 1e-9);
+    assert!(silk.z_top < bottom.z_bottom);
+
+    // Explicit dielectric thicknesses are scaled to the board thickness
+    let dielectric = |name: &str, thickness: f64| StackupEntry {
+        thickness: Some(thickness),
+        ..StackupEntry::new("Dielectric", name)
+    };
+    let entries = vec![
+        StackupEntry::new("Copper", "F.Cu"),
+        dielectric("Core", 1.0),
+        StackupEntry::new("Copper", "In1.Cu"),
+        dielectric("Prepreg", 0.5),
+        StackupEntry::new("Copper", "B.Cu"),
+    ];
+    let stackup = Stackup::from_entries(&entries, 0.8 + pcbgen::COPPER_THICKNESS);
+    let inner = stackup.copper(None, Some(2)).unwrap();
+    assert_eq!(inner.name, "In1.Cu");
+    assert!((inner.z_bottom - 0.8 / 3.0).abs() < 1e-9);
+    assert!((stackup.copper(None, Some(1)).unwrap().z_bottom - 0.835).abs() < 1e-9);
+}
+
+#[test]
+fn test_soldermask_openings() {
+    use pcbgen::excellon::types::{Hole, Plating};
+    use pcbgen::gerber::types::Point;
+    use pcbgen::intermediate::model::{LayerType, Side};
+    use pcbgen::{board_outline, build_soldermask_mesh};
+
+    // 20 x 10 mm board
+    let edge = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let (outline, _) = board_outline(&parse::parse_gerber(edge).unwrap(), ARC_TOLERANCE).unwrap();
+    assert_eq!((outline.len(), outline[0].outer.len()), (1, 4));
+
+    // A 2 x 2 mm pad opening, one overlapping it and one crossing the edge
+    let mask = "%FSLAX46Y46*%\n%MOMM*%\n%ADD11R,2X2*%\nD11*\nX5000000Y5000000D03*\nX5500000Y5000000D03*\nX19900000Y5000000D03*\nM02*\n";
+    let commands = parse::parse_gerber(mask).unwrap();
+
+    let hole = |x: f64| Hole {
+        tool: 1,
+        position: Point { x, y: 5.0 },
+        end: None,
+        diameter: 1.0,
+        plating: Plating::NonPlated,
+    };
+    // The first hole is exposed by the pad opening, the second is tented
+    let holes = vec![hole(5.0), hole(15.0)];
+
+    let mesh = build_soldermask_mesh(&outline, &commands, &holes, Side::Top, 1.635, 1.645, ARC_TOLERANCE)
+        .unwrap();
+    assert_eq!(mesh.layer_type, LayerType::Soldermask);
+    // The overlapping openings merge into one rectangle around the first
+    // hole, and the opening crossing the edge notches the outline: 2 caps,
+    // 8 outline walls, 4 opening walls and 24 hole walls
+    let cap = 8 + (4 + 2) + (24 + 2);
+    assert_eq!(mesh.faces.len(), 2 * (cap - 2) + 2 * (8 + 4 + 24));
+    assert!(mesh
+        .vertices
+        .iter()
+        .all(|v| v.position.z == 1.635 || v.position.z == 1.645));
+}
+
+#[test]
+fn test_paste_stencil() {
+    use pcbgen::geometry::polygon::{bridge_holes, signed_area};
+    use pcbgen::geometry::shapes::regular_polygon;
+    use pcbgen::geometry::triangulate::triangulate;
+    use pcbgen::gerber::types::Point;
+    use pcbgen::{StencilOptions, board_outline, build_stencil_mesh};
+
+    // 20 x 10 mm board with two 1 x 2 mm paste apertures
+    let edge = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10C,0.1*%\nD10*\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let (outline, _) = board_outline(&parse::parse_gerber(edge).unwrap(), ARC_TOLERANCE).unwrap();
+    let paste = "%FSLAX46Y46*%\n%MOMM*%\n%ADD11R,1X2*%\nD11*\nX5000000Y5000000D03*\nX8000000Y5000000D03*\nM02*\n";
+    let commands = parse::parse_gerber(paste).unwrap();
+
+    let options = StencilOptions {
+        margin: 5.0,
+        frame_width: Some(1.0),
+        registration_hole: Some(2.0),
+        ..StencilOptions::default()
+    };
+    let mesh = build_stencil_mesh(&outline, &commands, &options).unwrap();
+    assert!(mesh.faces.iter().all(|face| face.vertices.len() == 3));
+    // Plate walls: 4 outer, 2 apertures and 4 registration holes; frame:
+    // 4 outer and 4 inner walls
+    let walls = mesh.faces.iter().filter(|face| {
+        let z: Vec<f64> = face.vertices.iter().map(|&i| mesh.vertices[i].position.z).collect();
+        z.iter().any(|&other| other != z[0])
+    });
+    assert_eq!(walls.count(), 2 * (4 + 2 * 4 + 4 * 32 + 4 + 4));
+
+    // The top of the plate covers the 30 x 20 mm plate minus the apertures
+    // and the registration holes (up to the 1 nm grid of the cutouts)
+    let circle = signed_area(&regular_polygon(&Point { x: 0.0, y: 0.0 }, 1.0, 32, 0.0));
+    let plate_top: f64 = mesh
+        .faces
+        .iter()
+        .map(|face| {
+            let points: Vec<Point> = face
+                .vertices
+                .iter()
+                .map(|&i| mesh.vertices[i].position.clone())
+                .filter(|p| (p.z - 0.12).abs() < 1e-9)
+                .map(|p| Point { x: p.x, y: p.y })
+                .collect();
+            if points.len() == 3 { signed_area(&points).max(0.0) } else { 0.0 }
+        })
+        .sum();
+    assert!((plate_top - (600.0 - 2.0 * 2.0 - 4.0 * circle)).abs() < 1e-5);
+    let z_max = mesh
+        .vertices
+        .iter()
+        .map(|v| v.position.z)
+        .fold(f64::MIN, f64::max);
+    assert!((z_max - 1.12).abs() < 1e-9);
+
+    // The holes must fit between the frame and the board
+    let options = StencilOptions {
+        margin: 2.0,
+        frame_width: Some(1.0),
+        registration_hole: Some(1.5),
+        ..StencilOptions::default()
+    };
+    assert!(build_stencil_mesh(&outline, &commands, &options).is_err());
+
+    // Ear clipping covers a bridged cap exactly, keeping its winding
+    let holes: Vec<Vec<Point>> = [4.0, 10.0]
+        .iter()
+        .map(|x| regular_polygon(&Point { x: *x, y: 5.0 }, 1.0, 16, 0.0))
+        .collect();
+    let cap = bridge_holes(&outline[0].outer, &holes);
+    let triangles = triangulate(&cap);
+    let areas: Vec<f64> = triangles
+        .iter()
+        .map(|t| signed_area(&[cap[t[0]].clone(), cap[t[1]].clone(), cap[t[2]].clone()]))
+        .collect();
+    assert!(areas.iter().all(|area| *area > 0.0));
+    assert!((areas.iter().sum::<f64>() - signed_area(&cap)).abs() < 1e-9);
+}
+
+#[test]
+fn test_polygon_booleans() {
+    use pcbgen::geometry::boolean::{difference, intersection, union, union_all, xor};
+    use pcbgen::geometry::offset::offset;
+    use pcbgen::geometry::polygon::{Polygon, total_area};
+    use pcbgen::gerber::types::Point;
+
+    let square = |x: f64, y: f64, size: f64| {
+        Polygon::new(vec![
+            Point { x, y },
+            Point { x: x + size, y },
+            Point { x: x + size, y: y + size },
+            Point { x, y: y + size },
+        ])
+    };
+
+    // Overlapping squares
+    let (a, b) = ([square(0.0, 0.0, 2.0)], [square(1.0, 1.0, 2.0)]);
+    let merged = union(&a, &b).unwrap();
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].outer.len(), 8);
+    assert!((total_area(&merged) - 7.0).abs() < 1e-9);
+    assert!((total_area(&difference(&a, &b).unwrap()) - 3.0).abs() < 1e-9);
+    assert!((total_area(&intersection(&a, &b).unwrap()) - 1.0).abs() < 1e-9);
+
+    // Squares sharing an edge merge into one rectangle without a seam
+    let side_by_side = union(&[square(0.0, 0.0, 1.0)], &[square(1.0, 0.0, 1.0)]).unwrap();
+    assert_eq!(side_by_side.len(), 1);
+    assert_eq!(side_by_side[0].outer.len(), 4);
+
+    // Squares touching at a corner stay separate polygons
+    let corners = union_all(&[square(0.0, 0.0, 1.0), square(1.0, 1.0, 1.0)]).unwrap();
+    assert_eq!(corners.len(), 2);
+
+    // Vertices that are not finite fail the operation instead of snapping
+    assert!(union(&a, &[square(f64::NAN, 0.0, 1.0)]).is_err());
+    assert!(difference(&a, &[square(f64::INFINITY, 0.0, 1.0)]).is_err());
+
+    // A square cut from the middle leaves a hole; cut along an edge, a notch
+    let framed = difference(&[square(0.0, 0.0, 4.0)], &[square(1.0, 1.0, 2.0)]).unwrap();
+    assert_eq!((framed.len(), framed[0].holes.len()), (1, 1));
+    assert!((framed[0].area() - 12.0).abs() < 1e-9);
+    assert!(!framed[0].contains(&Point { x: 2.0, y: 2.0 }));
+    let notched = difference(&[square(0.0, 0.0, 4.0)], &[square(0.0, 1.0, 2.0)]).unwrap();
+    assert_eq!((notched[0].outer.len(), notched[0].holes.len()), (8, 0));
+    assert!(intersection(&[square(0.0, 0.0, 1.0)], &[square(1.0, 0.0, 1.0)]).unwrap().is_empty());
+
+    // Growing a square rounds its corners; shrinking it keeps it square
+    let grown = total_area(&offset(&[square(0.0, 0.0, 2.0)], 0.5).unwrap());
+    assert!((grown - (4.0 + 4.0 + std::f64::consts::PI * 0.25)).abs() < 0.01);
+    let shrunk = offset(&[square(0.0, 0.0, 2.0)], -0.5).unwrap();
+    assert!((total_area(&shrunk) - 1.0).abs() < 1e-6);
+
+    // Bowties whose edges all pass near the origin: snapping the first
+    // crossings to the grid bends edges across others, which takes more
+    // rounds of splitting before the areas add up
+    let bowtie = |points: [(f64, f64); 4]| {
+        [Polygon::new(points.iter().map(|&(x, y)| Point { x, y }).collect())]
+    };
+    let a = bowtie([
+        (-0.099293, 0.011866),
+        (0.099352, -0.011368),
+        (0.03359, 0.09419),
+        (-0.034366, -0.093909),
+    ]);
+    let b = bowtie([
+        (-0.063299, 0.077416),
+        (0.063249, -0.077457),
+        (0.095935, 0.028223),
+        (-0.09613, -0.02755),
+    ]);
+    let area_a = total_area(&union_all(&a).unwrap());
+    let area_b = total_area(&union_all(&b).unwrap());
+    let merged = total_area(&union(&a, &b).unwrap());
+    let common = total_area(&intersection(&a, &b).unwrap());
+    assert!(common > 0.0);
+    assert!((merged + common - area_a - area_b).abs() < 1e-6);
+    assert!((total_area(&xor(&a, &b).unwrap()) - (merged - common)).abs() < 1e-9);
+}
+
+#[test]
+fn test_cap_triangulation() {
+    use pcbgen::extrude_polygons;
+    use pcbgen::geometry::boolean::difference;
+    use pcbgen::geometry::polygon::{Polygon, signed_area};
+    use pcbgen::geometry::shapes::regular_polygon;
+    use pcbgen::geometry::triangulate::triangulate;
+    use pcbgen::gerber::types::Point;
+    use pcbgen::intermediate::model::{LayerType, Mesh};
+    use std::collections::HashMap;
+
+    // A plate with rows of holes whose bridges run through the holes beside
+    // them, and slots reaching in from the edge whose top edges line up
+    // with the bridges of the last holes
+    let plate = Polygon::new(vec![
+        Point { x: 0.0, y: 0.0 },
+        Point { x: 40.0, y: 0.0 },
+        Point { x: 40.0, y: 40.0 },
+        Point { x: 0.0, y: 40.0 },
+    ]);
+    let mut cuts = Vec::new();
+    for i in 0..12 {
+        for j in 0..12 {
+            let center = Point { x: 2.0 + i as f64 * 3.2, y: 2.5 + j as f64 * 3.2 };
+            cuts.push(Polygon::new(regular_polygon(&center, 1.0 + (i % 3) as f64 * 0.2, 16, 0.0)));
+        }
+    }
+    for j in 0..6 {
+        let y = 2.5 + j as f64 * 6.4;
+        cuts.push(Polygon::new(vec![
+            Point { x: 38.8, y: y - 0.2 },
+            Point { x: 41.0, y: y - 0.2 },
+            Point { x: 41.0, y },
+            Point { x: 38.8, y },
+        ]));
+    }
+    let polygons = difference(&[plate], &cuts).unwrap();
+    assert_eq!(polygons.len(), 1);
+    assert_eq!(polygons[0].holes.len(), 144);
+
+    // The triangles cover the bridged outline exactly, without overlaps
+    let outline = polygons[0].to_outline();
+    let triangles = triangulate(&outline);
+    assert_eq!(triangles.len(), outline.len() - 2);
+    let areas: Vec<f64> = triangles
+        .iter()
+        .map(|t| signed_area(&t.map(|i| outline[i].clone())))
+        .collect();
+    assert!(areas.iter().all(|area| *area > 0.0));
+    assert!((areas.iter().sum::<f64>() - signed_area(&outline)).abs() < 1e-9);
+
+    // Extruded, every edge is shared by exactly two faces running it in
+    // opposite directions
+    let mut mesh = Mesh {
+        vertices: Vec::new(),
+        faces: Vec::new(),
+        layer_type: LayerType::Copper,
+        side: None,
+    };
+    extrude_polygons(&mut mesh, &polygons, 0.0, 0.035);
+    let key = |p: &pcbgen::intermediate::model::Point3D| {
+        ((p.x * 1e6).round() as i64, (p.y * 1e6).round() as i64, (p.z * 1e6).round() as i64)
+    };
+    let mut edges: HashMap<_, i32> = HashMap::new();
+    for face in &mesh.faces {
+        for i in 0..3 {
+            let a = key(&mesh.vertices[face.vertices[i]].position);
+            let b = key(&mesh.vertices[face.vertices[(i + 1) % 3]].position);
+            *edges.entry((a, b)).or_default() += 1;
+        }
+    }
+    assert!(edges.iter().all(|(&(a, b), &count)| count == 1 && edges.get(&(b, a)) == Some(&1)));
+}
+
+#[test]
+fn test_stl_export() {
+    use pcbgen::intermediate::model::{BoardMetadata, PCBModel, Side, Units};
+    use pcbgen::usdz::export::{StlOptions, export_to_stl, layer_name};
+    use pcbgen::{build_board_mesh, build_copper_mesh};
+
+    let board = "%FSLAX46Y46*%\n%MOMM*%\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let copper = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10R,2X1*%\nD10*\nX5000000Y5000000D03*\nX15000000Y5000000D03*\nM02*\n";
+    let model = PCBModel {
+        meshes: vec![
+            build_board_mesh(&parse::parse_gerber(board).unwrap(), &[], Some(1.6)).unwrap(),
+            build_copper_mesh(&parse::parse_gerber(copper).unwrap(), true, Some(1.6)).unwrap(),
+            build_copper_mesh(&parse::parse_gerber(copper).unwrap(), false, Some(1.6)).unwrap(),
+        ],
+        units: Units::Millimeters,
+        metadata: BoardMetadata {
+            thickness: 1.6,
+            ..BoardMetadata::default()
+        },
+    };
+    // A box is 12 triangles: 2 on each cap and 2 per wall
+    let triangles = 12 * (1 + 2 + 2);
+
+    let dir = std::env::temp_dir().join(format!("pcbgen_stl_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("board.stl").to_string_lossy().to_string();
+
+    // Binary: 80 byte header, triangle count, 50 bytes per triangle
+    let written = export_to_stl(&model, &path, &StlOptions::default()).unwrap();
+    assert_eq!(written, vec![path.clone()]);
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(u32::from_le_bytes(bytes[80..84].try_into().unwrap()), triangles);
+    assert_eq!(bytes.len(), 84 + 50 * triangles as usize);
+    assert!(!bytes.starts_with(b"solid"));
+
+    let ascii = StlOptions {
+        ascii: true,
+        ..StlOptions::default()
+    };
+    export_to_stl(&model, &path, &ascii).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(text.starts_with("solid pcb\n") && text.trim_end().ends_with("endsolid pcb"));
+    assert_eq!(text.matches("facet normal").count(), triangles as usize);
+    assert_eq!(text.matches("vertex").count(), 3 * triangles as usize);
+
+    // One file per layer, named after the layer
+    let per_layer = StlOptions {
+        per_layer: true,
+        ..StlOptions::default()
+    };
+    let written = export_to_stl(&model, &path, &per_layer).unwrap();
+    let names: Vec<String> = written
+        .iter()
+        .map(|p| std::path::Path::new(p).file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, ["board_EdgeCuts.stl", "board_TopCopper.stl", "board_BottomCopper.stl"]);
+    // The name comes from the side the mesh was built for, not its height
+    let mut inner = model.meshes[1].clone();
+    inner.side = Some(Side::Inner);
+    assert_eq!(layer_name(&inner), "InnerCopper");
+    let bytes = std::fs::read(&written[1]).unwrap();
+    assert_eq!(bytes.len(), 84 + 50 * 24);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_usdz_export() {
+    use pcbgen::build_board_mesh;
+    use pcbgen::intermediate::model::{BoardMetadata, PCBModel, Units};
+    use pcbgen::usdz::export::{export_to_usdz, usda_stage};
+    use pcbgen::usdz::zip::{crc32, stored_zip};
+
+    let u16_at = |bytes: &[u8], at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+    let u32_at = |bytes: &[u8], at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    // Every entry is stored, with its data on a 64-byte boundary
+    let entries: [(&str, &[u8]); 3] = [("a.usda", b"#usda 1.0\n"), ("textures/b.png", &[7; 100]), ("c", b"")];
+    let archive = stored_zip(&entries).unwrap();
+    let mut offset = 0;
+    for (name, data) in entries {
+        assert_eq!(u32_at(&archive, offset), 0x0403_4b50);
+        assert_eq!(u16_at(&archive, offset + 8), 0);
+        assert_eq!(u32_at(&archive, offset + 14), crc32(data));
+        let start = offset + 30 + u16_at(&archive, offset + 26) + u16_at(&archive, offset + 28);
+        assert_eq!(start % 64, 0);
+        assert_eq!(&archive[offset + 30..offset + 30 + name.len()], name.as_bytes());
+        assert_eq!(&archive[start..start + data.len()], data);
+        offset = start + data.len();
+    }
+    let end = archive.len() - 22;
+    assert_eq!(u32_at(&archive, end), 0x0605_4b50);
+    assert_eq!(u16_at(&archive, end + 10), 3);
+    assert_eq!(u32_at(&archive, end + 16) as usize, offset);
+
+    // The package holds the stage, with a mesh and a material per layer
+    let board = "%FSLAX46Y46*%\n%MOMM*%\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let mut mesh = build_board_mesh(&parse::parse_gerber(board).unwrap(), &[], Some(1.6)).unwrap();
+    mesh.post_process();
+    let model = PCBModel {
+        meshes: vec![mesh],
+        units: Units::Millimeters,
+        metadata: BoardMetadata {
+            thickness: 1.6,
+            ..BoardMetadata::default()
+        },
+    };
+    let stage = usda_stage(&model);
+    assert!(stage.starts_with("#usda 1.0\n"));
+    assert!(stage.contains("def Mesh \"EdgeCuts\""));
+    assert!(stage.contains("rel material:binding = </Board/Materials/FR4>"));
+    assert!(stage.contains("uniform token info:id = \"UsdPreviewSurface\""));
+    assert!(stage.contains(&format!("int[] faceVertexCounts = [{}]", ["3"; 12].join(", "))));
+
+    let path = std::env::temp_dir().join(format!("pcbgen_usdz_{}.usdz", std::process::id()));
+    export_to_usdz(&model, path.to_str().unwrap()).unwrap();
+    let package = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let name_length = u16_at(&package, 26);
+    assert!(package[30..30 + name_length].ends_with(b".usda"));
+    let start = 30 + name_length + u16_at(&package, 28);
+    assert_eq!(start % 64, 0);
+    assert_eq!(&package[start..start + stage.len()], stage.as_bytes());
+}
+
+#[test]
+fn test_gltf_export() {
+    use pcbgen::intermediate::model::{BoardMetadata, PCBModel, Units};
+    use pcbgen::usdz::gltf::{export_to_glb, export_to_gltf, gltf_document};
+    use pcbgen::{build_board_mesh, build_copper_mesh};
+
+    let board = "%FSLAX46Y46*%\n%MOMM*%\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let copper = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10R,2X1*%\nD10*\nX5000000Y5000000D03*\nM02*\n";
+    let mut model = PCBModel {
+        meshes: vec![
+            build_board_mesh(&parse::parse_gerber(board).unwrap(), &[], Some(1.6)).unwrap(),
+            build_copper_mesh(&parse::parse_gerber(copper).unwrap(), true, Some(1.6)).unwrap(),
+        ],
+        units: Units::Millimeters,
+        metadata: BoardMetadata {
+            thickness: 1.6,
+            ..BoardMetadata::default()
+        },
+    };
+    for mesh in &mut model.meshes {
+        mesh.post_process();
+    }
+
+    // A node, mesh and material per layer; positions, normals and indices
+    // for each mesh
+    let (json, buffer) = gltf_document(&model, Some("board.bin"));
+    assert!(json.starts_with(r#"{"asset":{"version":"2.0""#));
+    assert!(json.contains(r#"{"name":"EdgeCuts","mesh":0}"#));
+    assert!(json.contains(r#"{"name":"TopCopper","mesh":1}"#));
+    assert!(json.contains(r#""name":"Copper","pbrMetallicRoughness":{"baseColorFactor":[0.72,0.45,0.2,1],"metallicFactor":1"#));
+    assert!(json.contains(r#""name":"FR4""#));
+    assert!(json.contains(&format!(r#""buffers":[{{"byteLength":{},"uri":"board.bin"}}]"#, buffer.len())));
+    assert_eq!(json.matches(r#""bufferView""#).count(), 6);
+    let vertices: usize = model.meshes.iter().map(|mesh| mesh.vertices.len()).sum();
+    assert_eq!(buffer.len(), vertices * 2 * 12 + 2 * 12 * 3 * 4);
+
+    let dir = std::env::temp_dir().join(format!("pcbgen_gltf_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // .gltf with the geometry in a .bin next to it
+    let gltf = dir.join("my board.gltf");
+    export_to_gltf(&model, gltf.to_str().unwrap()).unwrap();
+    assert!(std::fs::read_to_string(&gltf).unwrap().contains(r#""uri":"my%20board.bin""#));
+    assert_eq!(std::fs::read(dir.join("my board.bin")).unwrap(), buffer);
+
+    // GLB: header, then a JSON and a binary chunk, each 4-byte aligned
+    let glb = dir.join("board.glb");
+    export_to_glb(&model, glb.to_str().unwrap()).unwrap();
+    let bytes = std::fs::read(&glb).unwrap();
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+    assert_eq!(&bytes[0..4], b"glTF");
+    assert_eq!((u32_at(4), u32_at(8)), (2, bytes.len()));
+    let json_length = u32_at(12);
+    assert_eq!((&bytes[16..20], json_length % 4), (&b"JSON"[..], 0));
+    let json = std::str::from_utf8(&bytes[20..20 + json_length]).unwrap();
+    assert!(json.contains(&format!(r#""buffers":[{{"byteLength":{}}}]"#, buffer.len())));
+    let bin = 20 + json_length;
+    assert_eq!(&bytes[bin + 4..bin + 8], b"BIN\0");
+    assert_eq!(&bytes[bin + 8..bin + 8 + buffer.len()], &buffer[..]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // Without meshes the root node has no children list, which glTF forbids
+    // to be empty
+    model.meshes.clear();
+    let (json, buffer) = gltf_document(&model, None);
+    assert!(!json.contains(r#""children""#));
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn test_3mf_export() {
+    use pcbgen::excellon::types::{Hole, Plating};
+    use pcbgen::gerber::types::Point;
+    use pcbgen::intermediate::model::{BoardMetadata, PCBModel, Units};
+    use pcbgen::intermediate::stackup::Stackup;
+    use pcbgen::usdz::threemf::{export_to_3mf, model_xml};
+    use pcbgen::{build_board_mesh, build_copper_mesh, build_plated_barrels_mesh};
+
+    let board = "%FSLAX46Y46*%\n%MOMM*%\nX0Y0D02*\nX20000000Y0D01*\nX20000000Y10000000D01*\nX0Y10000000D01*\nX0Y0D01*\nM02*\n";
+    let copper = "%FSLAX46Y46*%\n%MOMM*%\n%ADD10R,2X1*%\nD10*\nX5000000Y5000000D03*\nM02*\n";
+    // Two overlapping plated holes, whose barrels merge into one solid
+    let holes: Vec<Hole> = [12.0, 12.8]
+        .iter()
+        .map(|&x| Hole {
+            tool: 1,
+            position: Point { x, y: 5.0 },
+            end: None,
+            diameter: 1.0,
+            plating: Plating::Plated,
+        })
+        .collect();
+    let mut model = PCBModel {
+        meshes: vec![
+            build_board_mesh(&parse::parse_gerber(board).unwrap(), &holes, Some(1.6)).unwrap(),
+            build_copper_mesh(&parse::parse_gerber(copper).unwrap(), true, Some(1.6)).unwrap(),
+            build_plated_barrels_mesh(&holes, &Stackup::with_copper_layers(2, 1.6), ARC_TOLERANCE)
+                .unwrap(),
+        ],
+        units: Units::Millimeters,
+        metadata: BoardMetadata {
+            thickness: 1.6,
+            project: Some("A&B".to_string()),
+            ..BoardMetadata::default()
+        },
+    };
+    for mesh in &mut model.meshes {
+        mesh.post_process();
+    }
+
+    // One object per layer in a single build, sharing one vertex per
+    // position; the two copper layers share a base material
+    let xml = model_xml(&model).unwrap();
+    assert!(xml.contains(r#"<model unit="millimeter""#));
+    assert!(xml.contains(r#"<metadata name="Title">A&amp;B</metadata>"#));
+    assert_eq!(xml.matches("<base ").count(), 2);
+    assert!(xml.contains(r##"<base name="FR4" displaycolor="#CEC8A6FF"/>"##));
+    assert!(xml.contains(r#"<object id="3" name="TopCopper" type="model" pid="1" pindex="1">"#));
+    assert!(xml.contains(r#"<object id="4" name="PlatedBarrels" type="model" pid="1" pindex="1">"#));
+    assert_eq!(xml.matches("<item objectid=").count(), 3);
+    let copper_object = &xml[xml.find(r#"name="TopCopper""#).unwrap()..xml.find(r#"name="PlatedBarrels""#).unwrap()];
+    assert_eq!(copper_object.matches("<vertex ").count(), 8);
+    assert_eq!(copper_object.matches("<triangle ").count(), 12);
+
+    let path = std::env::temp_dir().join(format!("pcbgen_3mf_{}.3mf", std::process::id()));
+    export_to_3mf(&model, path.to_str().unwrap()).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(&bytes[0..4], b"PK\x03\x04");
+    let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+    assert!(contains(b"[Content_Types].xml"));
+    assert!(contains(br#"Target="/3D/3dmodel.model""#));
+    assert!(contains(xml.as_bytes()));
+
+    // An open mesh is rejected
+    model.meshes[1].faces.pop();
+    let error = model_xml(&model).unwrap_err();
+    assert!(error.starts_with("TopCopper is not a manifold mesh"), "{}", error);
+}
+
+#[test]
+fn test_step_export() {
+    use pcbgen::geometry::boolean::difference;
+    use pcbgen::geometry::polygon::Polygon;
+    use pcbgen::geometry::shapes::{CIRCLE_SEGMENTS, stadium};
+    use pcbgen::gerber::types::Point;
+    use pcbgen::intermediate::model::BoardMetadata;
+    use pcbgen::intermediate::profile::{Circle, Prism, ProfileEdge, SolidModel, profiles};
+    use pcbgen::usdz::step::{export_to_step, step_document};
+    use pcbgen::{ProcessOptions, process_board_solids};
+
+    // The reference board: a rectangle with four rounded corners
+    let model = process_board_solids("gerbers", &ProcessOptions::default(), true).unwrap();
+    assert_eq!(model.prisms.len(), 1);
+    let board = &model.prisms[0];
+    assert_eq!((board.name.as_str(), board.z_bottom, board.z_top), ("Board", 0.0, 1.6));
+    let arcs = board.profile.outer.iter().filter(|edge| matches!(edge, ProfileEdge::Arc { .. }));
+    assert_eq!((board.profile.outer.len(), arcs.count()), (8, 4));
+
+    // A 1.00 mm plated hole is cut at its finished size, and widened for
+    // the barrel only when the barrels are exported
+    let dir = std::env::temp_dir().join(format!("pcbgen_solids_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("gerbers/reference-pcb-Edge_Cuts.gbr", dir.join("board-Edge_Cuts.gbr")).unwrap();
+    let drill = "M48\nMETRIC\nT1C1.000\n%\nT1\nX150.0Y-90.0\nM30\n";
+    std::fs::write(dir.join("board-PTH.drl"), drill).unwrap();
+    let hole_radius = |copper: bool| {
+        let options = ProcessOptions::default();
+        let model = process_board_solids(dir.to_str().unwrap(), &options, copper).unwrap();
+        match &model.prisms[0].profile.holes[..] {
+            [hole] => match &hole[..] {
+                [ProfileEdge::Arc { radius, .. }] => *radius,
+                edges => panic!("expected a full circle, got {:?}", edges),
+            },
+            holes => panic!("expected one hole, got {}", holes.len()),
+        }
+    };
+    assert_eq!(hole_radius(false), 0.5);
+    assert_eq!(hole_radius(true), 0.5 + pcbgen::PLATING_THICKNESS);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // A 20 x 10 mm board with a 3.2 mm hole: the chords of the hole are
+    // restored to a full circle, running clockwise
+    let center = Point { x: 5.0, y: 5.0 };
+    let outline = Polygon::new(vec![
+        Point { x: 0.0, y: 0.0 },
+        Point { x: 20.0, y: 0.0 },
+        Point { x: 20.0, y: 10.0 },
+        Point { x: 0.0, y: 10.0 },
+    ]);
+    let hole = Polygon::new(stadium(&center, &center, 1.6, CIRCLE_SEGMENTS));
+    let circles = [Circle {
+        center: center.clone(),
+        radius: 1.6,
+        max_step: 2.0 * std::f64::consts::PI / CIRCLE_SEGMENTS as f64,
+    }];
+    let profile = profiles(&difference(&[outline], &[hole]).unwrap(), &circles).remove(0);
+    assert_eq!(profile.outer.len(), 4);
+    assert_eq!(profile.holes.len(), 1);
+    match &profile.holes[0][..] {
+        [ProfileEdge::Arc { start, end, radius, clockwise, .. }] => {
+            assert_eq!((start.x, start.y), (end.x, end.y));
+            assert_eq!((*radius, *clockwise), (1.6, true));
+        }
+        edges => panic!("expected a full circle, got {:?}", edges),
+    }
+
+    // Four planar walls, a cylindrical hole wall and the two caps, every
+    // edge shared by two faces in opposite directions
+    let model = SolidModel {
+        prisms: vec![Prism {
+            name: "Board".to_string(),
+            profile,
+            z_bottom: 0.0,
+            z_top: 1.6,
+        }],
+        metadata: BoardMetadata {
+            project: Some("Rev 'A'".to_string()),
+            ..BoardMetadata::default()
+        },
+    };
+    let step = step_document(&model, "board.step");
+    assert!(step.starts_with("ISO-10303-21;\nHEADER;\n"));
+    assert!(step.contains("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));"));
+    assert!(step.contains("PRODUCT('Rev ''A''','Rev ''A''','',(#3))"));
+    assert!(step.contains("SI_UNIT(.MILLI.,.METRE.)"));
+    assert!(step.trim_end().ends_with("END-ISO-10303-21;"));
+    let count = |entity: &str| step.matches(entity).count();
+    assert_eq!(count("=ADVANCED_FACE("), 7);
+    assert_eq!(count("=PLANE("), 6);
+    assert_eq!(count("=CYLINDRICAL_SURFACE("), 1);
+    assert_eq!(count("=CIRCLE("), 2);
+    assert_eq!(count("=MANIFOLD_SOLID_BREP('Board'"), 1);
+    assert_eq!(count("=COLOUR_RGB('FR4'"), 1);
+    let edges: Vec<&str> = step
+        .lines()
+        .filter(|line| line.contains("=EDGE_CURVE("))
+        .map(|line| line.split('=').next().unwrap())
+        .collect();
+    assert_eq!(edges.len(), 4 * 3 + 2);
+    for edge in edges {
+        let used = |sense: &str| count(&format!("ORIENTED_EDGE('',*,*,{},{})", edge, sense));
+        assert_eq!((used(".T."), used(".F.")), (1, 1), "edge {}", edge);
+    }
+
+    let path = std::env::temp_dir().join(format!("pcbgen_step_{}.step", std::process::id()));
+    export_to_step(&model, path.to_str().unwrap()).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(written.contains(&format!("FILE_NAME('{}'", path.file_name().unwrap().to_str().unwrap())));
+}